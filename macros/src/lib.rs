@@ -0,0 +1,105 @@
+//! The `patterns!` macro, re-exported by `regex-ring` with the `macros` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use regex_automata::{RegexBuilder, DFA};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, ExprLit, Fields, Lit, LitStr};
+
+/// An enum of rules with their patterns, and a searcher for them.
+///
+/// ```text
+/// patterns! {
+///     pub enum Rule {
+///         Get = "GET /[a-z]+",
+///         Post = "POST",
+///     }
+/// }
+///
+/// let mut searcher = Rule::searcher(4096).unwrap();
+/// for (id, match_) in searcher.matches() {
+///     match Rule::from_id(id).unwrap() {
+///         Rule::Get => ...,
+///         Rule::Post => ...,
+///     }
+/// }
+/// ```
+///
+/// The patterns are checked at compile time, the searcher compiles them when created.
+/// The enum derives `Copy`, `Clone`, `Debug`, `PartialEq`, `Eq` and `Hash`.
+#[proc_macro]
+pub fn patterns(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let variants = match input.data {
+        Data::Enum(ref mut data) => &mut data.variants,
+        _ => return Err(Error::new(Span::call_site(), "expected an enum")),
+    };
+    let mut names = vec![];
+    let mut patterns = vec![];
+    for variant in variants.iter_mut() {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(&variant.fields, "rules can not have fields"));
+        }
+        let pattern = match variant.discriminant.take() {
+            Some((_, Expr::Lit(ExprLit { lit: Lit::Str(pattern), .. }))) => pattern,
+            _ => return Err(Error::new_spanned(&variant.ident, "expected `Name = \"pattern\"`")),
+        };
+        check(&pattern)?;
+        names.push(variant.ident.clone());
+        patterns.push(pattern);
+    }
+
+    let name = &input.ident;
+    let ids = 0 .. names.len();
+    Ok(quote! {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        #input
+
+        impl #name {
+            /// All rules, in the order of their search identifiers.
+            pub const ALL: &'static [#name] = &[#(#name::#names),*];
+
+            /// The rule of search identifier `id`.
+            pub fn from_id(id: usize) -> ::core::option::Option<#name> {
+                Self::ALL.get(id).copied()
+            }
+
+            /// The search identifier of the rule.
+            pub fn id(self) -> usize {
+                match self {
+                    #(#name::#names => #ids,)*
+                }
+            }
+
+            pub fn pattern(self) -> &'static str {
+                match self {
+                    #(#name::#names => #patterns,)*
+                }
+            }
+
+            /// A searcher with the given ringbuffer size, searching all rules.
+            pub fn searcher(buffer_size: usize) -> ::core::result::Result<::regex_ring::__private::DenseSearcher, ::regex_ring::Error> {
+                let mut searcher = ::regex_ring::RingSearcher::new(buffer_size);
+                for rule in Self::ALL {
+                    searcher.add_regex_str(rule.pattern())?;
+                }
+                ::core::result::Result::Ok(searcher)
+            }
+        }
+    })
+}
+
+/// Reject the patterns `add_regex_str` would reject.
+fn check(pattern: &LitStr) -> syn::Result<()> {
+    let regex = RegexBuilder::new().build(&pattern.value())
+        .map_err(|e| Error::new_spanned(pattern, format!("invalid pattern: {}", e)))?;
+    let dfa = regex.forward();
+    if dfa.is_match_state(dfa.start_state()) {
+        return Err(Error::new_spanned(pattern, "the pattern matches the empty string"));
+    }
+    Ok(())
+}
@@ -1,20 +1,687 @@
-use std::{env, io, str};
-use std::io::Read;
+use std::{env, io, thread};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
-use regex_ring::{RingSearcher};
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, Match, MatchData, CHUNK_PIECE};
+use regex_ring::input::{InputSource, ReaderSource};
+
+type Searcher = RingSearcher<DenseDFA<Vec<usize>, usize>>;
+
+/// most UDP peers or TCP connections scanned at once
+const MAX_PEERS: usize = 1024;
+/// time after which a silent UDP peer's stream ends
+const PEER_IDLE: Duration = Duration::from_secs(60);
+
+#[derive(Copy, Clone)]
+enum Proto {
+    Tcp,
+    Udp,
+}
+
+/// where the input comes from
+enum Input {
+    Stdin,
+    /// `--listen tcp:PORT` or `--listen udp:PORT`
+    Listen(Proto, String),
+    /// `--connect [tcp:|udp:]host:port`
+    Connect(Proto, String),
+    /// `--exec "cmd args"`
+    Exec(String),
+    /// files given after patterns passed with `-e`, or `-r` without files
+    Files(Vec<String>),
+}
+
+/// patterns and options shared by all scanned streams
+#[derive(Clone, Default)]
+struct Config {
+    patterns: Vec<String>,
+    crlf: bool,
+    /// NUL-delimited records
+    null_data: bool,
+    /// print only the match data
+    only_matching: bool,
+    /// print the stream position of each match with `only_matching`
+    byte_offset: bool,
+    /// stop after this many matches per pattern and stream
+    max_count: Option<usize>,
+    /// prefix matches with the file name, by default if there are several files
+    with_filename: Option<bool>,
+    /// scan directories recursively
+    recursive: bool,
+    /// follow all symbolic links while recursing, not only those given as arguments
+    follow_links: bool,
+    /// only scan files whose name matches one of these globs, if any
+    include: Vec<String>,
+    /// skip files whose name matches one of these globs
+    exclude: Vec<String>,
+    /// number of files scanned in parallel, by default the available parallelism
+    threads: Option<usize>,
+    /// print the matches of parallel scans as they are found, instead of in file order
+    unordered: bool,
+    template: Option<Searcher>,
+}
+
+impl Config {
+    /// compile the patterns once, every stream gets a clone
+    fn compile(&mut self) {
+        let mut searcher = RingSearcher::new(1024);
+        searcher.set_crlf(self.crlf);
+        if self.null_data {
+            searcher.set_record_delimiter(b'\0');
+            searcher.set_record_reset(true);
+        }
+        for regex_str in &self.patterns {
+            searcher.add_regex_str(regex_str).expect("invalid regex");
+        }
+        self.template = Some(searcher);
+    }
+
+    fn searcher(&self) -> Searcher {
+        self.template.clone().expect("patterns not compiled")
+    }
+
+    /// whether the file name of `path` passes the `--include` and `--exclude` filters
+    fn wants(&self, path: &Path) -> bool {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return true,
+        };
+        let matches = |globs: &[String]| globs.iter().any(|glob| glob_match(glob.as_bytes(), name.as_bytes()));
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+
+    fn stream(&self) -> Stream {
+        let searcher = self.searcher();
+        Stream {
+            counts: vec![0; searcher.num_searches()],
+            searcher,
+        }
+    }
+}
+
+/// the state of a scanned stream
+struct Stream {
+    searcher: Searcher,
+    /// number of matches reported per pattern
+    counts: Vec<usize>,
+}
+
+impl Stream {
+    /// Push `input` and report the matches to `out`.
+    /// Returns false once every pattern reached the maximum count.
+    ///
+    /// `input` is pushed in pieces no larger than the buffer or `CHUNK_PIECE`,
+    /// so the data of all matches is still in the buffer when they are reported.
+    fn push(&mut self, config: &Config, source: Option<&str>, input: &[u8], out: &mut dyn Write) -> bool {
+        let piece_len = self.searcher.capacity().min(CHUNK_PIECE);
+        for piece in input.chunks(piece_len) {
+            self.searcher.push_slice(piece);
+            let matches: Vec<_> = self.searcher.drain_matches().collect();
+            if !self.report(config, source, matches, out) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Report the final matches to `out`.
+    fn finish(&mut self, config: &Config, source: Option<&str>, out: &mut dyn Write) {
+        let matches: Vec<_> = self.searcher.final_matches().collect();
+        self.report(config, source, matches, out);
+    }
+
+    fn report(&mut self, config: &Config, source: Option<&str>, matches: Vec<(usize, Match)>, out: &mut dyn Write) -> bool {
+        let max_count = config.max_count.unwrap_or(usize::MAX);
+        for (search_id, match_) in matches {
+            if self.counts[search_id] >= max_count {
+                continue;
+            }
+            self.counts[search_id] += 1;
+            report(config, source, search_id, &match_, self.searcher.match_data(&match_), out);
+            if self.counts[search_id] == max_count {
+                self.searcher.pause(search_id);
+            }
+        }
+        self.counts.iter().any(|&count| count < max_count)
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: find [OPTIONS] [--listen tcp:PORT | --listen udp:PORT | --connect [tcp:|udp:]HOST:PORT] REGEX...");
+    eprintln!("       find [OPTIONS] --exec \"CMD ARGS\" REGEX...");
+    eprintln!("       find [OPTIONS] -e REGEX... [FILE...]");
+    eprintln!();
+    eprintln!("options:");
+    eprintln!("  --crlf                treat \\r\\n as \\n");
+    eprintln!("  -z, --null-data       input records and output entries are NUL-terminated");
+    eprintln!("  -o, --only-matching   print only the matched bytes");
+    eprintln!("  -b, --byte-offset     print the byte offset of each match (with -o)");
+    eprintln!("  -m, --max-count NUM   stop after NUM matches per pattern");
+    eprintln!("  -e, --regexp REGEX    search REGEX, the other arguments are files (- is stdin)");
+    eprintln!("  -H, --with-filename   prefix each match with the file name");
+    eprintln!("  -h, --no-filename     never prefix matches with the file name");
+    eprintln!("  -r, --recursive       scan directories recursively, following only symbolic links given as FILE");
+    eprintln!("  -R, --dereference-recursive");
+    eprintln!("                        scan directories recursively, following all symbolic links");
+    eprintln!("  --include GLOB        only scan files whose name matches GLOB");
+    eprintln!("  --exclude GLOB        skip files whose name matches GLOB");
+    eprintln!("  -j, --threads NUM     scan NUM files in parallel");
+    eprintln!("  --unordered           print matches of parallel scans as found, not in file order");
+    std::process::exit(2);
+}
+
+/// split an optional `tcp:` / `udp:` prefix off an address. Defaults to tcp.
+fn parse_endpoint(arg: Option<String>) -> (Proto, String) {
+    let arg = arg.unwrap_or_else(|| usage());
+    if let Some(rest) = arg.strip_prefix("tcp:") {
+        (Proto::Tcp, rest.into())
+    } else if let Some(rest) = arg.strip_prefix("udp:") {
+        (Proto::Udp, rest.into())
+    } else {
+        (Proto::Tcp, arg)
+    }
+}
 
 fn main() {
     let mut args = env::args();
     let _ = args.next().expect("no program name");
 
-    let mut searcher = RingSearcher::new(1024);
+    let mut input = Input::Stdin;
+    let mut config = Config::default();
+    // with `-e`, the positional arguments are files
+    let mut regexps = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => {
+                let (proto, port) = parse_endpoint(args.next());
+                input = Input::Listen(proto, format!("0.0.0.0:{}", port));
+            }
+            "--connect" => {
+                let (proto, addr) = parse_endpoint(args.next());
+                input = Input::Connect(proto, addr);
+            }
+            "--exec" => {
+                input = Input::Exec(args.next().unwrap_or_else(|| usage()));
+            }
+            "--crlf" => config.crlf = true,
+            "-z" | "--null-data" => config.null_data = true,
+            "-o" | "--only-matching" => config.only_matching = true,
+            "-b" | "--byte-offset" => config.byte_offset = true,
+            "-m" | "--max-count" => {
+                let count = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage());
+                config.max_count = Some(count);
+            }
+            "-e" | "--regexp" => regexps.push(args.next().unwrap_or_else(|| usage())),
+            "-H" | "--with-filename" => config.with_filename = Some(true),
+            "-h" | "--no-filename" => config.with_filename = Some(false),
+            "-r" | "--recursive" => config.recursive = true,
+            "-R" | "--dereference-recursive" => {
+                config.recursive = true;
+                config.follow_links = true;
+            }
+            "--include" => config.include.push(args.next().unwrap_or_else(|| usage())),
+            "--exclude" => config.exclude.push(args.next().unwrap_or_else(|| usage())),
+            "-j" | "--threads" => {
+                let threads = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage());
+                config.threads = Some(threads);
+            }
+            "--unordered" => config.unordered = true,
+            "--" => config.patterns.extend(args.by_ref()),
+            s if s.starts_with('-') && s.len() > 1 => usage(),
+            _ => config.patterns.push(arg),
+        }
+    }
+
+    if !regexps.is_empty() {
+        let files = std::mem::replace(&mut config.patterns, regexps);
+        if !files.is_empty() {
+            input = Input::Files(files);
+        } else if config.recursive {
+            input = Input::Files(vec![".".into()]);
+        }
+    }
+    config.compile();
+
+    match input {
+        Input::Stdin => {
+            scan(&config, None, ReaderSource::stdin(), &mut io::stdout());
+        }
+        Input::Listen(Proto::Tcp, addr) => {
+            let listener = TcpListener::bind(&addr).expect("failed to listen");
+            listen_tcp(&config, listener);
+        }
+        Input::Connect(Proto::Tcp, addr) => {
+            let stream = TcpStream::connect(&addr).expect("failed to connect");
+            scan_tcp(&config, stream);
+        }
+        Input::Listen(Proto::Udp, addr) => {
+            let socket = UdpSocket::bind(&addr).expect("failed to bind");
+            scan_udp(&config, socket, false);
+        }
+        Input::Connect(Proto::Udp, addr) => {
+            let socket = UdpSocket::bind("0.0.0.0:0").expect("failed to bind");
+            socket.connect(&addr).expect("failed to connect");
+            scan_udp(&config, socket, true);
+        }
+        Input::Exec(command) => {
+            scan_exec(&config, &command);
+        }
+        Input::Files(files) => {
+            scan_files(&config, &files);
+        }
+    }
+}
+
+/// Scan a complete stream. `source` identifies the stream in the output.
+fn scan(config: &Config, source: Option<&str>, mut input: impl InputSource, out: &mut dyn Write) {
+    let mut stream = config.stream();
+    loop {
+        match input.next_chunk() {
+            Ok(Some(chunk)) => {
+                if !stream.push(config, source, chunk, out) {
+                    return;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{}: {}", input.provenance().unwrap_or("?"), e);
+                break;
+            }
+        }
+    }
+    stream.finish(config, source, out);
+}
 
-    for regex_str in args {
-        searcher.add_regex_str(&regex_str).expect("invalid regex");
+/// Scan each file as a separate stream, on up to `config.threads` threads.
+///
+/// Unless `config.unordered` is set, the output of a file is held back until
+/// the output of all files before it is printed.
+fn scan_files(config: &Config, files: &[String]) {
+    let with_filename = config.with_filename.unwrap_or(files.len() > 1 || config.recursive);
+    let mut visited = HashSet::new();
+    let mut paths = vec![];
+    for path in files {
+        if config.recursive && path != "-" {
+            walk(config, Path::new(path), true, &mut visited, &mut |path| paths.push(path.to_owned()));
+        } else {
+            paths.push(PathBuf::from(path));
+        }
     }
 
-    searcher.input_matches(io::stdin().lock().bytes().flat_map(Result::ok), |search_id, match_, match_data| {
-        println!("#{} {:?}", search_id, match_);
-        println!("> {}", match_data.to_string());
+    let threads = config.threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .clamp(1, paths.len().max(1));
+    if threads == 1 {
+        for path in &paths {
+            scan_file(config, with_filename, path, &mut io::stdout());
+        }
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0 .. threads {
+            let (next, paths, sender) = (&next, &paths, sender.clone());
+            scope.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let path = match paths.get(i) {
+                        Some(path) => path,
+                        None => break,
+                    };
+                    if config.unordered {
+                        scan_file(config, with_filename, path, &mut io::stdout());
+                    } else {
+                        let mut out = vec![];
+                        scan_file(config, with_filename, path, &mut out);
+                        let _ = sender.send((i, out));
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // output of files finished before the files preceding them
+        let mut finished = BTreeMap::new();
+        let mut waiting = 0;
+        for (i, out) in receiver {
+            finished.insert(i, out);
+            while let Some(out) = finished.remove(&waiting) {
+                io::stdout().lock().write_all(&out).expect("failed to write output");
+                waiting += 1;
+            }
+        }
     });
-}
\ No newline at end of file
+}
+
+/// Scan the file at `path`, `-` is stdin.
+fn scan_file(config: &Config, with_filename: bool, path: &Path, out: &mut dyn Write) {
+    let name = path.display().to_string();
+    let source = if with_filename { Some(name.as_str()) } else { None };
+    if path == Path::new("-") {
+        return scan(config, source, ReaderSource::stdin(), out);
+    }
+    match ReaderSource::open(path) {
+        Ok(input) => scan(config, source, input, out),
+        Err(e) => eprintln!("{}: {}", name, e),
+    }
+}
+
+/// Call `f` for every regular file in `path`, in name order.
+/// Symbolic links are followed for arguments (`top`) or with `follow_links`.
+/// `visited` holds the directories already walked, so link loops end.
+fn walk(config: &Config, path: &Path, top: bool, visited: &mut HashSet<PathBuf>, f: &mut dyn FnMut(&Path)) {
+    let metadata = if top || config.follow_links {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    };
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(e) => return eprintln!("{}: {}", path.display(), e),
+    };
+    if metadata.is_file() {
+        if config.wants(path) {
+            f(path);
+        }
+        return;
+    }
+    if !metadata.is_dir() {
+        return;
+    }
+    if let Ok(canonical) = fs::canonicalize(path) {
+        if !visited.insert(canonical) {
+            return;
+        }
+    }
+    let mut entries: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(dir) => dir.flat_map(Result::ok).map(|entry| entry.path()).collect(),
+        Err(e) => return eprintln!("{}: {}", path.display(), e),
+    };
+    entries.sort();
+    for entry in entries {
+        walk(config, &entry, false, visited, f);
+    }
+}
+
+/// Scan every accepted connection in its own thread.
+///
+/// Connections beyond `MAX_PEERS` active ones are closed right away.
+fn listen_tcp(config: &Config, listener: TcpListener) {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept failed: {}", e);
+                continue;
+            }
+        };
+        if active.load(Ordering::Acquire) >= MAX_PEERS {
+            let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+            eprintln!("refusing connection from {}: {} connections active", peer, MAX_PEERS);
+            continue;
+        }
+        let connection = Connection::new(&active);
+        let config = config.clone();
+        thread::spawn(move || {
+            scan_tcp(&config, stream);
+            drop(connection);
+        });
+    }
+}
+
+/// Counts an active connection until dropped, also when its thread panics.
+struct Connection(Arc<AtomicUsize>);
+
+impl Connection {
+    fn new(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::AcqRel);
+        Connection(active.clone())
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn scan_tcp(config: &Config, stream: TcpStream) {
+    let input = ReaderSource::tcp(stream);
+    let source = input.provenance().map(String::from);
+    scan(config, source.as_deref(), input, &mut io::stdout());
+}
+
+/// Run `command` and scan its stdout and stderr as two separate streams.
+fn scan_exec(config: &Config, command: &str) {
+    let mut words = split_command(command).into_iter();
+    let program = words.next().unwrap_or_else(|| usage());
+    let mut child = Command::new(&program)
+        .args(words)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn command");
+
+    let stderr = child.stderr.take().expect("no stderr");
+    let stderr_config = config.clone();
+    let stderr_thread = thread::spawn(move || scan(&stderr_config, Some("stderr"), ReaderSource::new(stderr, Some("stderr".into())), &mut io::stdout()));
+
+    let stdout = child.stdout.take().expect("no stdout");
+    scan(config, Some("stdout"), ReaderSource::new(stdout, Some("stdout".into())), &mut io::stdout());
+
+    stderr_thread.join().expect("stderr scanner panicked");
+    let status = child.wait().expect("failed to wait for command");
+    if !status.success() {
+        eprintln!("{}: {}", program, status);
+    }
+}
+
+/// split a command line into words, honoring single and double quotes
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = None;
+    let mut quote = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// A UDP peer and the stream of its datagrams.
+struct Peer {
+    stream: Stream,
+    last_seen: Instant,
+    /// every pattern reached the maximum count, further datagrams are ignored
+    done: bool,
+}
+
+/// Every peer gets its own stream, continued by each datagram it sends.
+///
+/// A peer silent for `PEER_IDLE` ends its stream, and so does the least recently seen one
+/// when more than `MAX_PEERS` are active. Their final matches are reported then.
+/// With `connected`, the scan ends once the only peer is done.
+fn scan_udp(config: &Config, socket: UdpSocket, connected: bool) {
+    socket.set_read_timeout(Some(PEER_IDLE)).expect("failed to set the read timeout");
+    let mut peers: HashMap<SocketAddr, Peer> = HashMap::new();
+    let mut buf = vec![0; 65536];
+    let mut backoff = Duration::ZERO;
+    loop {
+        let (len, addr) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                expire_peers(config, &mut peers, Instant::now());
+                continue;
+            }
+            Err(e) => {
+                // do not spin on a persistent error
+                eprintln!("receive failed: {}", e);
+                backoff = (backoff * 2).clamp(Duration::from_millis(10), Duration::from_secs(1));
+                thread::sleep(backoff);
+                continue;
+            }
+        };
+        backoff = Duration::ZERO;
+        let now = Instant::now();
+        expire_peers(config, &mut peers, now);
+        if !peers.contains_key(&addr) && peers.len() >= MAX_PEERS {
+            let oldest = peers.iter().min_by_key(|(_, peer)| peer.last_seen).map(|(&addr, _)| addr);
+            if let Some(oldest) = oldest {
+                eprintln!("ending stream of udp:{}: {} peers active", oldest, MAX_PEERS);
+                end_peer(config, oldest, peers.remove(&oldest).unwrap());
+            }
+        }
+
+        let peer = peers.entry(addr).or_insert_with(|| Peer { stream: config.stream(), last_seen: now, done: false });
+        peer.last_seen = now;
+        if peer.done {
+            continue;
+        }
+        let source = format!("udp:{}", addr);
+        if !peer.stream.push(config, Some(&source), &buf[.. len], &mut io::stdout()) {
+            peer.done = true;
+            if connected {
+                return;
+            }
+        }
+    }
+}
+
+/// End the streams of the peers silent for `PEER_IDLE` at `now`.
+fn expire_peers(config: &Config, peers: &mut HashMap<SocketAddr, Peer>, now: Instant) {
+    let idle: Vec<SocketAddr> = peers.iter()
+        .filter(|(_, peer)| now.duration_since(peer.last_seen) >= PEER_IDLE)
+        .map(|(&addr, _)| addr)
+        .collect();
+    for addr in idle {
+        end_peer(config, addr, peers.remove(&addr).unwrap());
+    }
+}
+
+/// Report the final matches of a peer's stream, unless it is done.
+fn end_peer(config: &Config, addr: SocketAddr, mut peer: Peer) {
+    if !peer.done {
+        let source = format!("udp:{}", addr);
+        peer.stream.finish(config, Some(&source), &mut io::stdout());
+    }
+}
+
+/// Write a match to `out` with a single `write_all`, so reports of concurrent streams do not interleave.
+fn report(config: &Config, source: Option<&str>, search_id: usize, match_: &Match, match_data: MatchData, out: &mut dyn Write) {
+    let mut line = vec![];
+    format_match(config, source, search_id, match_, match_data, &mut line).expect("failed to format match");
+    out.write_all(&line).expect("failed to write output");
+}
+
+fn format_match(config: &Config, source: Option<&str>, search_id: usize, match_: &Match, match_data: MatchData, out: &mut Vec<u8>) -> io::Result<()> {
+    if config.only_matching {
+        if let Some(source) = source {
+            write!(out, "{}:", source)?;
+        }
+        if config.byte_offset {
+            match match_.start() {
+                Some(start) => write!(out, "{}:", start),
+                None => write!(out, "?:"),
+            }?;
+        }
+        out.write_all(match_data.head)?;
+        out.write_all(match_data.tail)?;
+        return out.write_all(if config.null_data { b"\0" } else { b"\n" });
+    }
+    if let Some(source) = source {
+        write!(out, "{} ", source)?;
+    }
+    writeln!(out, "#{} {:?}", search_id, match_)?;
+    if config.null_data {
+        write!(out, "> {}\0", match_data)
+    } else {
+        writeln!(out, "> {}", match_data)
+    }
+}
+
+/// Match `name` against a shell glob: `*` matches any run of bytes, `?` a single byte,
+/// `[abc]`, `[a-z]` and `[!a-z]` a byte in (or not in) the set, `\\` escapes the next byte.
+fn glob_match(glob: &[u8], name: &[u8]) -> bool {
+    // position in the glob after the last `*`, and the name position it is tried at
+    let mut star: Option<(usize, usize)> = None;
+    let (mut g, mut n) = (0, 0);
+    while n < name.len() {
+        let step = match glob.get(g) {
+            Some(b'*') => {
+                star = Some((g + 1, n));
+                g += 1;
+                continue;
+            }
+            Some(b'?') => Some(g + 1),
+            Some(b'[') => match_class(glob, g, name[n]),
+            Some(b'\\') if g + 1 < glob.len() => Some(g + 2).filter(|_| glob[g + 1] == name[n]),
+            Some(&c) => Some(g + 1).filter(|_| c == name[n]),
+            None => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                g = next;
+                n += 1;
+            }
+            // let the last `*` match one more byte
+            (None, Some((after, tried))) => {
+                star = Some((after, tried + 1));
+                g = after;
+                n = tried + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+    glob[g ..].iter().all(|&c| c == b'*')
+}
+
+/// Match `c` against the class starting at `glob[start] == b'['`.
+/// Returns the position after the class on a match. An unclosed `[` matches itself.
+fn match_class(glob: &[u8], start: usize, c: u8) -> Option<usize> {
+    let mut i = start + 1;
+    let negate = matches!(glob.get(i), Some(b'!' | b'^'));
+    if negate {
+        i += 1;
+    }
+    let mut found = false;
+    let mut first = true;
+    loop {
+        match glob.get(i) {
+            None => return Some(start + 1).filter(|_| c == b'['),
+            Some(b']') if !first => break,
+            Some(&lo) => {
+                if glob.get(i + 1) == Some(&b'-') && glob.get(i + 2).is_some_and(|&hi| hi != b']') {
+                    found |= (lo ..= glob[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= lo == c;
+                    i += 1;
+                }
+            }
+        }
+        first = false;
+    }
+    Some(i + 1).filter(|_| found != negate)
+}
@@ -0,0 +1,184 @@
+use std::{env, io, thread};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex_ring::{RingSearcher, MatchRecord};
+use regex_ring::follow::Follower;
+use regex_ring::sink::{MatchSink, TextSink};
+
+/// size of the reads from followed files
+const READ_SIZE: usize = 64 * 1024;
+
+#[derive(Clone)]
+struct Config {
+    patterns: Vec<String>,
+    crlf: bool,
+    /// print only the match data
+    only_matching: bool,
+    /// prefix matches with the file name, by default if there are several files
+    with_filename: Option<bool>,
+    /// scan the existing contents instead of starting at the end
+    from_start: bool,
+    /// time between polls once all files reached their end
+    interval: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            patterns: vec![],
+            crlf: false,
+            only_matching: false,
+            with_filename: None,
+            from_start: false,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Prefixes the provenance of every match with the time it was delivered.
+struct Timestamped<S>(S);
+
+impl<S: MatchSink> MatchSink for Timestamped<S> {
+    fn deliver(&mut self, provenance: Option<&str>, record: &MatchRecord) -> io::Result<()> {
+        let time = Timestamp(SystemTime::now());
+        let provenance = match provenance {
+            Some(provenance) => format!("{} {}", time, provenance),
+            None => time.to_string(),
+        };
+        self.0.deliver(Some(&provenance), record)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: tailgrep [OPTIONS] REGEX... -- FILE...");
+    eprintln!("       tailgrep [OPTIONS] -e REGEX... FILE...");
+    eprintln!();
+    eprintln!("Follows the files through rotation and truncation and prints matches as they appear.");
+    eprintln!();
+    eprintln!("options:");
+    eprintln!("  --crlf                 treat \\r\\n as \\n");
+    eprintln!("  -o, --only-matching    print only the matched bytes");
+    eprintln!("  -e, --regexp REGEX     search REGEX, the other arguments are files");
+    eprintln!("  -H, --with-filename    prefix each match with the file name");
+    eprintln!("  -h, --no-filename      never prefix matches with the file name");
+    eprintln!("  -a, --from-start       scan the existing contents of the files, not only what is appended");
+    eprintln!("  -s, --sleep-interval SECS");
+    eprintln!("                         wait SECS between polls, 1 by default");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut args = env::args();
+    let _ = args.next().expect("no program name");
+
+    let mut config = Config::default();
+    let mut regexps = vec![];
+    let mut positional = vec![];
+    let mut files = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crlf" => config.crlf = true,
+            "-o" | "--only-matching" => config.only_matching = true,
+            "-e" | "--regexp" => regexps.push(args.next().unwrap_or_else(|| usage())),
+            "-H" | "--with-filename" => config.with_filename = Some(true),
+            "-h" | "--no-filename" => config.with_filename = Some(false),
+            "-a" | "--from-start" => config.from_start = true,
+            "-s" | "--sleep-interval" => {
+                let secs: f64 = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage());
+                config.interval = Duration::try_from_secs_f64(secs).unwrap_or_else(|_| usage());
+            }
+            "--" => files = Some(args.by_ref().collect::<Vec<_>>()),
+            s if s.starts_with('-') && s.len() > 1 => usage(),
+            _ => positional.push(arg),
+        }
+    }
+
+    // with `-e`, the positional arguments are files, otherwise the files follow `--`
+    let files = match (regexps.is_empty(), files) {
+        (true, Some(files)) => {
+            config.patterns = positional;
+            files
+        }
+        (false, files) => {
+            config.patterns = regexps;
+            positional.extend(files.unwrap_or_default());
+            positional
+        }
+        (true, None) => usage(),
+    };
+    if config.patterns.is_empty() || files.is_empty() {
+        usage();
+    }
+
+    let mut template = RingSearcher::new(1024);
+    template.set_crlf(config.crlf);
+    for regex_str in &config.patterns {
+        template.add_regex_str(regex_str).expect("invalid regex");
+    }
+
+    let with_filename = config.with_filename.unwrap_or(files.len() > 1);
+    let mut followers: Vec<Follower<_>> = files.into_iter().map(|path| {
+        let mut follower = Follower::new(&path, template.clone(), config.from_start)
+            .unwrap_or_else(|e| panic!("{}: {}", path, e));
+        follower.set_source(Some(path).filter(|_| with_filename));
+        follower
+    }).collect();
+
+    let mut buf = vec![0; READ_SIZE];
+    let mut text = TextSink::new(io::stdout());
+    text.set_only_matching(config.only_matching);
+    let mut sink = Timestamped(text);
+    loop {
+        let mut read_any = false;
+        for follower in &mut followers {
+            match follower.poll(&mut buf, &mut sink) {
+                Ok(polled) => {
+                    if polled.truncated {
+                        eprintln!("{}: file truncated", follower.path().display());
+                    }
+                    read_any |= polled.read;
+                }
+                Err(e) => eprintln!("{}: {}", follower.path().display(), e),
+            }
+        }
+        if !read_any {
+            thread::sleep(config.interval);
+        }
+    }
+}
+
+/// Formats as RFC 3339 in UTC, with milliseconds.
+struct Timestamp(SystemTime);
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (days, secs_of_day) = (secs / 86400, secs % 86400);
+        let (year, month, day) = civil_from_days(days as i64);
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year, month, day,
+            secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60,
+            since_epoch.subsec_millis()
+        )
+    }
+}
+
+/// (year, month, day) of the proleptic Gregorian calendar, `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // shift the epoch to 0000-03-01, so leap days end the year
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
@@ -0,0 +1,57 @@
+//! A process-wide cache of compiled patterns.
+//!
+//! Creating many searchers with overlapping patterns, like one per connection,
+//! compiles each pattern once and shares its DFAs between the searchers.
+//! Only `RingSearcher::add_cached_regex_str` uses the cache.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex_automata::Regex;
+
+use crate::{RingSearcher, Error, flags_builder};
+
+/// (pattern, flags)
+type Key = (String, String);
+
+fn cache() -> &'static Mutex<HashMap<Key, Arc<Regex>>> {
+    static CACHE: OnceLock<Mutex<HashMap<Key, Arc<Regex>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// The compiled `pattern` with `flags`, from the cache if it was compiled before.
+///
+/// Flags are letters: `i` case insensitive, `s` `.` matches `\n`, `x` ignore whitespace, `U` swap greed.
+pub fn compile(pattern: &str, flags: &str) -> Result<Arc<Regex>, Error> {
+    let key = (pattern.to_owned(), flags.to_owned());
+    if let Some(regex) = cache().lock().unwrap().get(&key) {
+        return Ok(regex.clone());
+    }
+    // compile without holding the lock, a concurrent compile of the same pattern wins or loses the race harmlessly
+    let builder = flags_builder(flags).map_err(Error::InvalidFlag)?;
+    let regex = Arc::new(builder.build(pattern).map_err(|_| Error::InvalidRegex)?);
+    Ok(cache().lock().unwrap().entry(key).or_insert(regex).clone())
+}
+
+/// Number of cached patterns.
+pub fn len() -> usize {
+    cache().lock().unwrap().len()
+}
+
+/// Drop all cached patterns. Searchers keep the ones they use.
+pub fn clear() {
+    cache().lock().unwrap().clear();
+}
+
+impl RingSearcher<regex_automata::DenseDFA<Vec<usize>, usize>> {
+    /// Like `add_regex_str`, sharing the compiled pattern through the process-wide `cache`.
+    /// `flags` are regex flag letters like `"i"`, see `compile`.
+    ///
+    /// Returns the identifier for this search.
+    pub fn add_cached_regex_str(&mut self, regex_str: &str, flags: &str) -> Result<usize, Error> {
+        let regex = compile(regex_str, flags)?;
+        let search_nr = self.add_shared_regex(regex)?;
+        self.searches[search_nr].pattern = Some(regex_str.into());
+        Ok(search_nr)
+    }
+}
@@ -0,0 +1,152 @@
+//! Carving the data between a header and a footer, like files from disk images.
+//!
+//! A rule consists of a header pattern, a footer pattern and the maximum length of a carve.
+//! A header match opens a carve of its rule, the next footer match within the maximum length closes it
+//! and the data from the start of the header to the end of the footer is emitted.
+//! Headers of a rule with an open carve are part of its data, not the start of another carve.
+//! A carve without footer within the maximum length is dropped.
+
+use alloc::vec::Vec;
+use alloc::vec;
+
+use regex_automata::{Regex, DFA};
+#[cfg(feature = "std")]
+use regex_automata::DenseDFA;
+
+use crate::{RingSearcher, Match, StartBound, CHUNK_PIECE};
+#[cfg(feature = "std")]
+use crate::Error;
+
+struct Rule {
+    header: usize,
+    footer: usize,
+    max_len: usize,
+    /// (start of the header, end of the header) of the open carve
+    open: Option<(usize, usize)>,
+}
+
+/// Data between a header and a footer match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Carve {
+    /// rule identifier
+    pub rule: usize,
+    /// stream position of the start of the header
+    pub start: usize,
+    /// stream position after the end of the footer
+    pub end: usize,
+    pub data: Vec<u8>,
+}
+
+/// Carves the regions matching its rules out of a stream, see the module documentation.
+/// The buffer holds the largest maximum length.
+pub struct Carver<D: DFA> {
+    searcher: RingSearcher<D>,
+    rules: Vec<Rule>,
+    /// the carves completed by the last `push_slice`
+    found: Vec<Carve>,
+}
+
+impl<D: DFA> Carver<D> {
+    pub fn new() -> Self {
+        Carver {
+            searcher: RingSearcher::new(0),
+            rules: vec![],
+            found: vec![],
+        }
+    }
+
+    /// Add a rule carving from a `header` match to the next `footer` match, at most `max_len` bytes.
+    ///
+    /// Returns the identifier for this rule.
+    pub fn add_rule(&mut self, header: Regex<D>, footer: Regex<D>, max_len: usize) -> usize {
+        let header = self.searcher.add_regex(header);
+        let footer = self.searcher.add_regex(footer);
+        self.add(header, footer, max_len)
+    }
+
+    fn add(&mut self, header: usize, footer: usize, max_len: usize) -> usize {
+        self.searcher.set_window(header, max_len);
+        self.searcher.set_window(footer, max_len);
+        self.rules.push(Rule { header, footer, max_len, open: None });
+        self.rules.len() - 1
+    }
+
+    pub fn push_slice(&mut self, chunk: &[u8]) {
+        self.found.clear();
+        // pieces no larger than the buffer keep the data of all carves
+        let piece_len = self.searcher.capacity().clamp(1, CHUNK_PIECE);
+        for piece in chunk.chunks(piece_len) {
+            self.searcher.push_slice(piece);
+            let matches: Vec<_> = self.searcher.drain_matches().collect();
+            self.handle(matches);
+        }
+    }
+
+    /// The stream ended, footers at its end close their carves.
+    pub fn finish(&mut self) {
+        self.found.clear();
+        let matches: Vec<_> = self.searcher.final_matches().collect();
+        self.handle(matches);
+        for rule in &mut self.rules {
+            rule.open = None;
+        }
+    }
+
+    fn handle(&mut self, matches: Vec<(usize, Match)>) {
+        for (search_nr, match_) in matches {
+            for (i, rule) in self.rules.iter_mut().enumerate() {
+                match rule.open {
+                    // an expired carve does not block a new header
+                    Some((start, _)) if match_.end - start > rule.max_len => rule.open = None,
+                    _ => {}
+                }
+                match rule.open {
+                    None if search_nr == rule.header => {
+                        if let StartBound::Exact(start) = match_.start {
+                            rule.open = Some((start, match_.end));
+                        }
+                    }
+                    Some((start, header_end)) if search_nr == rule.footer && match_.start().is_some_and(|s| s >= header_end) => {
+                        let carve = Match::new(start, match_.end);
+                        self.found.push(Carve {
+                            rule: i,
+                            start,
+                            end: match_.end,
+                            data: self.searcher.match_data(&carve).to_vec(),
+                        });
+                        rule.open = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// The carves completed by the last `push_slice` or `finish`.
+    pub fn carves(&self) -> impl Iterator<Item=&Carve> + '_ {
+        self.found.iter()
+    }
+
+    /// Number of bytes pushed so far.
+    pub fn position(&self) -> usize {
+        self.searcher.position()
+    }
+}
+
+impl<D: DFA> Default for Carver<D> {
+    fn default() -> Self {
+        Carver::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Carver<DenseDFA<Vec<usize>, usize>> {
+    /// Add a rule from the sources of the header and footer patterns, see `add_rule`.
+    pub fn add_rule_str(&mut self, header: &str, footer: &str, max_len: usize) -> Result<usize, Error> {
+        let header = regex_automata::RegexBuilder::new().build(header).map_err(|_| Error::InvalidRegex)?;
+        let footer = regex_automata::RegexBuilder::new().build(footer).map_err(|_| Error::InvalidRegex)?;
+        let header = self.searcher.add_dense_regex(header)?;
+        let footer = self.searcher.add_dense_regex(footer)?;
+        Ok(self.add(header, footer, max_len))
+    }
+}
@@ -0,0 +1,70 @@
+//! `tokio_util::codec` integration.
+//!
+//! `MatchDecoder` turns a byte stream into a stream of match records:
+//!
+//! ```ignore
+//! let mut frames = FramedRead::new(socket, MatchDecoder::new(searcher));
+//! while let Some(record) = frames.next().await {
+//!     println!("{:?}", record?);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+
+use bytes::{Buf, BytesMut};
+use regex_automata::DFA;
+use tokio_util::codec::Decoder;
+
+use crate::{RingSearcher, MatchRecord};
+
+/// Decodes match records from the bytes searched by the wrapped searcher.
+/// All input is consumed, the frames are the matches in it.
+pub struct MatchDecoder<D: DFA> {
+    searcher: RingSearcher<D>,
+    pending: VecDeque<MatchRecord>,
+    /// the final matches were reported
+    finished: bool,
+}
+
+impl<D: DFA> MatchDecoder<D> {
+    pub fn new(searcher: RingSearcher<D>) -> Self {
+        MatchDecoder {
+            searcher,
+            pending: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    pub fn searcher(&self) -> &RingSearcher<D> {
+        &self.searcher
+    }
+
+    pub fn into_inner(self) -> RingSearcher<D> {
+        self.searcher
+    }
+}
+
+impl<D: DFA> Decoder for MatchDecoder<D> {
+    type Item = MatchRecord;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<MatchRecord>, io::Error> {
+        if self.pending.is_empty() {
+            self.searcher.push_records(src, &mut self.pending);
+            src.advance(src.len());
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<MatchRecord>, io::Error> {
+        if let Some(record) = self.decode(src)? {
+            return Ok(Some(record));
+        }
+        if !self.finished {
+            self.finished = true;
+            self.searcher.final_records(&mut self.pending);
+        }
+        Ok(self.pending.pop_front())
+    }
+}
@@ -0,0 +1,92 @@
+//! Compiling patterns at build time.
+//!
+//! A build script calls `embed` to compile the patterns and write their DFAs to `OUT_DIR`,
+//! and includes the Rust source it returns:
+//!
+//! ```text
+//! // build.rs
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! let source = regex_ring::codegen::embed(&["GET /[a-z]+", "POST"], &out_dir, "http_rules").unwrap();
+//! std::fs::write(format!("{}/http_rules.rs", out_dir), source).unwrap();
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/http_rules.rs"));
+//! let mut searcher = http_rules(4096);
+//! ```
+//!
+//! The generated function creates a searcher over the embedded DFAs, no regex is compiled at runtime.
+//! The DFAs use `u32` state ids and are written in both byte orders, so cross compiling works.
+
+#[cfg(feature = "std")]
+use std::{fmt::Write, fs, io, path::Path};
+
+use alloc::sync::Arc;
+
+use regex_automata::{DenseDFA, Regex};
+#[cfg(feature = "std")]
+use regex_automata::RegexBuilder;
+
+use crate::{RingSearcher, Error};
+
+/// The DFAs of embedded patterns.
+pub type EmbeddedDfa = DenseDFA<&'static [u32], u32>;
+
+impl RingSearcher<EmbeddedDfa> {
+    /// Add a search for the DFAs `embed` wrote for `pattern`.
+    ///
+    /// Returns the identifier for this search.
+    ///
+    /// # Safety
+    /// `forward` and `reverse` have to be files written by `embed`, in the byte order of the target,
+    /// and aligned to 4 bytes. The code generated by `embed` ensures this.
+    pub unsafe fn add_embedded(&mut self, forward: &'static [u8], reverse: &'static [u8], pattern: &str) -> Result<usize, Error> {
+        let forward: EmbeddedDfa = DenseDFA::from_bytes(forward);
+        let reverse: EmbeddedDfa = DenseDFA::from_bytes(reverse);
+        let memory = forward.memory_usage() + reverse.memory_usage();
+        let search_nr = self.add_shared_search(Arc::new(Regex::from_dfas(forward, reverse)), memory)?;
+        self.searches[search_nr].pattern = Some(pattern.into());
+        Ok(search_nr)
+    }
+}
+
+/// Compile `patterns`, write their DFAs to `dir` and return the source of a function `name`
+/// that takes the buffer size and returns a searcher with the patterns, as identifiers 0, 1, ...
+///
+/// The files are named after `name`, which has to be a valid identifier.
+/// Invalid patterns fail with `InvalidInput`.
+#[cfg(feature = "std")]
+pub fn embed(patterns: &[&str], dir: impl AsRef<Path>, name: &str) -> io::Result<String> {
+    let dir = dir.as_ref();
+    let mut source = String::new();
+    writeln!(source, "/// A searcher for the patterns embedded by `regex_ring::codegen::embed`.").unwrap();
+    writeln!(source, "pub fn {}(buffer_size: usize) -> ::regex_ring::RingSearcher<::regex_ring::codegen::EmbeddedDfa> {{", name).unwrap();
+    writeln!(source, "    #[repr(C)]").unwrap();
+    writeln!(source, "    struct Aligned<B: ?Sized> {{ _align: [u32; 0], bytes: B }}").unwrap();
+    writeln!(source, "    let mut searcher = ::regex_ring::RingSearcher::new(buffer_size);").unwrap();
+    for (i, pattern) in patterns.iter().enumerate() {
+        let invalid = |e: regex_automata::Error| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", pattern, e));
+        let regex = RegexBuilder::new().build_with_size::<u32>(pattern).map_err(invalid)?;
+        let mut files = vec![];
+        for (direction, dfa) in [("fwd", regex.forward()), ("rev", regex.reverse())] {
+            for (endian, bytes) in [("le", dfa.to_bytes_little_endian()), ("be", dfa.to_bytes_big_endian())] {
+                let path = dir.join(format!("{}_{}_{}_{}.dfa", name, i, direction, endian));
+                fs::write(&path, bytes.map_err(invalid)?)?;
+                files.push((endian, path));
+            }
+        }
+        for (endian, target) in [("le", "little"), ("be", "big")] {
+            let mut paths = files.iter().filter(|&&(e, _)| e == endian).map(|(_, path)| path);
+            let (forward, reverse) = (paths.next().unwrap(), paths.next().unwrap());
+            writeln!(source, "    #[cfg(target_endian = {:?})]", target).unwrap();
+            writeln!(source, "    {{").unwrap();
+            writeln!(source, "        static FORWARD: &Aligned<[u8]> = &Aligned {{ _align: [], bytes: *include_bytes!({:?}) }};", forward).unwrap();
+            writeln!(source, "        static REVERSE: &Aligned<[u8]> = &Aligned {{ _align: [], bytes: *include_bytes!({:?}) }};", reverse).unwrap();
+            writeln!(source, "        // SAFETY: written by `embed` for this byte order and aligned by `Aligned`").unwrap();
+            writeln!(source, "        unsafe {{ searcher.add_embedded(&FORWARD.bytes, &REVERSE.bytes, {:?}) }}.unwrap();", pattern).unwrap();
+            writeln!(source, "    }}").unwrap();
+        }
+    }
+    writeln!(source, "    searcher").unwrap();
+    writeln!(source, "}}").unwrap();
+    Ok(source)
+}
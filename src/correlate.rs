@@ -0,0 +1,200 @@
+//! Correlating the matches of several searches.
+//!
+//! A `Correlator` is fed the matches of a searcher, in the order `matches` yields them,
+//! and reports the combinations described by its rules:
+//!
+//! ```ignore
+//! let mut correlator = Correlator::new();
+//! // a failed login followed by a shell within 200 bytes, without a logout in between
+//! let rule = correlator.add_sequence(Sequence::new(failed, shell, 200).without(logout));
+//!
+//! searcher.push_slice(chunk);
+//! correlator.push(searcher.matches());
+//! for event in correlator.events() { ... }
+//! ```
+//!
+//! A `Threshold` counts the matches of a search over a sliding window of the stream,
+//! for rate-based detections like more than 100 hits in the last megabyte.
+//!
+//! Distances are measured between stream positions.
+//! A match without an exact start is treated as starting at its bound.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::Match;
+
+/// A match of search `first` followed by a match of search `then`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sequence {
+    pub first: usize,
+    pub then: usize,
+    /// maximum number of bytes from the end of the `first` match to the start of the `then` match
+    pub within: usize,
+    /// a match of this search in between breaks the sequence
+    pub without: Option<usize>,
+}
+
+impl Sequence {
+    pub fn new(first: usize, then: usize, within: usize) -> Self {
+        Sequence { first, then, within, without: None }
+    }
+
+    /// Break the sequence on a match of search `search_nr` after the `first` match.
+    pub fn without(self, search_nr: usize) -> Self {
+        Sequence { without: Some(search_nr), .. self }
+    }
+}
+
+/// A combination of matches reported by a `Correlator`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// rule identifier
+    pub rule: usize,
+    pub first: Match,
+    pub then: Match,
+}
+
+impl Event {
+    /// The stream region from the start of `first` to the end of `then`.
+    pub fn span(&self) -> Match {
+        Match { start: self.first.start, end: self.then.end }
+    }
+}
+
+/// More than `count` matches of search `search_nr` within `window` bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Threshold {
+    pub search_nr: usize,
+    pub count: usize,
+    pub window: usize,
+}
+
+/// A `Threshold` was exceeded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ThresholdEvent {
+    /// rule identifier
+    pub rule: usize,
+    /// number of matches in the window
+    pub count: usize,
+    /// the stream region from the start of the first counted match to the end of the last
+    pub span: Match,
+}
+
+struct SequenceRule {
+    sequence: Sequence,
+    /// the latest `first` match not yet followed by a `then` match
+    pending: Option<Match>,
+}
+
+struct ThresholdRule {
+    threshold: Threshold,
+    /// the matches within the window
+    window: VecDeque<Match>,
+}
+
+/// Reports combinations of matches, see the module documentation.
+#[derive(Default)]
+pub struct Correlator {
+    sequences: Vec<SequenceRule>,
+    thresholds: Vec<ThresholdRule>,
+    /// the events of the last `push`
+    found: Vec<Event>,
+    /// the threshold events of the last `push`
+    exceeded: Vec<ThresholdEvent>,
+}
+
+impl Correlator {
+    pub fn new() -> Self {
+        Correlator::default()
+    }
+
+    /// Report a `then` match following a `first` match as described by `sequence`.
+    ///
+    /// Only the latest `first` match is kept, and each is used for one event.
+    /// Returns the identifier for this rule.
+    pub fn add_sequence(&mut self, sequence: Sequence) -> usize {
+        self.sequences.push(SequenceRule { sequence, pending: None });
+        self.sequences.len() - 1
+    }
+
+    /// Report more matches than allowed by `threshold`.
+    ///
+    /// A match counts while its end is within the window of the end of the latest match.
+    /// Once reported, the count starts over, so a burst of matches is one event.
+    /// Returns the identifier for this rule, counted separately from the sequence rules.
+    pub fn add_threshold(&mut self, threshold: Threshold) -> usize {
+        self.thresholds.push(ThresholdRule { threshold, window: VecDeque::new() });
+        self.thresholds.len() - 1
+    }
+
+    /// Feed (search identifier, match) pairs, ordered by their end.
+    pub fn push<I>(&mut self, matches: I) where I: IntoIterator<Item=(usize, Match)> {
+        self.found.clear();
+        self.exceeded.clear();
+        for (search_nr, match_) in matches {
+            self.handle(search_nr, match_);
+        }
+    }
+
+    fn handle(&mut self, search_nr: usize, match_: Match) {
+        let start = start(&match_);
+        for (rule, state) in self.sequences.iter_mut().enumerate() {
+            let Sequence { first, then, within, without } = state.sequence;
+            if let Some(pending) = state.pending {
+                if search_nr == then && start >= pending.end {
+                    if start - pending.end <= within {
+                        self.found.push(Event { rule, first: pending, then: match_ });
+                    }
+                    state.pending = None;
+                } else if without == Some(search_nr) && start >= pending.end {
+                    state.pending = None;
+                }
+            }
+            if search_nr == first {
+                state.pending = Some(match_);
+            }
+        }
+        for (rule, state) in self.thresholds.iter_mut().enumerate() {
+            let Threshold { search_nr: counted, count, window } = state.threshold;
+            if search_nr != counted {
+                continue;
+            }
+            while state.window.front().is_some_and(|m| m.end + window < match_.end) {
+                state.window.pop_front();
+            }
+            state.window.push_back(match_);
+            if state.window.len() > count {
+                let span = Match { start: state.window[0].start, end: match_.end };
+                self.exceeded.push(ThresholdEvent { rule, count: state.window.len(), span });
+                state.window.clear();
+            }
+        }
+    }
+
+    /// The events reported by the last `push`.
+    pub fn events(&self) -> impl Iterator<Item=&Event> + '_ {
+        self.found.iter()
+    }
+
+    /// The thresholds exceeded by the last `push`.
+    pub fn threshold_events(&self) -> impl Iterator<Item=&ThresholdEvent> + '_ {
+        self.exceeded.iter()
+    }
+
+    /// Forget all pending and counted matches, for example at the start of a new stream.
+    pub fn reset(&mut self) {
+        for state in &mut self.sequences {
+            state.pending = None;
+        }
+        for state in &mut self.thresholds {
+            state.window.clear();
+        }
+        self.found.clear();
+        self.exceeded.clear();
+    }
+}
+
+fn start(match_: &Match) -> usize {
+    match_.start.position()
+}
@@ -0,0 +1,619 @@
+//! Searching base64 and hex encoded regions of a stream.
+//!
+//! `DecodingSearcher` looks for runs of at least `min_len` base64 or hex characters,
+//! decodes them and searches the decoded bytes.
+//! Each encoding is detected on its own, so a run of hex digits is also decoded as base64.
+//!
+//! Every region is searched by a fresh clone of the template searcher, matches do not span regions.
+//! `ViewSearcher` also searches the raw stream, so detection does not have to choose between the views.
+//! A region is a contiguous run of characters, line-wrapped base64 is decoded line by line.
+//! Decoding starts at the first character of the run, so base64 which is not aligned to it
+//! (because it directly follows other alphabet characters) decodes to garbage.
+//!
+//! The decoded bytes of a region can pass through further stages before they are searched,
+//! see `Pipeline`. A stage is anything implementing `Stage`, for example gzip decompression with `flate2`:
+//!
+//! ```ignore
+//! struct Gunzip(flate2::write::GzDecoder<Vec<u8>>);
+//!
+//! impl Stage for Gunzip {
+//!     fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> bool {
+//!         let ok = self.0.write_all(input).is_ok();
+//!         out.append(self.0.get_mut());
+//!         ok
+//!     }
+//!     fn fresh(&self) -> Box<dyn Stage> {
+//!         Box::new(Gunzip(flate2::write::GzDecoder::new(vec![])))
+//!     }
+//! }
+//!
+//! let pipeline = Pipeline::new(Encoding::Base64).then(Gunzip(GzDecoder::new(vec![])));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Match, StartBound, CHUNK_PIECE};
+
+/// Default minimum number of characters of an encoded region.
+pub const MIN_LEN: usize = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Standard and URL-safe alphabet. `=` padding ends a region.
+    Base64,
+    /// Upper and lower case hex digits.
+    Hex,
+}
+
+impl Encoding {
+    /// value of the character `c`, if it is part of the alphabet
+    fn value(self, c: u8) -> Option<u8> {
+        match (self, c) {
+            (Encoding::Base64, b'A' ..= b'Z') => Some(c - b'A'),
+            (Encoding::Base64, b'a' ..= b'z') => Some(c - b'a' + 26),
+            (Encoding::Base64, b'0' ..= b'9') => Some(c - b'0' + 52),
+            (Encoding::Base64, b'+' | b'-') => Some(62),
+            (Encoding::Base64, b'/' | b'_') => Some(63),
+            (Encoding::Hex, b'0' ..= b'9') => Some(c - b'0'),
+            (Encoding::Hex, b'a' ..= b'f') => Some(c - b'a' + 10),
+            (Encoding::Hex, b'A' ..= b'F') => Some(c - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    /// bits per character
+    fn bits(self) -> usize {
+        match self {
+            Encoding::Base64 => 6,
+            Encoding::Hex => 4,
+        }
+    }
+
+    /// Index of the character holding the first bit of decoded byte `k`.
+    fn char_start(self, k: usize) -> usize {
+        8 * k / self.bits()
+    }
+
+    /// Index after the character holding the last bit of the decoded bytes before `k`.
+    fn char_end(self, k: usize) -> usize {
+        (8 * k).div_ceil(self.bits())
+    }
+}
+
+/// A decoding step after the first of a `Pipeline`.
+pub trait Stage: Send {
+    /// Decode `input`, which follows the input decoded so far, and append the output to `out`.
+    /// Returning false, for example on invalid input, abandons the rest of the region.
+    fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> bool;
+
+    /// The region ended, append the remaining output to `out`.
+    fn finish(&mut self, _out: &mut Vec<u8>) -> bool {
+        true
+    }
+
+    /// A stage in its initial state, for the next region.
+    fn fresh(&self) -> Box<dyn Stage>;
+}
+
+/// Decodes the characters of the alphabet. Any other byte is invalid, except base64 padding.
+/// 
+/// `Encoding` itself decodes each input separately, the `fresh` stage of each region keeps incomplete groups of characters.
+impl Stage for Encoding {
+    fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> bool {
+        let bits = self.bits();
+        let (mut acc, mut len) = (0u32, 0);
+        for &c in input {
+            let value = match self.value(c) {
+                Some(value) => value,
+                None if *self == Encoding::Base64 && c == b'=' => continue,
+                None => return false,
+            };
+            acc = acc << bits | value as u32;
+            len += bits;
+            if len >= 8 {
+                len -= 8;
+                out.push((acc >> len) as u8);
+                acc &= (1 << len) - 1;
+            }
+        }
+        true
+    }
+
+    fn fresh(&self) -> Box<dyn Stage> {
+        Box::new(Chars { encoding: *self, pending: vec![] })
+    }
+}
+
+/// `Encoding` as a stage of a region, keeping the characters of an incomplete group.
+struct Chars {
+    encoding: Encoding,
+    pending: Vec<u8>,
+}
+
+impl Stage for Chars {
+    fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> bool {
+        self.pending.extend_from_slice(input);
+        // characters per complete group of bytes
+        let group = match self.encoding {
+            Encoding::Base64 => 4,
+            Encoding::Hex => 2,
+        };
+        let complete = self.pending.len() / group * group;
+        let ok = self.encoding.decode(&self.pending[.. complete], out);
+        self.pending.drain(.. complete);
+        ok
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> bool {
+        let pending = core::mem::take(&mut self.pending);
+        self.encoding.decode(&pending, out)
+    }
+
+    fn fresh(&self) -> Box<dyn Stage> {
+        self.encoding.fresh()
+    }
+}
+
+/// Detects regions of `encoding`, decodes them and passes the bytes through the stages in order.
+pub struct Pipeline {
+    encoding: Encoding,
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    pub fn new(encoding: Encoding) -> Self {
+        Pipeline { encoding, stages: vec![] }
+    }
+
+    /// Append a stage. Each region starts with a `fresh` copy of it.
+    pub fn then(mut self, stage: impl Stage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+}
+
+impl From<Encoding> for Pipeline {
+    fn from(encoding: Encoding) -> Self {
+        Pipeline::new(encoding)
+    }
+}
+
+/// A match in a decoded region.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedMatch {
+    /// index of the pipeline
+    pub pipeline: usize,
+    /// encoding of the region
+    pub encoding: Encoding,
+    /// search identifier
+    pub id: usize,
+    /// stream position of the first character of the region
+    pub region: usize,
+    /// positions in the bytes of the region leaving the last stage
+    pub decoded: Match,
+    /// stream positions of the characters encoding the match
+    /// 
+    /// Through stages, a decoded byte maps to the input consumed since the previous output,
+    /// so for compressed data the range may cover more than necessary.
+    /// If its mapping was already discarded, the start is at or before the oldest position still mapped.
+    pub encoded: Match,
+    /// the decoded match data
+    pub data: Vec<u8>,
+}
+
+/// Searches the decoded contents of the encoded regions of a stream, see the module documentation.
+/// 
+/// Each pipeline detects regions independently.
+pub struct DecodingSearcher<D: DFA> {
+    decoders: Vec<Decoder<D>>,
+    min_len: usize,
+    position: usize,
+    /// the matches completed by the last `push`, `push_slice` or `finish`
+    found: Vec<DecodedMatch>,
+}
+
+impl<D: DFA> DecodingSearcher<D> {
+    /// Search the regions of the given encodings with clones of `template`.
+    pub fn new(template: RingSearcher<D>, encodings: &[Encoding]) -> Self {
+        Self::with_pipelines(template, encodings.iter().map(|&encoding| Pipeline::new(encoding)))
+    }
+
+    /// Search the output of the pipelines with clones of `template`.
+    pub fn with_pipelines(template: RingSearcher<D>, pipelines: impl IntoIterator<Item=Pipeline>) -> Self {
+        let mut searcher = DecodingSearcher::default();
+        for pipeline in pipelines {
+            searcher.add_pipeline(pipeline, template.clone());
+        }
+        searcher
+    }
+
+    /// Add a pipeline with its own patterns in `template`. It only sees the stream from the current position on.
+    /// 
+    /// Returns the index of the pipeline.
+    pub fn add_pipeline(&mut self, pipeline: Pipeline, template: RingSearcher<D>) -> usize {
+        let index = self.decoders.len();
+        self.decoders.push(Decoder::new(index, pipeline, template));
+        index
+    }
+
+    /// Only decode runs of at least `min_len` characters. Shorter runs are too likely to be plain words.
+    pub fn set_min_len(&mut self, min_len: usize) {
+        self.min_len = min_len.max(1);
+    }
+
+    pub fn push(&mut self, input: u8) {
+        self.found.clear();
+        self.push_byte(input);
+    }
+
+    pub fn push_slice(&mut self, chunk: &[u8]) {
+        self.found.clear();
+        for &b in chunk {
+            self.push_byte(b);
+        }
+    }
+
+    fn push_byte(&mut self, input: u8) {
+        let position = self.position;
+        self.position += 1;
+        for decoder in &mut self.decoders {
+            decoder.push(self.min_len, position, input, &mut self.found);
+        }
+    }
+
+    /// End the regions at the end of the stream. `matches` then yields their final matches.
+    pub fn finish(&mut self) {
+        self.found.clear();
+        for decoder in &mut self.decoders {
+            decoder.end(&mut self.found);
+        }
+    }
+
+    /// The matches completed by the last `push`, `push_slice` or `finish`.
+    pub fn matches(&self) -> impl Iterator<Item=&DecodedMatch> + '_ {
+        self.found.iter()
+    }
+
+    /// Number of bytes pushed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// No pipelines, add them with `add_pipeline`.
+impl<D: DFA> Default for DecodingSearcher<D> {
+    fn default() -> Self {
+        DecodingSearcher {
+            decoders: vec![],
+            min_len: MIN_LEN,
+            position: 0,
+            found: vec![],
+        }
+    }
+}
+
+/// A view of the stream searched by a `ViewSearcher`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum View {
+    Raw,
+    /// the output of a pipeline
+    Decoded(usize),
+}
+
+/// A match in one of the views of a `ViewSearcher`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViewMatch {
+    Raw {
+        /// search identifier
+        id: usize,
+        match_: Match,
+        data: Vec<u8>,
+    },
+    Decoded(DecodedMatch),
+}
+
+impl ViewMatch {
+    pub fn view(&self) -> View {
+        match *self {
+            ViewMatch::Raw { .. } => View::Raw,
+            ViewMatch::Decoded(ref m) => View::Decoded(m.pipeline),
+        }
+    }
+
+    /// search identifier
+    pub fn id(&self) -> usize {
+        match *self {
+            ViewMatch::Raw { id, .. } => id,
+            ViewMatch::Decoded(ref m) => m.id,
+        }
+    }
+
+    /// stream positions of the match, for decoded views of the characters encoding it
+    pub fn stream_match(&self) -> Match {
+        match *self {
+            ViewMatch::Raw { match_, .. } => match_,
+            ViewMatch::Decoded(ref m) => m.encoded,
+        }
+    }
+
+    /// the match data, decoded for decoded views
+    pub fn data(&self) -> &[u8] {
+        match *self {
+            ViewMatch::Raw { ref data, .. } | ViewMatch::Decoded(DecodedMatch { ref data, .. }) => data,
+        }
+    }
+}
+
+/// Searches the raw stream and the decoded views at the same time.
+/// 
+/// The raw searcher and the pipelines can share the patterns or have their own.
+pub struct ViewSearcher<D: DFA> {
+    raw: RingSearcher<D>,
+    decoding: DecodingSearcher<D>,
+    /// the matches completed by the last `push_slice` or `finish`
+    found: Vec<ViewMatch>,
+}
+
+impl<D: DFA> ViewSearcher<D> {
+    pub fn new(raw: RingSearcher<D>, decoding: DecodingSearcher<D>) -> Self {
+        ViewSearcher { raw, decoding, found: vec![] }
+    }
+
+    /// Search `chunk` in all views. The matches of the raw view come first.
+    pub fn push_slice(&mut self, chunk: &[u8]) {
+        self.found.clear();
+        // pieces no larger than the buffer keep the data of the raw matches
+        let piece_len = self.raw.capacity().clamp(1, CHUNK_PIECE);
+        for piece in chunk.chunks(piece_len) {
+            self.raw.push_slice(piece);
+            for (id, match_) in self.raw.matches() {
+                self.found.push(ViewMatch::Raw { id, match_, data: self.raw.match_data(&match_).to_vec() });
+            }
+        }
+        self.decoding.push_slice(chunk);
+        self.found.extend(self.decoding.matches().cloned().map(ViewMatch::Decoded));
+    }
+
+    /// End all views at the end of the stream. `matches` then yields their final matches.
+    pub fn finish(&mut self) {
+        self.found.clear();
+        for (id, match_) in self.raw.final_matches() {
+            self.found.push(ViewMatch::Raw { id, match_, data: self.raw.match_data(&match_).to_vec() });
+        }
+        self.decoding.finish();
+        self.found.extend(self.decoding.matches().cloned().map(ViewMatch::Decoded));
+    }
+
+    /// The matches completed by the last `push_slice` or `finish`.
+    pub fn matches(&self) -> impl Iterator<Item=&ViewMatch> + '_ {
+        self.found.iter()
+    }
+
+    pub fn raw(&self) -> &RingSearcher<D> {
+        &self.raw
+    }
+
+    pub fn decoding(&self) -> &DecodingSearcher<D> {
+        &self.decoding
+    }
+}
+
+struct Decoder<D: DFA> {
+    /// index of the pipeline
+    index: usize,
+    pipeline: Pipeline,
+    /// cloned for every region
+    template: RingSearcher<D>,
+    /// values of the characters of a run too short to be decoded yet
+    pending: Vec<u8>,
+    /// stream position of the first character of the run
+    run_start: usize,
+    region: Option<Region<D>>,
+}
+
+impl<D: DFA> Decoder<D> {
+    fn new(index: usize, pipeline: Pipeline, template: RingSearcher<D>) -> Self {
+        Decoder { index, pipeline, template, pending: vec![], run_start: 0, region: None }
+    }
+
+    fn push(&mut self, min_len: usize, position: usize, input: u8, found: &mut Vec<DecodedMatch>) {
+        let encoding = self.pipeline.encoding;
+        let value = match encoding.value(input) {
+            Some(value) => value,
+            None => return self.end(found),
+        };
+        if let Some(ref mut region) = self.region {
+            return region.push(value, found);
+        }
+        if self.pending.is_empty() {
+            self.run_start = position;
+        }
+        self.pending.push(value);
+        if self.pending.len() >= min_len {
+            let mut region = Region {
+                pipeline: self.index,
+                encoding,
+                start: self.run_start,
+                searcher: self.template.clone(),
+                bits: 0,
+                len: 0,
+                stages: self.pipeline.stages.iter().map(|stage| StageState::new(stage.fresh())).collect(),
+                valid: true,
+                scratch: Default::default(),
+            };
+            for value in self.pending.drain(..) {
+                region.push(value, found);
+            }
+            self.region = Some(region);
+        }
+    }
+
+    /// The run ended, report the final matches of the region.
+    fn end(&mut self, found: &mut Vec<DecodedMatch>) {
+        self.pending.clear();
+        if let Some(mut region) = self.region.take() {
+            region.finish(found);
+        }
+    }
+}
+
+struct Region<D: DFA> {
+    pipeline: usize,
+    encoding: Encoding,
+    /// stream position of the first character
+    start: usize,
+    searcher: RingSearcher<D>,
+    /// the `len` bits not yet decoded
+    bits: u32,
+    len: usize,
+    stages: Vec<StageState>,
+    /// no stage rejected the data so far
+    valid: bool,
+    /// input and output of a stage, reused for every byte
+    scratch: [Vec<u8>; 2],
+}
+
+impl<D: DFA> Region<D> {
+    fn push(&mut self, value: u8, found: &mut Vec<DecodedMatch>) {
+        if !self.valid {
+            return;
+        }
+        self.bits = self.bits << self.encoding.bits() | value as u32;
+        self.len += self.encoding.bits();
+        if self.len < 8 {
+            return;
+        }
+        self.len -= 8;
+        let byte = (self.bits >> self.len) as u8;
+        self.bits &= (1 << self.len) - 1;
+        self.run(Some(byte), false, found);
+    }
+
+    fn finish(&mut self, found: &mut Vec<DecodedMatch>) {
+        if self.valid {
+            self.run(None, true, found);
+            self.report(self.searcher.final_matches(), found);
+        }
+    }
+
+    /// Pass `byte` through the stages and search the output.
+    fn run(&mut self, byte: Option<u8>, finish: bool, found: &mut Vec<DecodedMatch>) {
+        let [mut data, mut out] = core::mem::take(&mut self.scratch);
+        data.clear();
+        data.extend(byte);
+        for state in &mut self.stages {
+            out.clear();
+            if !state.stage.decode(&data, &mut out) || (finish && !state.stage.finish(&mut out)) {
+                self.valid = false;
+                return;
+            }
+            state.record(data.len(), out.len());
+            core::mem::swap(&mut data, &mut out);
+        }
+        for &b in &data {
+            self.searcher.push(b);
+            self.report(self.searcher.matches(), found);
+        }
+        self.scratch = [data, out];
+        self.trim();
+    }
+
+    /// Discard the mappings of output no longer in the buffer of the searcher.
+    fn trim(&mut self) {
+        let mut needed = self.searcher.position() - self.searcher.buffer_len();
+        for state in self.stages.iter_mut().rev() {
+            while state.spans.front().is_some_and(|span| span.out_end <= needed) {
+                state.spans.pop_front();
+            }
+            needed = state.spans.front().map_or(state.mapped, |span| span.in_start);
+        }
+    }
+
+    fn report(&self, matches: impl Iterator<Item=(usize, Match)>, found: &mut Vec<DecodedMatch>) {
+        for (id, decoded) in matches {
+            // map back through the stages
+            let (mut start, mut end) = (decoded.start, decoded.end);
+            for state in self.stages.iter().rev() {
+                start = state.map_start(start);
+                end = state.map_end(end);
+            }
+            found.push(DecodedMatch {
+                pipeline: self.pipeline,
+                encoding: self.encoding,
+                id,
+                region: self.start,
+                decoded,
+                encoded: Match {
+                    start: start.map(|s| self.start + self.encoding.char_start(s)),
+                    end: self.start + self.encoding.char_end(end),
+                },
+                data: self.searcher.match_data(&decoded).to_vec(),
+            });
+        }
+    }
+}
+
+/// A stage with the mapping of its output to its input.
+struct StageState {
+    stage: Box<dyn Stage>,
+    spans: VecDeque<Span>,
+    input_len: usize,
+    output_len: usize,
+    /// input position after the input of the last span
+    mapped: usize,
+}
+
+/// Output `out_start .. out_end` was produced while consuming input `in_start .. in_end`.
+struct Span {
+    out_start: usize,
+    out_end: usize,
+    in_start: usize,
+    in_end: usize,
+}
+
+impl StageState {
+    fn new(stage: Box<dyn Stage>) -> Self {
+        StageState { stage, spans: VecDeque::new(), input_len: 0, output_len: 0, mapped: 0 }
+    }
+
+    /// `input` bytes were decoded to `output` bytes.
+    /// Input producing no output is mapped together with the next input that does.
+    fn record(&mut self, input: usize, output: usize) {
+        self.input_len += input;
+        if output == 0 {
+            return;
+        }
+        self.spans.push_back(Span {
+            out_start: self.output_len,
+            out_end: self.output_len + output,
+            in_start: self.mapped,
+            in_end: self.input_len,
+        });
+        self.output_len += output;
+        self.mapped = self.input_len;
+    }
+
+    /// input position of the output byte at `start`.
+    /// If its span was already dropped, the start is at or before the oldest input still mapped.
+    fn map_start(&self, start: StartBound) -> StartBound {
+        let oldest = self.spans.front().map_or(self.mapped, |span| span.in_start);
+        let position = start.position();
+        let mapped = self.spans.iter().find(|span| span.out_start <= position && position < span.out_end).map(|span| span.in_start);
+        match (start, mapped) {
+            (StartBound::Exact(_), Some(mapped)) => StartBound::Exact(mapped),
+            (StartBound::AtOrBefore(_), Some(mapped)) => StartBound::AtOrBefore(mapped),
+            (_, None) => StartBound::AtOrBefore(oldest),
+        }
+    }
+
+    /// input position after the output before `position`
+    fn map_end(&self, position: usize) -> usize {
+        self.spans.iter().find(|span| span.out_start < position && position <= span.out_end)
+            .map_or(self.input_len, |span| span.in_end)
+    }
+}
@@ -0,0 +1,306 @@
+//! Patterns the DFAs can not express, confirmed with `fancy-regex`.
+//!
+//! Such a pattern is searched in two stages:
+//!  1. The stream is searched with DFAs for an approximation of the pattern,
+//!     which matches at least everything the pattern matches.
+//!  2. Every candidate match is confirmed by running `fancy-regex` on the buffered bytes
+//!     ending at the candidate.
+//!
+//! Backreferences are approximated by a copy of the referenced group,
+//! so `(a+)b\1` is searched as `(a+)b(?:a+)` first.
+//!
+//! Positive lookaround is approximated by matching its contents, negative lookaround is left out.
+//! `foo(?=bar)` is searched as `foo(?:bar)` and `(?<!a)b` as `b`.
+//!
+//! Window limitation: lookaround can only see what is in the buffer.
+//!  - Lookbehind sees the buffered bytes before the match, at most the buffer size.
+//!  - Lookahead sees the bytes up to the end of the approximate match
+//!    and the bytes pushed until the candidate is reported, usually one.
+//!    `foo(?!bar)` therefore only rejects `foo` followed by `b`, at the end of the stream not even that.
+//!
+//! `fancy-regex` works on UTF-8. Invalid sequences in the buffer are replaced before confirming,
+//! and therefore can not be matched.
+
+use std::sync::Arc;
+
+use regex_automata::{DenseDFA, DFA};
+
+use crate::{RingSearcher, Error, Match, StartBound};
+
+impl RingSearcher<DenseDFA<Vec<usize>, usize>> {
+    /// Add a regex from a `str` which may use the features of `fancy-regex`,
+    /// like backreferences and lookaround. See the module documentation for the limitations.
+    ///
+    /// Returns the identifier for this search.
+    pub fn add_fancy_regex_str(&mut self, regex_str: &str) -> Result<usize, Error> {
+        let confirm = fancy_regex::Regex::new(regex_str).map_err(|_| Error::InvalidRegex)?;
+        let approximation = approximate(regex_str);
+        let regex = regex_automata::RegexBuilder::new().build(&approximation).map_err(|_| Error::InvalidRegex)?;
+        let search_nr = self.add_dense_regex(regex)?;
+        self.searches[search_nr].confirm = Some(Arc::new(confirm));
+        self.searches[search_nr].pattern = Some(regex_str.into());
+        Ok(search_nr)
+    }
+}
+
+impl<D: DFA> RingSearcher<D> {
+    /// Find the first match of `regex` in the candidate `match_`.
+    /// All buffered data, followed by `tail`, is available to the regex as context.
+    pub(crate) fn confirm(&self, regex: &fancy_regex::Regex, match_: Match, tail: &[u8]) -> Option<Match> {
+        // first data byte in self.buffer is at this stream position
+        let offset = self.position - self.buffer.len() - tail.len();
+        let bytes: Vec<u8> = self.buffer.iter().chain(tail).cloned().collect();
+        let (text, map) = decode(&bytes);
+
+        let from = to_text(&map, match_.start().map_or(0, |start| start - offset));
+        let found = regex.find_from_pos(&text, from).ok()??;
+
+        let start = to_bytes(&map, found.start());
+        let end = offset + to_bytes(&map, found.end());
+        if end > match_.end {
+            return None;
+        }
+        Some(Match {
+            start: if start == 0 && offset > 0 { StartBound::AtOrBefore(offset) } else { StartBound::Exact(offset + start) },
+            end,
+        })
+    }
+}
+
+/// Rewrite `pattern` into a pattern the DFAs support, which matches at least everything `pattern` matches.
+///
+/// Backreferences are replaced by the referenced group,
+/// positive lookaround by its contents and negative lookaround is removed.
+pub fn approximate(pattern: &str) -> String {
+    let groups = parse_groups(pattern);
+    let mut out = String::with_capacity(pattern.len());
+    approximate_range(pattern, &groups, 0, pattern.len(), &mut out);
+    out
+}
+
+fn approximate_range(pattern: &str, groups: &[Group], start: usize, end: usize, out: &mut String) {
+    let mut chars = pattern[.. end].char_indices().skip_while(|&(i, _)| i < start).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let (end, reference) = parse_escape(pattern, i);
+                match reference {
+                    Some(reference) => {
+                        // a group can only be copied if it was closed before the reference
+                        match groups.iter().find(|g| g.matches(&reference) && g.end <= i) {
+                            Some(group) => {
+                                out.push_str("(?:");
+                                approximate_range(pattern, groups, group.start, group.end, out);
+                                out.push(')');
+                            }
+                            None => out.push_str("(?s:.*)"),
+                        }
+                    }
+                    None => out.push_str(&pattern[i .. end]),
+                }
+                while chars.next_if(|&(j, _)| j < end).is_some() {}
+            }
+            '(' if is_lookaround(&pattern[i ..]) => {
+                let end = group_end(pattern, i);
+                let rest = &pattern[i + 1 ..];
+                if rest.starts_with("?=") || rest.starts_with("?<=") {
+                    let prefix = if rest.starts_with("?=") { 3 } else { 4 };
+                    out.push_str("(?:");
+                    approximate_range(pattern, groups, i + prefix, end - 1, out);
+                    out.push(')');
+                }
+                while chars.next_if(|&(j, _)| j < end).is_some() {}
+            }
+            '[' => {
+                let end = class_end(pattern, i);
+                out.push_str(&pattern[i .. end]);
+                while chars.next_if(|&(j, _)| j < end).is_some() {}
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+enum Reference {
+    Number(usize),
+    Name(String),
+}
+
+/// a capturing group, with the range of its contents
+struct Group {
+    number: usize,
+    name: Option<String>,
+    start: usize,
+    end: usize,
+}
+
+impl Group {
+    fn matches(&self, reference: &Reference) -> bool {
+        match *reference {
+            Reference::Number(n) => n == self.number,
+            Reference::Name(ref name) => self.name.as_ref() == Some(name),
+        }
+    }
+}
+
+/// Parse the escape sequence starting at `i`.
+/// Returns the end of the escape sequence and the backreference, if it is one.
+fn parse_escape(pattern: &str, i: usize) -> (usize, Option<Reference>) {
+    let rest = &pattern[i + 1 ..];
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 {
+        let number = rest[.. digits].parse().unwrap_or(usize::MAX);
+        return (i + 1 + digits, Some(Reference::Number(number)));
+    }
+    if let Some(named) = rest.strip_prefix("k<") {
+        if let Some(len) = named.find('>') {
+            let name = &named[.. len];
+            let reference = match name.parse() {
+                Ok(number) => Reference::Number(number),
+                Err(_) => Reference::Name(name.into()),
+            };
+            return (i + 3 + len + 1, Some(reference));
+        }
+    }
+    let len = rest.chars().next().map_or(0, char::len_utf8);
+    (i + 1 + len, None)
+}
+
+fn is_lookaround(group: &str) -> bool {
+    ["(?=", "(?!", "(?<=", "(?<!"].iter().any(|p| group.starts_with(p))
+}
+
+/// end of the group starting at `i`, after the closing `)`
+fn group_end(pattern: &str, i: usize) -> usize {
+    let bytes = pattern.as_bytes();
+    let mut depth = 0;
+    let mut j = i;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' => j = parse_escape(pattern, j).0,
+            b'[' => j = class_end(pattern, j),
+            b'(' => {
+                depth += 1;
+                j += 1;
+            }
+            b')' => {
+                depth -= 1;
+                j += 1;
+                if depth == 0 {
+                    return j;
+                }
+            }
+            _ => j += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// end of the character class starting at `i`
+fn class_end(pattern: &str, i: usize) -> usize {
+    let bytes = pattern.as_bytes();
+    let mut depth = 0;
+    let mut j = i;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' => j += 1,
+            b'[' => {
+                depth += 1;
+                // a `]` right after the opening bracket (or `^`) is literal
+                if bytes.get(j + 1) == Some(&b'^') {
+                    j += 1;
+                }
+                if bytes.get(j + 1) == Some(&b']') {
+                    j += 1;
+                }
+            }
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return j + 1;
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    bytes.len()
+}
+
+/// find all capturing groups
+fn parse_groups(pattern: &str) -> Vec<Group> {
+    let bytes = pattern.as_bytes();
+    let mut groups = vec![];
+    // (index into groups, if capturing)
+    let mut open: Vec<Option<usize>> = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i = parse_escape(pattern, i).0,
+            b'[' => i = class_end(pattern, i),
+            b'(' => {
+                let rest = &pattern[i + 1 ..];
+                let (name, skip) = if let Some(named) = rest.strip_prefix("?P<").or_else(|| {
+                    rest.strip_prefix("?<").filter(|r| !r.starts_with('=') && !r.starts_with('!'))
+                }) {
+                    let len = named.find('>').unwrap_or(named.len());
+                    (Some(named[.. len].to_string()), rest.len() - named.len() + len + 1)
+                } else {
+                    (None, 0)
+                };
+                if name.is_some() || !rest.starts_with('?') {
+                    open.push(Some(groups.len()));
+                    groups.push(Group {
+                        number: groups.len() + 1,
+                        name,
+                        start: i + 1 + skip,
+                        end: usize::MAX,
+                    });
+                } else {
+                    open.push(None);
+                }
+                i += 1 + skip;
+            }
+            b')' => {
+                if let Some(Some(group)) = open.pop() {
+                    groups[group].end = i;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    groups
+}
+
+/// Decode as UTF-8, replacing invalid sequences.
+/// Also returns the (text offset, byte offset) pairs after each replacement.
+fn decode(bytes: &[u8]) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut map = vec![];
+    let mut offset = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        offset += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            offset += chunk.invalid().len();
+            map.push((text.len(), offset));
+        }
+    }
+    (text, map)
+}
+
+fn to_bytes(map: &[(usize, usize)], text_offset: usize) -> usize {
+    match map.iter().rev().find(|&&(t, _)| t <= text_offset) {
+        Some(&(t, b)) => b + (text_offset - t),
+        None => text_offset,
+    }
+}
+
+fn to_text(map: &[(usize, usize)], byte_offset: usize) -> usize {
+    match map.iter().rev().find(|&&(_, b)| b <= byte_offset) {
+        Some(&(t, b)) => t + (byte_offset - b),
+        None => byte_offset,
+    }
+}
@@ -0,0 +1,63 @@
+//! XXH64 with seed 0, so fingerprints can be reproduced by any xxhash implementation.
+
+const P1: u64 = 0x9E37_79B1_85EB_CA87;
+const P2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const P3: u64 = 0x1656_67B1_9E37_79F9;
+const P4: u64 = 0x85EB_CA77_C2B2_AE63;
+const P5: u64 = 0x27D4_EB2F_1656_67C5;
+
+/// A stable 64 bit hash of `data`: XXH64 with seed 0.
+///
+/// Used for the `fingerprint` of match records, to deduplicate and correlate matches without their data.
+pub fn fingerprint(data: &[u8]) -> u64 {
+    let mut rest = data;
+    let mut h = if data.len() >= 32 {
+        let mut v = [P1.wrapping_add(P2), P2, 0, P1.wrapping_neg()];
+        while rest.len() >= 32 {
+            for (i, v) in v.iter_mut().enumerate() {
+                *v = round(*v, read_u64(&rest[8 * i ..]));
+            }
+            rest = &rest[32 ..];
+        }
+        let h = v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18));
+        v.iter().fold(h, |h, &v| (h ^ round(0, v)).wrapping_mul(P1).wrapping_add(P4))
+    } else {
+        P5
+    };
+    h = h.wrapping_add(data.len() as u64);
+
+    while rest.len() >= 8 {
+        h ^= round(0, read_u64(rest));
+        h = h.rotate_left(27).wrapping_mul(P1).wrapping_add(P4);
+        rest = &rest[8 ..];
+    }
+    if rest.len() >= 4 {
+        let k = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as u64;
+        h ^= k.wrapping_mul(P1);
+        h = h.rotate_left(23).wrapping_mul(P2).wrapping_add(P3);
+        rest = &rest[4 ..];
+    }
+    for &b in rest {
+        h ^= (b as u64).wrapping_mul(P5);
+        h = h.rotate_left(11).wrapping_mul(P1);
+    }
+
+    h ^= h >> 33;
+    h = h.wrapping_mul(P2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(P3);
+    h ^ (h >> 32)
+}
+
+fn round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(P2)).rotate_left(31).wrapping_mul(P1)
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[.. 8]);
+    u64::from_le_bytes(bytes)
+}
@@ -0,0 +1,148 @@
+//! A searcher without heap allocations.
+
+use regex_automata::{Regex, DFA};
+
+use crate::{Error, Match, StartBound, MatchData, Step, dfa_step, rfind_iter, slice_window, matches_empty};
+
+struct FixedSearch<D: DFA> {
+    regex: Regex<D>,
+    state_id: D::ID,
+    /// stream position after the last byte of the match in progress
+    match_end: Option<usize>,
+    /// end of the match completed by the last `push`
+    completed: Option<usize>,
+}
+
+/// A searcher with an inline ring of `N` bytes and room for `S` searches.
+/// 
+/// Nothing is allocated, so with DFAs borrowing their tables
+/// (like `DenseDFA<&'static [u16], u16>` from `DenseDFA::from_bytes`)
+/// it works without `std` and never touches the heap, and `new` can initialize a static.
+/// The crate still links `alloc` for the other searchers, so a global allocator must exist.
+/// 
+/// Compared to `RingSearcher`, there are no windows, record delimiters, CRLF handling or byte maps.
+/// `N` must not be zero, `new` does not compile otherwise:
+/// 
+/// ```compile_fail
+/// let searcher = regex_ring::FixedSearcher::<regex_automata::DenseDFA<Vec<usize>, usize>, 0, 1>::new();
+/// ```
+pub struct FixedSearcher<D: DFA, const N: usize, const S: usize> {
+    buffer: [u8; N],
+    /// index in `buffer` the next byte is written to
+    head: usize,
+    /// number of bytes pushed
+    position: usize,
+    searches: [Option<FixedSearch<D>>; S],
+}
+
+impl<D: DFA, const N: usize, const S: usize> FixedSearcher<D, N, S> {
+    const EMPTY: Option<FixedSearch<D>> = None;
+    const NONZERO: () = assert!(N > 0, "FixedSearcher needs a ring of at least one byte");
+
+    pub const fn new() -> Self {
+        let () = Self::NONZERO;
+        FixedSearcher {
+            buffer: [0; N],
+            head: 0,
+            position: 0,
+            searches: [Self::EMPTY; S],
+        }
+    }
+
+    /// Add a search for `regex`. Fails if all `S` searches are in use
+    /// or with `EmptyMatch` if `regex` can match the empty string.
+    /// 
+    /// Returns the identifier for this search.
+    pub fn add_regex(&mut self, regex: Regex<D>) -> Result<usize, Error> {
+        if matches_empty(&regex) {
+            return Err(Error::EmptyMatch);
+        }
+        let (search_nr, slot) = self.searches.iter_mut().enumerate()
+            .find(|(_, slot)| slot.is_none())
+            .ok_or(Error::TooManySearches)?;
+        *slot = Some(FixedSearch {
+            state_id: regex.forward().start_state(),
+            regex,
+            match_end: None,
+            completed: None,
+        });
+        Ok(search_nr)
+    }
+
+    /// feed one stream byte to the searcher
+    pub fn push(&mut self, input: u8) {
+        self.buffer[self.head] = input;
+        self.head = (self.head + 1) % N;
+        self.position += 1;
+
+        let position = self.position;
+        for search in self.searches.iter_mut().flatten() {
+            search.completed = match dfa_step(search.regex.forward(), &mut search.state_id, input) {
+                Step::Match => {
+                    search.match_end = Some(position);
+                    None
+                }
+                Step::Hold => None,
+                Step::NoMatch => search.match_end.take(),
+                Step::Rematch => search.match_end.replace(position),
+            };
+        }
+    }
+
+    /// Obtain the matches ending at the previous input byte.
+    /// 
+    /// The iterator yields (search identifier, match).
+    pub fn matches(&self) -> impl Iterator<Item=(usize, Match)> + '_ {
+        self.searches.iter().enumerate().filter_map(move |(i, search)| {
+            let search = search.as_ref()?;
+            search.completed.and_then(|end| self.find_match(search, end)).map(|match_| (i, match_))
+        })
+    }
+
+    /// Obtain the final matches, after the input has ended.
+    pub fn final_matches(&self) -> impl Iterator<Item=(usize, Match)> + '_ {
+        self.searches.iter().enumerate().filter_map(move |(i, search)| {
+            let search = search.as_ref()?;
+            search.match_end.and_then(|end| self.find_match(search, end)).map(|match_| (i, match_))
+        })
+    }
+
+    fn find_match(&self, search: &FixedSearch<D>, end: usize) -> Option<Match> {
+        // first byte in the ring is at this stream position
+        let offset = self.position.saturating_sub(N);
+        let available = end - offset;
+        // ring index after the last byte of the match
+        let last = (self.head + N - (self.position - end) % N) % N;
+        let bytes = (0 .. available).map(|i| self.buffer[(last + N - 1 - i) % N]);
+        let len = rfind_iter(search.regex.reverse(), bytes.enumerate())?;
+        Some(Match {
+            start: if len == available && offset > 0 { StartBound::AtOrBefore(offset) } else { StartBound::Exact(end - len) },
+            end,
+        })
+    }
+
+    /// The part of the match that is still in the ring.
+    pub fn match_data(&self, match_: &Match) -> MatchData<'_> {
+        let (head, tail) = if self.position >= N {
+            (&self.buffer[self.head ..], &self.buffer[.. self.head])
+        } else {
+            (&self.buffer[.. self.head], &self.buffer[.. 0])
+        };
+
+        // first byte in the ring is at this stream position
+        let offset = self.position.saturating_sub(N);
+        let start = match_.start().unwrap_or(offset).saturating_sub(offset);
+        let end = match_.end.saturating_sub(offset);
+
+        MatchData {
+            head: slice_window(head, start, end),
+            tail: slice_window(tail, start.saturating_sub(head.len()), end.saturating_sub(head.len()))
+        }
+    }
+}
+
+impl<D: DFA, const N: usize, const S: usize> Default for FixedSearcher<D, N, S> {
+    fn default() -> Self {
+        FixedSearcher::new()
+    }
+}
@@ -0,0 +1,158 @@
+//! Following a log file through rotation and truncation, like `tail -F`.
+//!
+//! A `Follower` reads whatever file is at its path, noticing when it is replaced by a new file
+//! (a different device and inode, only on unix) or when it shrinks, and delivers the matches to a `MatchSink`.
+
+use std::fs::{self, File, Metadata};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, CHUNK_PIECE};
+use crate::sink::MatchSink;
+
+/// What happened in a `Follower::poll`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Polled {
+    /// data was read
+    pub read: bool,
+    /// a new file appeared at the path and was read from its start
+    pub rotated: bool,
+    /// the file shrunk below what was read and was read again from its start
+    pub truncated: bool,
+}
+
+/// The identity of a file, so a new file at the same path is noticed.
+/// Only available on unix, elsewhere rotation is only noticed when the file shrinks.
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// A logical log: whatever file is at `path`, continued through rotation and truncation.
+///
+/// The searcher keeps its state when the file is replaced,
+/// so the positions of matches count the bytes of all files read from `path`.
+pub struct Follower<D: DFA> {
+    path: PathBuf,
+    /// provenance of the matches, if any
+    source: Option<String>,
+    /// the file being read, `None` while nothing exists at `path`
+    file: Option<File>,
+    id: Option<(u64, u64)>,
+    /// bytes read from `file`
+    offset: u64,
+    searcher: RingSearcher<D>,
+}
+
+impl<D: DFA> Follower<D> {
+    /// Follow the file at `path` with `searcher`, if it exists yet.
+    /// Unless `from_start` is set, its existing contents are skipped.
+    pub fn new(path: impl Into<PathBuf>, searcher: RingSearcher<D>, from_start: bool) -> io::Result<Self> {
+        let mut follower = Follower {
+            path: path.into(),
+            source: None,
+            file: None,
+            id: None,
+            offset: 0,
+            searcher,
+        };
+        follower.open(from_start)?;
+        Ok(follower)
+    }
+
+    /// Deliver the matches with `source` as provenance, like the file name.
+    pub fn set_source(&mut self, source: Option<String>) {
+        self.source = source;
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn searcher(&self) -> &RingSearcher<D> {
+        &self.searcher
+    }
+
+    fn open(&mut self, from_start: bool) -> io::Result<()> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let metadata = file.metadata()?;
+        self.id = file_id(&metadata);
+        self.offset = 0;
+        if !from_start {
+            (&file).seek(SeekFrom::Start(metadata.len()))?;
+            self.offset = metadata.len();
+        }
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// Read the open file to its end, then check whether it was truncated or replaced,
+    /// delivering the matches found to `sink`. The read buffer is `buf`.
+    pub fn poll(&mut self, buf: &mut [u8], sink: &mut dyn MatchSink) -> io::Result<Polled> {
+        let mut polled = Polled { read: self.read(buf, sink)?, ..Polled::default() };
+
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // moved away, keep reading the old file until a new one appears
+            Err(_) => return Ok(polled),
+        };
+        if self.file.is_none() || file_id(&metadata) != self.id {
+            // rotated: the rest of the old file was read above, continue with the new one
+            self.file = None;
+            self.open(true)?;
+            polled.rotated = true;
+            polled.read |= self.read(buf, sink)?;
+        } else if metadata.len() < self.offset {
+            if let Some(file) = &mut self.file {
+                file.seek(SeekFrom::Start(0))?;
+                self.offset = 0;
+            }
+            polled.truncated = true;
+            polled.read |= self.read(buf, sink)?;
+        }
+        Ok(polled)
+    }
+
+    fn read(&mut self, buf: &mut [u8], sink: &mut dyn MatchSink) -> io::Result<bool> {
+        let mut read_any = false;
+        while let Some(file) = &mut self.file {
+            match file.read(buf) {
+                Ok(0) => break,
+                Ok(len) => {
+                    self.offset += len as u64;
+                    read_any = true;
+                    self.push(&buf[.. len], sink)?;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(read_any)
+    }
+
+    /// Push `input` in pieces no larger than the buffer or `CHUNK_PIECE`,
+    /// so the data of all matches is still in the buffer when they are delivered.
+    fn push(&mut self, input: &[u8], sink: &mut dyn MatchSink) -> io::Result<()> {
+        let piece_len = self.searcher.capacity().min(CHUNK_PIECE);
+        for piece in input.chunks(piece_len) {
+            self.searcher.push_slice(piece);
+            let matches: Vec<_> = self.searcher.drain_matches().collect();
+            for (id, match_) in matches {
+                sink.deliver(self.source.as_deref(), &self.searcher.record(id, match_))?;
+            }
+        }
+        sink.flush()
+    }
+}
@@ -0,0 +1,153 @@
+//! Approximate matching of literals with the bitap algorithm (Wu-Manber).
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Search, Engine, Step, Error};
+
+/// Bitap state: bit `i` of `state[d]` is set if the first `i + 1` bytes of the literal
+/// match the input ending at the current position with at most `d` errors.
+#[derive(Clone)]
+pub(crate) struct Fuzzy {
+    masks: Arc<Masks>,
+    len: usize,
+    max_errors: usize,
+    state: Vec<u64>,
+    /// fewest errors of the match in progress
+    best: Option<usize>,
+}
+
+/// bit `i` is set for the bytes equal to the i-th byte of the literal (from the end, for `reverse`)
+struct Masks {
+    forward: [u64; 256],
+    reverse: [u64; 256],
+}
+
+impl<D: DFA> RingSearcher<D> {
+    /// Add a search for `literal`, allowing up to `max_errors` edits
+    /// (inserted, deleted or substituted bytes).
+    ///
+    /// The literal must not be longer than 64 bytes, and longer than `max_errors`.
+    /// Of overlapping candidates, the one with the fewest errors is reported.
+    ///
+    /// The bit masks count towards the memory limit, exceeding it fails with `Error::MemoryLimit`.
+    ///
+    /// Returns the identifier for this search.
+    pub fn add_fuzzy(&mut self, literal: &[u8], max_errors: usize) -> Result<usize, Error> {
+        let fuzzy = Fuzzy::new(literal, max_errors)?;
+        let memory = core::mem::size_of::<Masks>() + fuzzy.state.len() * core::mem::size_of::<u64>();
+        self.check_memory(memory)?;
+        Ok(self.push_search(Search::new(Engine::Fuzzy(fuzzy), memory)))
+    }
+}
+
+impl Fuzzy {
+    fn new(literal: &[u8], max_errors: usize) -> Result<Fuzzy, Error> {
+        if literal.len() > 64 || literal.len() <= max_errors {
+            return Err(Error::InvalidFuzzy);
+        }
+        let mut masks = Masks {
+            forward: [0; 256],
+            reverse: [0; 256],
+        };
+        for (i, &b) in literal.iter().enumerate() {
+            masks.forward[b as usize] |= 1 << i;
+            masks.reverse[b as usize] |= 1 << (literal.len() - 1 - i);
+        }
+        Ok(Fuzzy {
+            masks: Arc::new(masks),
+            len: literal.len(),
+            max_errors,
+            state: initial_state(max_errors),
+            best: None,
+        })
+    }
+
+    pub(crate) fn step(&mut self, input: u8) -> Step {
+        step_state(&mut self.state, self.masks.forward[input as usize], None);
+
+        let errors = self.errors();
+        match (errors, self.best) {
+            (None, _) => {
+                self.best = None;
+                Step::NoMatch
+            }
+            (Some(errors), Some(best)) if errors > best => Step::Hold,
+            (Some(errors), _) => {
+                self.best = Some(errors);
+                Step::Match
+            }
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.state = initial_state(self.max_errors);
+        self.best = None;
+    }
+
+    /// Whether no match is in progress: the state is the one every position starts with.
+    pub(crate) fn is_start(&self) -> bool {
+        self.best.is_none() && self.state.iter().enumerate().all(|(d, &r)| r == (1 << d) - 1)
+    }
+
+    /// fewest errors of a match ending at the current position
+    fn errors(&self) -> Option<usize> {
+        let top = 1 << (self.len - 1);
+        self.state.iter().position(|r| r & top != 0)
+    }
+
+    /// Length of the match with the fewest errors ending before the reversed input.
+    /// Of equally good matches, the shortest is chosen.
+    pub(crate) fn rfind(&self, bytes: impl Iterator<Item=(usize, u8)>) -> Option<usize> {
+        let top = 1 << (self.len - 1);
+        let mut state = initial_state(self.max_errors);
+        let mut best: Option<(usize, usize)> = None;
+        for (t, (i, b)) in bytes.enumerate() {
+            if t >= self.len + self.max_errors {
+                break;
+            }
+            step_state(&mut state, self.masks.reverse[b as usize], Some(t));
+            if let Some(errors) = state.iter().position(|r| r & top != 0) {
+                if best.is_none_or(|(e, _)| errors < e) {
+                    best = Some((errors, i + 1));
+                }
+            }
+        }
+        best.map(|(_, len)| len)
+    }
+}
+
+fn initial_state(max_errors: usize) -> Vec<u64> {
+    // the first `d` bytes of the literal can be deleted
+    (0 ..= max_errors).map(|d| (1u64 << d) - 1).collect()
+}
+
+/// Advance the state over a byte with the given mask.
+///
+/// `consumed` is the number of bytes before this one for anchored matching,
+/// or `None` if a match may start anywhere.
+fn step_state(state: &mut [u64], mask: u64, consumed: Option<usize>) {
+    // whether the empty prefix of the literal matches with `d` errors,
+    // before (`t` = 0) or after (`t` = 1) this byte
+    let empty = |d: usize, t: usize| consumed.map_or(1, |c| (c + t <= d) as u64);
+
+    let mut prev_old = 0;
+    let mut prev_new = 0;
+    for (d, r) in state.iter_mut().enumerate() {
+        let old = *r;
+        let mut new = ((old << 1) | empty(d, 0)) & mask;
+        if d > 0 {
+            // inserted byte
+            new |= prev_old;
+            // substituted byte
+            new |= (prev_old << 1) | empty(d - 1, 0);
+            // deleted byte of the literal
+            new |= (prev_new << 1) | empty(d - 1, 1);
+        }
+        *r = new;
+        prev_old = old;
+        prev_new = new;
+    }
+}
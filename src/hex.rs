@@ -0,0 +1,122 @@
+//! Binary signatures written as hex, like `DE AD ?? EF [4-12] 00`.
+//!
+//! Syntax:
+//!  - `DE`: the byte 0xDE
+//!  - `??`: any byte, `D?` and `?E`: any byte with the given high or low nibble
+//!  - `[4-12]`: a jump over 4 to 12 arbitrary bytes, `[4]` exactly 4, `[4-]` at least 4
+//!  - `( DE AD | BE EF )`: alternatives
+//!
+//! Whitespace between tokens is optional.
+
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::Error;
+
+/// Translate the hex signature `sig` into a regex for `RegexBuilder`s with Unicode disabled.
+///
+/// Fails with `InvalidHex` on malformed signatures.
+pub fn hex_to_regex(sig: &str) -> Result<String, Error> {
+    let mut regex = String::new();
+    let mut depth = 0usize;
+    let mut bytes = sig.bytes().filter(|b| !b.is_ascii_whitespace());
+    while let Some(b) = bytes.next() {
+        match b {
+            b'[' => {
+                let mut jump = String::new();
+                loop {
+                    match bytes.next() {
+                        Some(b']') => break,
+                        Some(b) if b.is_ascii_digit() || b == b'-' => jump.push(b as char),
+                        _ => return Err(Error::InvalidHex),
+                    }
+                }
+                let (min, max) = match jump.split_once('-') {
+                    Some((min, max)) => (min, max),
+                    None => (jump.as_str(), jump.as_str()),
+                };
+                let min: usize = min.parse().map_err(|_| Error::InvalidHex)?;
+                let max: Option<usize> = match max {
+                    "" => None,
+                    max => Some(max.parse().map_err(|_| Error::InvalidHex)?),
+                };
+                if max.is_some_and(|max| max < min) {
+                    return Err(Error::InvalidHex);
+                }
+                match max {
+                    Some(max) if max == min => write!(regex, "(?s:.){{{}}}", min),
+                    Some(max) => write!(regex, "(?s:.){{{},{}}}", min, max),
+                    None => write!(regex, "(?s:.){{{},}}", min),
+                }.unwrap();
+            }
+            b'(' => {
+                depth += 1;
+                regex.push_str("(?:");
+            }
+            b'|' if depth > 0 => regex.push('|'),
+            b')' if depth > 0 => {
+                depth -= 1;
+                regex.push(')');
+            }
+            high => {
+                let low = bytes.next().ok_or(Error::InvalidHex)?;
+                match (nibble(high)?, nibble(low)?) {
+                    (Some(high), Some(low)) => write!(regex, r"\x{:02X}", high << 4 | low).unwrap(),
+                    (Some(high), None) => write!(regex, r"[\x{:X}0-\x{:X}F]", high, high).unwrap(),
+                    (None, Some(low)) => {
+                        regex.push('[');
+                        for high in 0 .. 16 {
+                            write!(regex, r"\x{:02X}", high << 4 | low).unwrap();
+                        }
+                        regex.push(']');
+                    }
+                    (None, None) => regex.push_str("(?s:.)"),
+                }
+            }
+        }
+    }
+    if depth != 0 || regex.is_empty() {
+        return Err(Error::InvalidHex);
+    }
+    Ok(regex)
+}
+
+/// A regex matching `bytes` literally, for `RegexBuilder`s with Unicode disabled.
+#[cfg(any(feature = "yara", feature = "snort"))]
+pub(crate) fn literal_regex(bytes: &[u8]) -> String {
+    let mut regex = String::new();
+    for &b in bytes {
+        match b.is_ascii_alphanumeric() {
+            true => regex.push(b as char),
+            false => write!(regex, r"\x{:02X}", b).unwrap(),
+        }
+    }
+    regex
+}
+
+/// The value of a hex digit, `None` for `?`.
+fn nibble(b: u8) -> Result<Option<u8>, Error> {
+    match b {
+        b'?' => Ok(None),
+        _ => (b as char).to_digit(16).map(|d| Some(d as u8)).ok_or(Error::InvalidHex),
+    }
+}
+
+#[cfg(feature = "std")]
+impl crate::RingSearcher<regex_automata::DenseDFA<Vec<usize>, usize>> {
+    /// Add a search for the hex signature `sig`, see the `hex` module for the syntax.
+    ///
+    /// Returns the identifier for this search.
+    pub fn add_hex_str(&mut self, sig: &str) -> Result<usize, Error> {
+        let regex = regex_automata::RegexBuilder::new()
+            .unicode(false)
+            .allow_invalid_utf8(true)
+            .build(&hex_to_regex(sig)?)
+            .map_err(|_| Error::InvalidRegex)?;
+        let search_nr = self.add_dense_regex(regex)?;
+        self.searches[search_nr].pattern = Some(sig.into());
+        Ok(search_nr)
+    }
+}
@@ -0,0 +1,164 @@
+//! A shim mirroring the Hyperscan stream API.
+//!
+//! Meant to ease evaluating this crate as a replacement for Hyperscan:
+//! the functions correspond to `hs_compile_multi`, `hs_alloc_scratch`, `hs_open_stream`,
+//! `hs_scan_stream` and `hs_close_stream`.
+//!
+//! Differences:
+//!  - Matches are reported with the start offset (`from`) when it is known, not only with `HS_FLAG_SOM_LEFTMOST`.
+//!  - Matches are reported once per match, like `regex` does, not for every end offset.
+//!  - Matches longer than the buffer (`BUFFER_SIZE`) are reported with `from` = 0.
+//!  - `HS_FLAG_MULTILINE` is not supported: the DFAs can not match `^` and `$` at line boundaries.
+
+use regex_automata::DenseDFA;
+
+use crate::{RingSearcher, Error};
+
+pub const HS_FLAG_CASELESS: u32 = 1;
+pub const HS_FLAG_DOTALL: u32 = 2;
+/// Not supported, expressions with it fail to compile with `InvalidFlag('m')`.
+pub const HS_FLAG_MULTILINE: u32 = 4;
+pub const HS_FLAG_SINGLEMATCH: u32 = 8;
+/// Accepted for compatibility. The start of a match is always reported if known.
+pub const HS_FLAG_SOM_LEFTMOST: u32 = 256;
+
+/// Size of the buffer of each stream.
+pub const BUFFER_SIZE: usize = 1 << 20;
+
+/// Returned by the match callback.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Matching {
+    Continue,
+    Terminate,
+}
+
+/// The callback requested termination. The stream does not accept further data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScanTerminated;
+
+/// An expression failed to compile.
+#[derive(Debug)]
+pub struct CompileError {
+    /// index of the failed expression
+    pub expression: usize,
+    pub error: Error,
+}
+
+/// The compiled expressions.
+pub struct Database {
+    searcher: RingSearcher<DenseDFA<Vec<usize>, usize>>,
+    ids: Vec<u32>,
+    single_match: Vec<bool>,
+}
+
+/// Not needed by this implementation. Exists for API compatibility.
+#[derive(Debug, Default)]
+pub struct Scratch(());
+
+/// State of an open stream.
+pub struct Stream {
+    searcher: RingSearcher<DenseDFA<Vec<usize>, usize>>,
+    ids: Vec<u32>,
+    single_match: Vec<bool>,
+    /// SINGLEMATCH expressions that already matched
+    matched: Vec<bool>,
+    terminated: bool,
+}
+
+/// Compile the `expressions` with their `flags`. Matches are reported with the corresponding entry of `ids`.
+/// 
+/// Fails with `InvalidFlag('m')` for `HS_FLAG_MULTILINE`.
+pub fn compile_multi(expressions: &[&str], flags: &[u32], ids: &[u32]) -> Result<Database, CompileError> {
+    assert_eq!(expressions.len(), flags.len());
+    assert_eq!(expressions.len(), ids.len());
+
+    let mut searcher = RingSearcher::new(BUFFER_SIZE);
+    for (i, (&expression, &flags)) in expressions.iter().zip(flags).enumerate() {
+        let mut prefix = String::new();
+        if flags & HS_FLAG_CASELESS != 0 {
+            prefix.push('i');
+        }
+        if flags & HS_FLAG_DOTALL != 0 {
+            prefix.push('s');
+        }
+        if flags & HS_FLAG_MULTILINE != 0 {
+            return Err(CompileError { expression: i, error: Error::InvalidFlag('m') });
+        }
+        let pattern = if prefix.is_empty() {
+            expression.into()
+        } else {
+            format!("(?{}){}", prefix, expression)
+        };
+        searcher.add_regex_str(&pattern).map_err(|error| CompileError { expression: i, error })?;
+    }
+
+    Ok(Database {
+        searcher,
+        ids: ids.into(),
+        single_match: flags.iter().map(|&f| f & HS_FLAG_SINGLEMATCH != 0).collect(),
+    })
+}
+
+pub fn alloc_scratch(_db: &Database) -> Scratch {
+    Scratch(())
+}
+
+pub fn open_stream(db: &Database) -> Stream {
+    Stream {
+        searcher: db.searcher.clone(),
+        ids: db.ids.clone(),
+        single_match: db.single_match.clone(),
+        matched: vec![false; db.ids.len()],
+        terminated: false,
+    }
+}
+
+/// Scan the next block of data of the stream.
+/// 
+/// `on_event` receives (id, from, to, flags).
+pub fn scan_stream<F>(stream: &mut Stream, data: &[u8], _scratch: &mut Scratch, mut on_event: F) -> Result<(), ScanTerminated>
+    where F: FnMut(u32, u64, u64, u32) -> Matching
+{
+    if stream.terminated {
+        return Err(ScanTerminated);
+    }
+    for &b in data {
+        stream.searcher.push(b);
+        let Stream { ref searcher, ref ids, ref single_match, ref mut matched, ref mut terminated } = *stream;
+        for (search_nr, match_) in searcher.matches() {
+            if report(ids, single_match, matched, search_nr, match_.start(), match_.end, &mut on_event) == Matching::Terminate {
+                *terminated = true;
+                return Err(ScanTerminated);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Close the stream, reporting the matches at the end of the stream.
+pub fn close_stream<F>(stream: Stream, _scratch: &mut Scratch, mut on_event: F) -> Result<(), ScanTerminated>
+    where F: FnMut(u32, u64, u64, u32) -> Matching
+{
+    if stream.terminated {
+        return Err(ScanTerminated);
+    }
+    let Stream { searcher, ids, single_match, mut matched, .. } = stream;
+    for (search_nr, match_) in searcher.final_matches() {
+        if report(&ids, &single_match, &mut matched, search_nr, match_.start(), match_.end, &mut on_event) == Matching::Terminate {
+            return Err(ScanTerminated);
+        }
+    }
+    Ok(())
+}
+
+fn report<F>(ids: &[u32], single_match: &[bool], matched: &mut [bool], search_nr: usize, start: Option<usize>, end: usize, on_event: &mut F) -> Matching
+    where F: FnMut(u32, u64, u64, u32) -> Matching
+{
+    if single_match[search_nr] {
+        if matched[search_nr] {
+            return Matching::Continue;
+        }
+        matched[search_nr] = true;
+    }
+    on_event(ids[search_nr], start.unwrap_or(0) as u64, end as u64, 0)
+}
@@ -0,0 +1,117 @@
+//! Sources of input to scan.
+//!
+//! An `InputSource` hands out chunks until its input ends. `ReaderSource` adapts anything
+//! implementing `Read`, with constructors for files, stdin and TCP connections.
+//! Other sources, like message queues or serial ports, implement `InputSource` themselves.
+
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Stdin};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+
+use crate::Match;
+use crate::scanner::StreamScanner;
+
+/// size of the chunks read by `ReaderSource`
+pub const READ_SIZE: usize = 64 * 1024;
+
+/// A blocking source of chunks.
+pub trait InputSource {
+    /// Wait for the next chunk, `None` at the end of the input.
+    fn next_chunk(&mut self) -> io::Result<Option<&[u8]>>;
+
+    /// Where the input comes from, like a file name or peer address.
+    fn provenance(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<S: InputSource + ?Sized> InputSource for Box<S> {
+    fn next_chunk(&mut self) -> io::Result<Option<&[u8]>> {
+        (**self).next_chunk()
+    }
+
+    fn provenance(&self) -> Option<&str> {
+        (**self).provenance()
+    }
+}
+
+/// Reads chunks of up to `READ_SIZE` bytes from `R`.
+pub struct ReaderSource<R> {
+    reader: R,
+    buf: Vec<u8>,
+    provenance: Option<String>,
+}
+
+impl<R: Read> ReaderSource<R> {
+    pub fn new(reader: R, provenance: Option<String>) -> Self {
+        ReaderSource { reader, buf: vec![0; READ_SIZE], provenance }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl ReaderSource<File> {
+    /// Open the file at `path`, with the path as provenance.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        Ok(ReaderSource::new(File::open(path)?, Some(path.display().to_string())))
+    }
+}
+
+impl ReaderSource<Stdin> {
+    pub fn stdin() -> Self {
+        ReaderSource::new(io::stdin(), Some("stdin".into()))
+    }
+}
+
+impl ReaderSource<TcpStream> {
+    /// Connect to `addr`.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(ReaderSource::tcp(TcpStream::connect(addr)?))
+    }
+
+    /// Read from an established connection, with `tcp:` and the peer address as provenance.
+    pub fn tcp(stream: TcpStream) -> Self {
+        let provenance = match stream.peer_addr() {
+            Ok(addr) => format!("tcp:{}", addr),
+            Err(_) => "tcp:?".into(),
+        };
+        ReaderSource::new(stream, Some(provenance))
+    }
+}
+
+impl<R: Read> InputSource for ReaderSource<R> {
+    fn next_chunk(&mut self) -> io::Result<Option<&[u8]>> {
+        loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => return Ok(None),
+                Ok(len) => return Ok(Some(&self.buf[.. len])),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn provenance(&self) -> Option<&str> {
+        self.provenance.as_deref()
+    }
+}
+
+/// Scan all chunks of `source` with `scanner` until the input ends,
+/// then report the matches still in progress.
+///
+/// `report` is called with the provenance of the source, the pattern identifier and the match.
+pub fn scan_source(source: &mut dyn InputSource, scanner: &mut dyn StreamScanner, report: &mut dyn FnMut(Option<&str>, usize, Match)) -> io::Result<()> {
+    while let Some(chunk) = source.next_chunk()? {
+        let mut found = vec![];
+        scanner.scan(chunk, &mut |id, match_| found.push((id, match_)));
+        for (id, match_) in found {
+            report(source.provenance(), id, match_);
+        }
+    }
+    scanner.finish(&mut |id, match_| report(source.provenance(), id, match_));
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! Searching the systemd journal.
+//!
+//! Entries are read in the export format of `journalctl -o export`:
+//! fields are `NAME=value` lines, or for binary values `NAME`, a little endian `u64` length,
+//! the value and a `\n`. An empty line ends the entry.
+//!
+//! `JournalSearcher` pushes the selected fields of each entry, each followed by `\n`,
+//! and reports matches with the cursor of the entry they end in,
+//! so a search can be resumed with `journalctl --after-cursor`.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Match, CHUNK_PIECE};
+
+/// The fields of a journal entry, in the order they were read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl JournalEntry {
+    /// The value of the first field called `name`.
+    pub fn field(&self, name: &str) -> Option<&[u8]> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_slice())
+    }
+
+    /// The `__CURSOR` field.
+    pub fn cursor(&self) -> Option<&str> {
+        self.field("__CURSOR").and_then(|cursor| std::str::from_utf8(cursor).ok())
+    }
+}
+
+/// Reads entries in the export format.
+pub struct ExportReader<R> {
+    reader: R,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> ExportReader<R> {
+    pub fn new(reader: R) -> Self {
+        ExportReader { reader, line: vec![] }
+    }
+
+    /// The next entry, `None` at the end of the input.
+    pub fn next_entry(&mut self) -> io::Result<Option<JournalEntry>> {
+        let mut entry = JournalEntry::default();
+        loop {
+            self.line.clear();
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                return Ok(Some(entry).filter(|entry| !entry.fields.is_empty()));
+            }
+            let line = self.line.strip_suffix(b"\n").unwrap_or(&self.line);
+            if line.is_empty() {
+                if entry.fields.is_empty() {
+                    continue;
+                }
+                return Ok(Some(entry));
+            }
+            let field = match memchr::memchr(b'=', line) {
+                Some(i) => (field_name(&line[.. i])?, line[i + 1 ..].to_vec()),
+                None => {
+                    let name = field_name(line)?;
+                    let mut len = [0; 8];
+                    self.reader.read_exact(&mut len)?;
+                    let len = u64::from_le_bytes(len);
+                    // the length is not trusted: only what is actually there is allocated
+                    let mut value = vec![];
+                    (&mut self.reader).take(len).read_to_end(&mut value)?;
+                    if (value.len() as u64) < len {
+                        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated binary field"));
+                    }
+                    let mut newline = [0];
+                    self.reader.read_exact(&mut newline)?;
+                    if newline != *b"\n" {
+                        return Err(invalid("binary field not followed by a line break"));
+                    }
+                    (name, value)
+                }
+            };
+            entry.fields.push(field);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ExportReader<R> {
+    type Item = io::Result<JournalEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+fn field_name(name: &[u8]) -> io::Result<String> {
+    String::from_utf8(name.to_vec()).map_err(|_| invalid("field name is not UTF-8"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Run `journalctl -o export`, starting after `after_cursor` if given, and read its output.
+///
+/// With `follow`, journalctl keeps running and the reader waits for new entries.
+pub fn journalctl(after_cursor: Option<&str>, follow: bool) -> io::Result<(Child, ExportReader<BufReader<ChildStdout>>)> {
+    let mut command = Command::new("journalctl");
+    command.args(["-o", "export", "--no-pager"]);
+    if let Some(cursor) = after_cursor {
+        command.arg(format!("--after-cursor={}", cursor));
+    }
+    if follow {
+        command.arg("--follow");
+    }
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().expect("no stdout");
+    Ok((child, ExportReader::new(BufReader::new(stdout))))
+}
+
+/// A match in the journal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalMatch {
+    pub search_nr: usize,
+    /// position in the pushed fields
+    pub match_: Match,
+    /// cursor of the entry the match ends in
+    pub cursor: Option<String>,
+    /// the matched bytes, `None` if they were evicted from the buffer
+    pub data: Option<Vec<u8>>,
+}
+
+/// Searches the fields of journal entries.
+pub struct JournalSearcher<D: DFA> {
+    searcher: RingSearcher<D>,
+    fields: Vec<String>,
+    /// (stream position of its first byte, cursor) of the last entry pushed and the one before
+    previous: Option<(usize, Option<String>)>,
+    current: Option<(usize, Option<String>)>,
+}
+
+impl<D: DFA> JournalSearcher<D> {
+    /// Search the `MESSAGE` field of each entry with the searches of `searcher`.
+    pub fn new(searcher: RingSearcher<D>) -> Self {
+        JournalSearcher {
+            searcher,
+            fields: vec!["MESSAGE".into()],
+            previous: None,
+            current: None,
+        }
+    }
+
+    /// Search these fields instead of `MESSAGE`, in this order.
+    pub fn set_fields(&mut self, fields: impl IntoIterator<Item=impl Into<String>>) {
+        self.fields = fields.into_iter().map(Into::into).collect();
+    }
+
+    pub fn searcher(&self) -> &RingSearcher<D> {
+        &self.searcher
+    }
+
+    pub fn searcher_mut(&mut self) -> &mut RingSearcher<D> {
+        &mut self.searcher
+    }
+
+    /// Push the selected fields of `entry` and return the matches completed by them.
+    ///
+    /// Entries without any of the fields are skipped.
+    pub fn push_entry(&mut self, entry: &JournalEntry) -> Vec<JournalMatch> {
+        let mut found = vec![];
+        let values: Vec<&[u8]> = self.fields.iter().filter_map(|name| entry.field(name)).collect();
+        if values.is_empty() {
+            return found;
+        }
+        self.previous = self.current.take();
+        self.current = Some((self.searcher.position(), entry.cursor().map(String::from)));
+
+        // in pieces no larger than the buffer or `CHUNK_PIECE`, so the data of matches is still in the buffer
+        let piece_len = self.searcher.capacity().min(CHUNK_PIECE);
+        for value in values {
+            for piece in value.chunks(piece_len).chain([&b"\n"[..]]) {
+                self.searcher.push_slice(piece);
+                let matches: Vec<_> = self.searcher.drain_matches().collect();
+                self.collect(matches, &mut found);
+            }
+        }
+        found
+    }
+
+    /// The input ended, return the matches still in progress.
+    pub fn finish(&mut self) -> Vec<JournalMatch> {
+        let mut found = vec![];
+        let matches: Vec<_> = self.searcher.final_matches().collect();
+        self.collect(matches, &mut found);
+        found
+    }
+
+    fn collect(&self, matches: Vec<(usize, Match)>, found: &mut Vec<JournalMatch>) {
+        for (search_nr, match_) in matches {
+            // a match ending with an entry completes with the first byte of the next
+            let entry = match (&self.current, &self.previous) {
+                (Some((start, _)), Some(previous)) if match_.end <= *start => previous,
+                (Some(current), _) => current,
+                (None, _) => continue,
+            };
+            found.push(JournalMatch {
+                search_nr,
+                match_,
+                cursor: entry.1.clone(),
+                data: self.searcher.checked_match_data(&match_).ok().map(|data| data.to_vec()),
+            });
+        }
+    }
+}
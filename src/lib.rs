@@ -1,20 +1,482 @@
-use regex_automata::{Regex, RegexBuilder, DFA, DenseDFA};
-use std::collections::VecDeque;
-use std::borrow::Borrow;
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+use regex_automata::{Regex, DFA};
+#[cfg(feature = "std")]
+use regex_automata::{RegexBuilder, DenseDFA, StateID};
+use alloc::collections::{VecDeque, BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::{ControlFlow, Range};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+pub mod rules;
+
+#[cfg(feature = "yara")]
+pub mod yara;
+
+#[cfg(feature = "snort")]
+pub mod snort;
+
+#[cfg(feature = "hs-compat")]
+pub mod hyperscan;
+
+#[cfg(feature = "fancy")]
+pub mod fancy;
+
+#[cfg(feature = "tokio")]
+pub mod codec;
+
+#[cfg(feature = "futures")]
+pub mod stream;
+
+#[cfg(feature = "replay")]
+pub mod replay;
+
+#[cfg(feature = "journal")]
+pub mod journal;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+pub mod decode;
+
+pub mod carve;
+
+pub mod correlate;
+
+pub mod multiline;
+
+pub mod scanner;
+
+#[cfg(feature = "std")]
+pub mod input;
+
+#[cfg(feature = "std")]
+pub mod sink;
+
+#[cfg(feature = "std")]
+pub mod follow;
+
+pub mod hex;
+
+pub mod codegen;
+
+mod fuzzy;
+
+mod fixed;
+pub use fixed::FixedSearcher;
+
+mod fingerprint;
+pub use fingerprint::fingerprint;
+
+#[cfg(feature = "std")]
+mod spill;
+
+#[cfg(feature = "std")]
+mod persist;
+
+#[cfg(feature = "std")]
+pub mod cache;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+
+#[cfg(feature = "profile")]
+mod profile;
+#[cfg(feature = "profile")]
+pub use profile::{SearchProfile, Budget};
+
+pub mod registry;
+pub use registry::{PatternRegistry, PatternHandle};
+
+#[cfg(feature = "macros")]
+pub use regex_ring_macros::patterns;
+
+/// used by the code `patterns!` generates
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub type DenseSearcher = crate::RingSearcher<regex_automata::DenseDFA<alloc::vec::Vec<usize>, usize>>;
+}
 
 // state to keep for each Regex
 struct Search<D: DFA> {
-    regex: Regex<D>,
-    state_id: D::ID,
-    was_match: bool,
-    is_match: bool,
+    engine: Engine<D>,
+    /// stream position after the last byte of the match in progress
+    match_end: Option<usize>,
+    /// memory used by the DFAs, if known
+    memory: usize,
+    /// number of most recent bytes this search looks at, if different from the default
+    window: Option<usize>,
+    /// not stepped, the state is frozen
+    paused: bool,
+    /// only stepped inside this region
+    region: Option<usize>,
+    /// (search, distance): matches only count if that search matched at most distance bytes before their start
+    preceded_by: Option<(usize, usize)>,
+    /// end of the last reported match
+    last_end: Option<usize>,
+    /// reset at record delimiters, if different from the searcher setting
+    record_reset: Option<bool>,
+    /// the bytes leaving the start state, if few enough to skip to them with memchr
+    start_bytes: Option<Vec<u8>>,
+    /// the source of the pattern, if added as a string
+    pattern: Option<Arc<str>>,
+    /// start of the match in progress, once the buffer is evicted near it
+    overflow_start: Option<usize>,
+    /// data of the match in progress was passed to the overflow callback
+    overflowing: bool,
+    /// ends of completed matches that were passed to the overflow callback, not yet resolved
+    overflowed: Vec<usize>,
+    /// stream position where the search last left its start state, kept in the buffer by pinning
+    pin_start: Option<usize>,
+    /// only started at flagged positions
+    anchor: Option<Anchor>,
+    /// stream position the search was added at, its matches do not start before it
+    since: usize,
+    /// the DFAs only approximate the pattern, candidates are confirmed with this regex
+    #[cfg(feature = "fancy")]
+    confirm: Option<Arc<fancy_regex::Regex>>,
+    #[cfg(feature = "profile")]
+    profile: profile::SearchProfile,
+    /// paused for exceeding the budget
+    #[cfg(feature = "profile")]
+    quarantined: bool,
+}
+
+/// what steps through the stream for a search
+enum Engine<D: DFA> {
+    Dfa {
+        regex: Arc<Regex<D>>,
+        state_id: D::ID,
+    },
+    Fuzzy(fuzzy::Fuzzy),
+    /// the search was removed, its identifier stays taken
+    Removed,
+}
+
+// derive would require D: Clone
+impl<D: DFA> Clone for Search<D> {
+    fn clone(&self) -> Self {
+        Search {
+            engine: self.engine.clone(),
+            match_end: self.match_end,
+            memory: self.memory,
+            window: self.window,
+            paused: self.paused,
+            region: self.region,
+            preceded_by: self.preceded_by,
+            last_end: self.last_end,
+            record_reset: self.record_reset,
+            start_bytes: self.start_bytes.clone(),
+            pattern: self.pattern.clone(),
+            overflow_start: self.overflow_start,
+            overflowing: self.overflowing,
+            overflowed: self.overflowed.clone(),
+            pin_start: self.pin_start,
+            anchor: self.anchor.clone(),
+            since: self.since,
+            #[cfg(feature = "fancy")]
+            confirm: self.confirm.clone(),
+            #[cfg(feature = "profile")]
+            profile: self.profile,
+            #[cfg(feature = "profile")]
+            quarantined: self.quarantined,
+        }
+    }
+}
+
+impl<D: DFA> Clone for Engine<D> {
+    fn clone(&self) -> Self {
+        match *self {
+            Engine::Dfa { ref regex, state_id } => Engine::Dfa { regex: regex.clone(), state_id },
+            Engine::Fuzzy(ref fuzzy) => Engine::Fuzzy(fuzzy.clone()),
+            Engine::Removed => Engine::Removed,
+        }
+    }
+}
+
+impl<D: DFA> Search<D> {
+    fn new(engine: Engine<D>, memory: usize) -> Self {
+        Search {
+            start_bytes: engine.start_bytes(),
+            pattern: None,
+            overflow_start: None,
+            overflowing: false,
+            overflowed: vec![],
+            pin_start: None,
+            anchor: None,
+            since: 0,
+            engine,
+            match_end: None,
+            memory,
+            window: None,
+            paused: false,
+            region: None,
+            preceded_by: None,
+            last_end: None,
+            record_reset: None,
+            #[cfg(feature = "fancy")]
+            confirm: None,
+            #[cfg(feature = "profile")]
+            profile: Default::default(),
+            #[cfg(feature = "profile")]
+            quarantined: false,
+        }
+    }
+
+    /// Step over `input`, which ends at stream `position`.
+    /// Returns the end position of a match that was completed by this byte.
+    fn step(&mut self, input: u8, position: usize) -> Option<usize> {
+        let step = self.engine.step(input);
+        #[cfg(feature = "profile")]
+        if !self.engine.is_start() {
+            self.profile.active_bytes += 1;
+        }
+        let end = match step {
+            Step::Match => {
+                self.match_end = Some(position);
+                None
+            }
+            Step::Hold => None,
+            Step::NoMatch => self.finish_match(),
+            Step::Rematch => {
+                let end = self.finish_match();
+                self.match_end = Some(position);
+                end
+            }
+        };
+        self.track_pin(position);
+        end
+    }
+
+    /// Step over the bytes `from .. to` of `piece`.
+    /// Completed matches are added to `completed` as (`search_nr`, end position).
+    fn step_range(&mut self, search_nr: usize, piece: &Piece, from: usize, to: usize, completed: &mut Vec<(usize, usize)>) {
+        let reset = self.record_reset.unwrap_or(piece.record_reset);
+        let mut j = from;
+        while j < to {
+            // an idle search only needs to look at the bytes leaving the start state.
+            // resetting it at a delimiter would not change anything.
+            if let Some(ref bytes) = self.start_bytes {
+                if self.is_idle() {
+                    match find_any(bytes, &piece.input[j .. to]) {
+                        Some(k) => j += k,
+                        None => break,
+                    }
+                }
+            }
+            let (raw, b) = (piece.raw[j], piece.input[j]);
+            j += 1;
+            let end = if reset && raw == piece.delimiter {
+                self.engine.reset();
+                self.finish_match()
+            } else {
+                self.step(b, piece.start + j)
+            };
+            if let Some(end) = end {
+                completed.push((search_nr, end));
+            }
+        }
+    }
+
+    /// Complete the match in progress. Returns its end.
+    fn finish_match(&mut self) -> Option<usize> {
+        let end = self.match_end.take();
+        if self.overflowing {
+            // its start is no longer in the buffer
+            self.overflowed.extend(end);
+        }
+        self.overflow_start = None;
+        self.overflowing = false;
+        end
+    }
+
+    /// Update `pin_start` after stepping over the byte ending at `position`.
+    /// 
+    /// A search that restarts without passing its start state keeps its earlier `pin_start`.
+    fn track_pin(&mut self, position: usize) {
+        if self.is_idle() {
+            self.pin_start = None;
+        } else if self.pin_start.is_none() {
+            self.pin_start = Some(position - 1);
+        }
+    }
+
+    /// in the start state, with no match in progress
+    fn is_idle(&self) -> bool {
+        self.match_end.is_none() && self.engine.is_start()
+    }
+}
+
+/// a piece of input, as seen by `Search::step_range`
+struct Piece<'a> {
+    raw: &'a [u8],
+    /// after the byte map
+    input: &'a [u8],
+    /// stream position of the first byte
+    start: usize,
+    delimiter: u8,
+    record_reset: bool,
+}
+
+/// A section of the stream, from the end of a match of `enter` to the end of the next match of `leave`.
+#[derive(Clone)]
+struct Region {
+    enter: usize,
+    leave: usize,
+    inside: bool,
+}
+
+/// The flagged start positions of an anchored search.
+#[derive(Clone)]
+struct Anchor {
+    /// matches of this search flag their end
+    source: Option<usize>,
+    /// flagged positions not tried yet
+    pending: BTreeSet<usize>,
+    /// start of the candidate in progress
+    start: Option<usize>,
+    /// stream position the candidate in progress was stepped to
+    scanned: usize,
+}
+
+/// result of stepping an engine over one byte
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Step {
+    NoMatch,
+    Match,
+    /// still in a match, but not a better end for it
+    Hold,
+    /// the match in progress ended before this byte, a new one ends at it
+    Rematch,
+}
+
+impl<D: DFA> Engine<D> {
+    /// Step the forward direction over `input`.
+    fn step(&mut self, input: u8) -> Step {
+        match *self {
+            Engine::Dfa { ref regex, ref mut state_id } => dfa_step(regex.forward(), state_id, input),
+            Engine::Fuzzy(ref mut fuzzy) => fuzzy.step(input),
+            Engine::Removed => Step::NoMatch,
+        }
+    }
+
+    /// The result of stepping over `input`, without changing the state.
+    fn peek(&self, input: u8) -> Step {
+        match *self {
+            Engine::Dfa { ref regex, state_id } => {
+                let dfa = regex.forward();
+                if dfa.is_match_state(dfa.next_state(state_id, input)) { Step::Match } else { Step::NoMatch }
+            }
+            Engine::Fuzzy(ref fuzzy) => fuzzy.clone().step(input),
+            Engine::Removed => Step::NoMatch,
+        }
+    }
+
+    /// Step the forward direction over `input` without restarting.
+    /// Returns whether it is in a match, or `None` once it can not match anymore.
+    fn step_anchored(&mut self, input: u8) -> Option<bool> {
+        match *self {
+            Engine::Dfa { ref regex, ref mut state_id } => {
+                let dfa = regex.forward();
+                *state_id = dfa.next_state(*state_id, input);
+                match dfa.is_dead_state(*state_id) {
+                    true => None,
+                    false => Some(dfa.is_match_state(*state_id)),
+                }
+            }
+            Engine::Fuzzy(_) | Engine::Removed => None,
+        }
+    }
+
+    fn is_anchored(&self) -> bool {
+        match *self {
+            Engine::Dfa { ref regex, .. } => regex.forward().is_anchored(),
+            Engine::Fuzzy(_) | Engine::Removed => false,
+        }
+    }
+
+    /// back to the start state
+    fn reset(&mut self) {
+        match *self {
+            Engine::Dfa { ref regex, ref mut state_id } => *state_id = regex.forward().start_state(),
+            Engine::Fuzzy(ref mut fuzzy) => fuzzy.reset(),
+            Engine::Removed => {}
+        }
+    }
+
+    fn is_start(&self) -> bool {
+        match *self {
+            Engine::Dfa { ref regex, state_id } => state_id == regex.forward().start_state(),
+            Engine::Fuzzy(ref fuzzy) => fuzzy.is_start(),
+            Engine::Removed => true,
+        }
+    }
+
+    /// The bytes that can start a match, if there are at most three.
+    fn start_bytes(&self) -> Option<Vec<u8>> {
+        match *self {
+            Engine::Dfa { ref regex, .. } => start_bytes(regex.forward()),
+            // every byte may be a substitution
+            Engine::Fuzzy(_) => None,
+            // nothing leaves the start state
+            Engine::Removed => Some(vec![]),
+        }
+    }
+
+    /// Length of the match ending before the reversed input.
+    fn rfind(&self, bytes: impl Iterator<Item=(usize, u8)>) -> Option<usize> {
+        match *self {
+            Engine::Dfa { ref regex, .. } => rfind_iter(regex.reverse(), bytes),
+            Engine::Fuzzy(ref fuzzy) => fuzzy.rfind(bytes),
+            Engine::Removed => None,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Error {
-    InvalidRegex
+    InvalidRegex,
+    /// Adding the regex would exceed the memory limit
+    MemoryLimit,
+    /// The literal of a fuzzy search is empty, longer than 64 bytes or not longer than the allowed errors
+    InvalidFuzzy,
+    /// All search slots of a `FixedSearcher` are in use
+    TooManySearches,
+    /// Unknown or unsupported regex flag letter
+    InvalidFlag(char),
+    /// The handle does not refer to a pattern of the registry
+    UnknownPattern,
+    /// Malformed hex signature
+    InvalidHex,
+    /// The regex can match the empty string
+    EmptyMatch,
+    /// Input was pushed after `finish`
+    Finished,
+    /// A rule file could not be read or parsed
+    #[cfg(any(feature = "yaml", feature = "toml", feature = "yara", feature = "snort"))]
+    InvalidRules(String),
 }
 
+/// Why a pattern of a bulk load was not added.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PatternError {
+    pub error: Error,
+    /// description of the problem, like the syntax error of the regex
+    pub reason: String,
+}
+
+/// Size of the pieces `push_slice` splits chunks into.
+pub const CHUNK_PIECE: usize = 4096;
+
 /// A Ringbuffer backed steam searcher
 /// 
 /// Usage:
@@ -32,7 +494,165 @@ pub struct RingSearcher<D: DFA> {
     buffer: VecDeque<u8>,
     position: usize,
     searches: Vec<Search<D>>,
+    /// size of the ring: the largest window of all searches
     buffer_size: usize,
+    /// window of searches without their own
+    default_window: usize,
+    /// never evict, the default window is unlimited
+    unbounded: bool,
+    /// (search identifier, end position) of the matches completed, but not yet resolved
+    completed: Vec<(usize, usize)>,
+    /// the matches completed by the last `push` or `push_slice`
+    found: Vec<(usize, Match)>,
+    crlf: bool,
+    /// a `\r` was pushed, but not yet fed to the DFAs
+    pending_cr: bool,
+    delimiter: u8,
+    record_reset: bool,
+    records: usize,
+    memory_limit: Option<usize>,
+    /// the buffer size the memory limit allowed, if smaller than requested
+    truncated: Option<usize>,
+    /// applied to the input bytes before the searches see them: `user_byte_map`, then the case fold
+    byte_map: Option<Arc<[u8; 256]>>,
+    /// the map set with `set_byte_map`
+    user_byte_map: Option<Arc<[u8; 256]>>,
+    case_fold: bool,
+    /// receives the evicted data of matches in progress
+    overflow: Option<OverflowCallback>,
+    /// receives the matches as they are found
+    on_match: Option<MatchCallback>,
+    /// receives all evicted data
+    evicted: Option<EvictionCallback>,
+    /// keeps evicted data on disk
+    #[cfg(feature = "std")]
+    spill: Option<spill::Spill>,
+    lines: Option<Lines>,
+    /// stops the scanning helpers once set
+    cancel: Option<Arc<AtomicBool>>,
+    /// number of matches found so far
+    match_count: usize,
+    regions: Vec<Region>,
+    /// include fingerprints in match records
+    fingerprints: bool,
+    /// (interval in bytes, callback)
+    progress: Option<(usize, ProgressCallback)>,
+    /// stream position at which the progress callback is called next
+    next_progress: usize,
+    /// largest size the buffer may grow to keep the bytes of matches in progress
+    pin_limit: Option<usize>,
+    /// keep matches and their data until drained
+    queue_matches: bool,
+    /// searches exceeding it are quarantined
+    #[cfg(feature = "profile")]
+    budget: Option<profile::Budget>,
+    /// the stream ended with `finish`
+    finished: bool,
+}
+
+/// Statistics passed to the progress callback.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// number of bytes pushed so far
+    pub position: usize,
+    /// number of matches found so far
+    pub matches: usize,
+    /// number of record delimiters seen so far
+    pub records: usize,
+}
+
+/// newline counting for `line_col`
+#[derive(Clone)]
+struct Lines {
+    /// newlines pushed since tracking started
+    count: usize,
+    /// stream position after the last evicted newline
+    evicted_line_start: usize,
+}
+
+/// Line and column of a stream position, both counted from 1. Columns count bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// (search identifier, stream position, data)
+type OverflowCallback = Arc<dyn Fn(usize, usize, &[u8]) + Send + Sync>;
+
+/// (search identifier, match, data)
+type MatchCallback = Arc<dyn Fn(usize, &Match, MatchData) + Send + Sync>;
+
+/// (stream position, data)
+type EvictionCallback = Arc<dyn Fn(usize, &[u8]) + Send + Sync>;
+
+type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// Cloning is cheap in terms of patterns: the compiled regexes are shared.
+/// The buffer and the state of all searches are copied.
+impl<D: DFA> Clone for RingSearcher<D> {
+    fn clone(&self) -> Self {
+        RingSearcher {
+            buffer: self.buffer.clone(),
+            position: self.position,
+            searches: self.searches.clone(),
+            buffer_size: self.buffer_size,
+            default_window: self.default_window,
+            unbounded: self.unbounded,
+            completed: self.completed.clone(),
+            found: self.found.clone(),
+            crlf: self.crlf,
+            pending_cr: self.pending_cr,
+            delimiter: self.delimiter,
+            record_reset: self.record_reset,
+            records: self.records,
+            memory_limit: self.memory_limit,
+            truncated: self.truncated,
+            byte_map: self.byte_map.clone(),
+            user_byte_map: self.user_byte_map.clone(),
+            case_fold: self.case_fold,
+            overflow: self.overflow.clone(),
+            on_match: self.on_match.clone(),
+            evicted: self.evicted.clone(),
+            #[cfg(feature = "std")]
+            spill: None,
+            lines: self.lines.clone(),
+            cancel: self.cancel.clone(),
+            match_count: self.match_count,
+            regions: self.regions.clone(),
+            fingerprints: self.fingerprints,
+            progress: self.progress.clone(),
+            next_progress: self.next_progress,
+            pin_limit: self.pin_limit,
+            queue_matches: self.queue_matches,
+            #[cfg(feature = "profile")]
+            budget: self.budget,
+            finished: self.finished,
+        }
+    }
+}
+
+/// A summary of the state, not the buffer contents or the patterns.
+impl<D: DFA> fmt::Debug for RingSearcher<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let in_match: Vec<usize> = self.searches.iter().enumerate()
+            .filter(|(_, search)| search.match_end.is_some())
+            .map(|(i, _)| i)
+            .collect();
+        let paused: Vec<usize> = self.searches.iter().enumerate()
+            .filter(|(_, search)| search.paused)
+            .map(|(i, _)| i)
+            .collect();
+        f.debug_struct("RingSearcher")
+            .field("position", &self.position)
+            .field("buffered", &self.buffer.len())
+            .field("buffer_size", &self.buffer_size)
+            .field("searches", &self.searches.len())
+            .field("in_match", &in_match)
+            .field("paused", &paused)
+            .field("records", &self.records)
+            .finish()
+    }
 }
 
 impl<D: DFA> RingSearcher<D> {
@@ -41,118 +661,1284 @@ impl<D: DFA> RingSearcher<D> {
     pub fn new(buffer_size: usize) -> Self {
         RingSearcher {
             searches: vec![],
-            buffer: VecDeque::with_capacity(buffer_size),
+            buffer: VecDeque::new(),
             position: 0,
             buffer_size,
+            default_window: buffer_size,
+            unbounded: false,
+            completed: vec![],
+            found: vec![],
+            crlf: false,
+            pending_cr: false,
+            delimiter: b'\n',
+            record_reset: false,
+            records: 0,
+            memory_limit: None,
+            truncated: None,
+            byte_map: None,
+            user_byte_map: None,
+            case_fold: false,
+            overflow: None,
+            on_match: None,
+            evicted: None,
+            #[cfg(feature = "std")]
+            spill: None,
+            lines: None,
+            cancel: None,
+            match_count: 0,
+            regions: vec![],
+            fingerprints: false,
+            progress: None,
+            next_progress: 0,
+            pin_limit: None,
+            queue_matches: false,
+            #[cfg(feature = "profile")]
+            budget: None,
+            finished: false,
+        }
+    }
+
+    /// Fold ASCII letters to lowercase before searching.
+    /// 
+    /// A cheap way to search case-insensitively: the patterns have to be written in lowercase.
+    /// Positions and match data still refer to the original input.
+    /// Combines with a byte map set with `set_byte_map`, which is applied first.
+    pub fn set_ascii_case_fold(&mut self, fold: bool) {
+        self.case_fold = fold;
+        self.update_byte_map();
+    }
+
+    /// Apply `map` to every input byte before searching,
+    /// for example to map whitespace variants together or to strip the high bit.
+    /// 
+    /// `map` is evaluated for all 256 byte values once and stored as a table.
+    /// Positions and match data still refer to the original input.
+    /// Replaces an earlier map, and is followed by `set_ascii_case_fold` if enabled.
+    pub fn set_byte_map<F: Fn(u8) -> u8>(&mut self, map: F) {
+        let mut table = [0; 256];
+        for (i, m) in table.iter_mut().enumerate() {
+            *m = map(i as u8);
         }
+        self.user_byte_map = Some(Arc::new(table));
+        self.update_byte_map();
+    }
+
+    /// Search the input bytes as they are, apart from `set_ascii_case_fold`.
+    pub fn clear_byte_map(&mut self) {
+        self.user_byte_map = None;
+        self.update_byte_map();
+    }
+
+    /// Compose `byte_map` from the user map and the case fold.
+    fn update_byte_map(&mut self) {
+        self.byte_map = match (&self.user_byte_map, self.case_fold) {
+            (map, false) => map.clone(),
+            (map, true) => {
+                let mut table = [0; 256];
+                for (i, m) in table.iter_mut().enumerate() {
+                    let b = map.as_ref().map_or(i as u8, |map| map[i]);
+                    *m = b.to_ascii_lowercase();
+                }
+                Some(Arc::new(table))
+            }
+        };
+    }
+
+    /// Call `callback` with the data of matches in progress that is about to be evicted from the buffer,
+    /// so matches longer than the buffer can be reconstructed:
+    /// the data passed for a search, followed by the `match_data` of its completed match, is the whole match.
+    /// 
+    /// The callback receives the search identifier, the stream position of the data and the data.
+    /// The data may extend past the end of the match, if the match does not grow any further: cut it at `Match::end`.
+    /// 
+    /// Only data of matches that already matched once is passed,
+    /// so for a pattern like `BEGIN.*END` there is nothing before the first `END`.
+    /// With a callback, `push_slice` no longer searches large chunks in place.
+    pub fn set_overflow_callback<F>(&mut self, callback: F) where F: Fn(usize, usize, &[u8]) + Send + Sync + 'static {
+        self.overflow = Some(Arc::new(callback));
+    }
+
+    pub fn clear_overflow_callback(&mut self) {
+        self.overflow = None;
+    }
+
+    /// Call `callback` for every match as `push` and `push_slice` find it, with the search identifier,
+    /// the match and its data, so a loop feeding the searcher does not need to poll `matches`.
+    /// 
+    /// The matches are still available from `matches` as well. `final_matches` are not passed to the callback.
+    /// With a callback, `push_slice` no longer searches large chunks in place.
+    pub fn set_match_callback<F>(&mut self, callback: F) where F: Fn(usize, &Match, MatchData) + Send + Sync + 'static {
+        self.on_match = Some(Arc::new(callback));
+    }
+
+    pub fn clear_match_callback(&mut self) {
+        self.on_match = None;
+    }
+
+    /// Call `callback` with all data leaving the buffer, in stream order,
+    /// for example to archive the stream or keep a longer history elsewhere.
+    /// 
+    /// The callback receives the stream position of the data and the data.
+    /// Data still in the buffer when the searcher is dropped is not passed.
+    pub fn set_eviction_callback<F>(&mut self, callback: F) where F: Fn(usize, &[u8]) + Send + Sync + 'static {
+        self.evicted = Some(Arc::new(callback));
+    }
+
+    pub fn clear_eviction_callback(&mut self) {
+        self.evicted = None;
+    }
+
+    /// Stop `input_matches`, `scan_reader` and their variants once `flag` is set, for example from another thread.
+    /// 
+    /// The flag is checked every `CHUNK_PIECE` bytes. A cancelled scan returns without reporting the final matches,
+    /// `scan_reader` with an error. Clones share the flag.
+    pub fn set_cancel_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancel = Some(flag);
+    }
+
+    pub fn clear_cancel_flag(&mut self) {
+        self.cancel = None;
+    }
+
+    /// Call `callback` every `interval` input bytes, for progress bars and heartbeats.
+    /// 
+    /// `push_slice` calls it at most once per piece of `CHUNK_PIECE` bytes.
+    pub fn set_progress_callback<F>(&mut self, interval: usize, callback: F) where F: Fn(&Progress) + Send + Sync + 'static {
+        let interval = interval.max(1);
+        self.progress = Some((interval, Arc::new(callback)));
+        self.next_progress = self.position + interval;
+    }
+
+    pub fn clear_progress_callback(&mut self) {
+        self.progress = None;
+    }
+
+    /// Whether the cancel flag is set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Limit the memory used by the buffer and the DFAs to `limit` bytes.
+    /// 
+    /// Adding a regex that would exceed the limit fails with `Error::MemoryLimit`.
+    /// If the buffer can not grow to the requested size, it stays smaller and `truncated` reports it.
+    /// The memory of DFAs added with `add_regex` is not known and not counted.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    /// Let the buffer grow without evicting anything, so the data of all matches is available.
+    /// 
+    /// The buffer then holds the whole stream, which is only sensible for bounded inputs.
+    /// Windows set with `set_window` still limit the matches of their search,
+    /// and the memory limit still applies.
+    pub fn set_unbounded(&mut self, unbounded: bool) {
+        self.unbounded = unbounded;
+        self.update_buffer_size();
+    }
+
+    /// Keep the bytes of matches in progress in the buffer, from where their search left its start state,
+    /// growing the buffer up to about `limit` bytes if needed.
+    /// 
+    /// The start and data of long matches are then not lost just because other data flowed through the buffer.
+    /// Patterns like `a.*b` leave their start state on any `a`, so pinning keeps a lot for them.
+    /// The buffer shrinks back once the matches complete. `None` turns pinning off.
+    /// The memory limit takes precedence: pinned bytes are evicted when the buffer can not grow.
+    pub fn set_pinning(&mut self, limit: Option<usize>) {
+        self.pin_limit = limit;
+    }
+
+    /// Keep the matches found by `push` and `push_slice` until `drain_matches`,
+    /// instead of replacing them with the matches of the next push.
+    /// 
+    /// The data of queued matches stays in the buffer, which grows as needed,
+    /// so `match_data` works for drained matches until the next push.
+    /// Drain regularly, queued matches hold on to their data.
+    /// Within the memory limit, the data of queued matches may still be evicted, see `checked_match_data`.
+    pub fn set_match_queue(&mut self, queue: bool) {
+        self.queue_matches = queue;
+    }
+
+    fn clear_found(&mut self) {
+        if !self.queue_matches {
+            self.found.clear();
+        }
+    }
+
+    fn default_window(&self) -> usize {
+        if self.unbounded { usize::MAX } else { self.default_window }
+    }
+
+    /// Memory used by the buffer and the DFAs, as far as known.
+    pub fn memory_usage(&self) -> usize {
+        self.buffer.capacity() + self.searches.iter().map(|s| s.memory).sum::<usize>()
+    }
+
+    /// Whether the buffer was kept smaller than requested to stay within the memory limit.
+    /// 
+    /// Matches longer than the buffer will have no `start`.
+    pub fn truncated(&self) -> bool {
+        self.truncated.is_some()
+    }
+
+    /// Only keep the last `window` bytes for the search `search_nr`.
+    /// 
+    /// Matches of this search will not start before its window.
+    /// The ring buffer only has to hold the largest window, so giving searches
+    /// with short matches a small window reduces the memory needed.
+    pub fn set_window(&mut self, search_nr: usize, window: usize) {
+        self.searches[search_nr].window = Some(window);
+        self.update_buffer_size();
+    }
+
+    fn update_buffer_size(&mut self) {
+        let default_window = self.default_window();
+        let mut size = self.searches.iter()
+            .map(|s| s.window.unwrap_or(default_window))
+            .max()
+            .unwrap_or(default_window);
+        if let Some(limit) = self.truncated {
+            size = size.min(limit);
+        }
+        self.buffer_size = size;
+        self.evict(self.buffer.len().saturating_sub(size));
+    }
+
+    /// Set the byte that terminates records (lines). Defaults to `\n`.
+    /// 
+    /// Use `\0` for NUL-delimited input, like the output of `find -print0`.
+    pub fn set_record_delimiter(&mut self, delimiter: u8) {
+        self.delimiter = delimiter;
+    }
+
+    /// Reset all searches at every record delimiter.
+    /// 
+    /// Matches then never span records and never include the delimiter.
+    /// Searches that do not need to look across records also do less work this way.
+    pub fn set_record_reset(&mut self, reset: bool) {
+        self.record_reset = reset;
+    }
+
+    /// Whether the search `search_nr` resets at record delimiters, overriding `set_record_reset`.
+    pub fn set_search_record_reset(&mut self, search_nr: usize, reset: bool) {
+        self.searches[search_nr].record_reset = Some(reset);
+    }
+
+    /// An independent copy of the stream state (buffer and search states) for speculative scanning,
+    /// for example to try two interpretations of ambiguous input and keep one.
+    /// 
+    /// Like `clone`, the compiled patterns are shared.
+    /// The fork does not call the overflow, eviction and progress callbacks and does not spill,
+    /// so the discarded interpretation does not end up in the archive.
+    pub fn fork(&self) -> Self {
+        let mut fork = self.clone();
+        fork.overflow = None;
+        fork.evicted = None;
+        fork.progress = None;
+        fork
+    }
+
+    /// Number of searches added.
+    pub fn num_searches(&self) -> usize {
+        self.searches.len()
+    }
+
+    /// The source of the pattern of search `search_nr`, if it was added as a string.
+    pub fn pattern(&self, search_nr: usize) -> Option<&str> {
+        self.searches.get(search_nr)?.pattern.as_deref()
+    }
+
+    /// Number of bytes currently in the buffer.
+    pub fn buffer_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Number of bytes the buffer holds at most, the largest window. `usize::MAX` if unbounded.
+    pub fn capacity(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Number of bytes pushed so far, the stream position of the next byte.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of record delimiters seen so far.
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Count newlines, so `line_col` can map stream positions to lines and columns.
+    /// Lines are counted from the start of the buffer when tracking is enabled.
+    pub fn set_line_tracking(&mut self, track: bool) {
+        self.lines = match (track, self.lines.take()) {
+            (true, Some(lines)) => Some(lines),
+            (true, None) => Some(Lines {
+                count: self.buffer.iter().filter(|&&b| b == b'\n').count(),
+                evicted_line_start: self.position - self.buffer.len(),
+            }),
+            (false, _) => None,
+        };
+    }
+
+    /// Line and column of stream `position`, for example of `match_.end`.
+    /// 
+    /// `None` without line tracking or if `position` is no longer in the buffer.
+    pub fn line_col(&self, position: usize) -> Option<LineCol> {
+        let lines = self.lines.as_ref()?;
+        // first data byte in self.buffer is at this stream position
+        let offset = self.position - self.buffer.len();
+        if position < offset || position > self.position {
+            return None;
+        }
+        let i = position - offset;
+        let after = self.buffer.range(i ..).filter(|&&b| b == b'\n').count();
+        let line_start = match self.buffer.range(.. i).rposition(|&b| b == b'\n') {
+            Some(j) => offset + j + 1,
+            None => lines.evicted_line_start,
+        };
+        Some(LineCol {
+            line: lines.count - after + 1,
+            column: position - line_start + 1,
+        })
+    }
+
+    /// Treat `\r\n` as `\n` for matching purposes.
+    /// 
+    /// The reported positions and match data still refer to the original input.
+    /// Should be set before the first `push`.
+    pub fn set_crlf(&mut self, crlf: bool) {
+        self.crlf = crlf;
     }
 
     /// add a Regex to search for
     /// 
+    /// Searches can be added at any time, for example in response to earlier matches.
+    /// A search added mid-stream starts at the current position: its matches do not start in data pushed before.
+    /// 
+    /// Empty matches have no place in a stream and are not reported.
+    /// The other functions adding searches reject regexes that can match the empty string with `EmptyMatch`.
+    /// 
     /// Returns the identifier for this search.
     /// The identifiers will be 0, 1, ...
     pub fn add_regex(&mut self, regex: Regex<D>) -> usize {
+        self.add_search(regex, 0)
+    }
+
+    /// Replace all searches with the given regexes, keeping the buffer and stream position.
+    /// 
+    /// The new searches get the identifiers 0, 1, ... and start at the current position.
+    /// The old searches end as with `finish`: their matches not yet drained, including those in progress,
+    /// are returned instead of being yielded by `matches`, where they would pass for matches of the new searches.
+    /// Per-search windows are dropped.
+    /// 
+    /// Fails with `EmptyMatch` if one of the regexes can match the empty string, like `add_regex_str`,
+    /// and leaves the old searches in place.
+    /// As with `add_regex`, the memory of the DFAs is not known; `swap_dense_patterns` accounts for it.
+    pub fn swap_patterns<I>(&mut self, new_set: I) -> Result<Vec<(usize, Match)>, Error> where I: IntoIterator<Item=Regex<D>> {
+        self.swap_searches(new_set.into_iter().map(|regex| (Arc::new(regex), 0)).collect())
+    }
+
+    /// Replace all searches with the given (regex, memory), checking all before touching the old searches.
+    fn swap_searches(&mut self, new_set: Vec<(Arc<Regex<D>>, usize)>) -> Result<Vec<(usize, Match)>, Error> {
+        if new_set.iter().any(|(regex, _)| matches_empty(regex)) {
+            return Err(Error::EmptyMatch);
+        }
+        let memory: usize = new_set.iter().map(|&(_, memory)| memory).sum();
+        if let Some(limit) = self.memory_limit {
+            if self.buffer.capacity() + memory > limit {
+                return Err(Error::MemoryLimit);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(searches = self.searches.len(), "swapping patterns");
+        self.resolve();
+        let mut old: Vec<(usize, Match)> = self.found.drain(..).collect();
+        old.extend(self.final_matches());
+        self.searches.clear();
+        self.regions.clear();
+        for (regex, memory) in new_set {
+            let state_id = regex.forward().start_state();
+            self.push_search(Search::new(Engine::Dfa { regex, state_id }, memory));
+        }
+        self.update_buffer_size();
+        Ok(old)
+    }
+
+    /// Remove search `search_nr`, releasing its DFAs.
+    /// Returns `false` if it was removed before or never existed.
+    /// 
+    /// The identifiers of the other searches stay the same and the identifier is not given to later searches,
+    /// so identifiers kept elsewhere, like in configuration, never refer to another search.
+    /// The slot stays behind as a tombstone of a few words that is never reclaimed:
+    /// each push still skips over it, so a process adding and removing searches over and over
+    /// should rebuild its searcher, for example with `swap_patterns`, from time to time.
+    pub fn remove_search(&mut self, search_nr: usize) -> bool {
+        if self.is_removed(search_nr) {
+            return false;
+        }
+        let search = match self.searches.get_mut(search_nr) {
+            Some(search) => search,
+            None => return false,
+        };
+        *search = Search::new(Engine::Removed, 0);
+        search.paused = true;
+        self.completed.retain(|&(i, _)| i != search_nr);
+        self.update_buffer_size();
+        true
+    }
+
+    /// Whether search `search_nr` was removed with `remove_search`. `false` for identifiers that were never given out.
+    pub fn is_removed(&self, search_nr: usize) -> bool {
+        matches!(self.searches.get(search_nr), Some(search) if matches!(search.engine, Engine::Removed))
+    }
+
+    /// Stop stepping the search `search_nr`, freezing its state.
+    /// 
+    /// Useful to switch off expensive searches during sections of the stream where they can not match.
+    /// After `resume`, the search continues as if the input in between did not exist.
+    /// A match in progress may then not find its start.
+    pub fn pause(&mut self, search_nr: usize) {
+        self.searches[search_nr].paused = true;
+    }
+
+    /// Searches not in their start state, as (search identifier, bytes since they left it).
+    /// 
+    /// The distance is approximate: a search that restarts a match without passing its start state
+    /// counts from the earlier match.
+    /// Long distances point to patterns that keep the buffer pinned, or to matches that never complete.
+    pub fn in_progress(&self) -> impl Iterator<Item=(usize, usize)> + '_ {
+        self.searches.iter().enumerate()
+            .filter_map(move |(i, search)| search.pin_start.map(|start| (i, self.position - start)))
+    }
+
+    /// Continue stepping a search stopped by `pause`, or quarantined.
+    pub fn resume(&mut self, search_nr: usize) {
+        self.searches[search_nr].paused = false;
+        #[cfg(feature = "profile")]
+        {
+            self.searches[search_nr].quarantined = false;
+        }
+    }
+
+    pub fn is_paused(&self, search_nr: usize) -> bool {
+        self.searches[search_nr].paused
+    }
+
+    /// Only report matches of search `search_nr` if a match of search `other` ended
+    /// at most `distance` bytes before their start, or after it.
+    /// 
+    /// Cuts false positives of signatures that only make sense near another one.
+    /// A match without an exact start counts from its bound.
+    pub fn set_preceded_by(&mut self, search_nr: usize, other: usize, distance: usize) {
+        self.searches[search_nr].preceded_by = Some((other, distance));
+    }
+
+    /// Report all matches of search `search_nr` again.
+    pub fn clear_preceded_by(&mut self, search_nr: usize) {
+        self.searches[search_nr].preceded_by = None;
+    }
+
+    /// Only start search `search_nr` at flagged positions:
+    /// the ends of the matches of search `source`, if given, and the positions passed to `flag_start`.
+    /// 
+    /// Useful in structured protocols, where a pattern only makes sense at message starts.
+    /// The regex of the search has to be anchored, see `add_anchored_regex_str`.
+    /// Its matches have an exact start and are reported once they can not be extended.
+    /// Anchored searches ignore regions, record delimiters and `set_crlf`.
+    pub fn anchor_search(&mut self, search_nr: usize, source: Option<usize>) {
+        let search = &mut self.searches[search_nr];
+        assert!(search.engine.is_anchored(), "the regex of search {} is not anchored", search_nr);
+        search.engine.reset();
+        search.match_end = None;
+        search.anchor = Some(Anchor { source, pending: BTreeSet::new(), start: None, scanned: 0 });
+    }
+
+    /// Let the anchored search `search_nr` start at stream `position`.
+    /// 
+    /// Positions that are no longer buffered when the search gets to them are skipped.
+    pub fn flag_start(&mut self, search_nr: usize, position: usize) {
+        if let Some(ref mut anchor) = self.searches[search_nr].anchor {
+            anchor.pending.insert(position);
+        }
+    }
+
+    /// Include the `fingerprint` of their data in match records.
+    pub fn set_fingerprints(&mut self, fingerprints: bool) {
+        self.fingerprints = fingerprints;
+    }
+
+    /// Add a region of the stream: it starts after a match of search `enter`
+    /// and ends with the end of the next match of search `leave`.
+    /// 
+    /// Searches restricted to the region with `set_region` are only stepped inside it,
+    /// which saves the cost of rules that can only match in some context.
+    /// They start from scratch when entering the region, their match in progress completes when leaving it.
+    /// An `enter` match completed in a later chunk than its end starts the region at that chunk.
+    /// 
+    /// `enter` and `leave` may be the same search and should not be restricted themselves.
+    /// Returns the identifier for this region.
+    pub fn add_region(&mut self, enter: usize, leave: usize) -> usize {
+        self.regions.push(Region { enter, leave, inside: false });
+        self.regions.len() - 1
+    }
+
+    /// Only step search `search_nr` inside `region`, `None` steps it everywhere.
+    pub fn set_region(&mut self, search_nr: usize, region: Option<usize>) {
+        self.searches[search_nr].region = region;
+    }
+
+    /// The stream is currently inside `region`.
+    pub fn in_region(&self, region: usize) -> bool {
+        self.regions[region].inside
+    }
+
+    /// Update the regions with the matches in `self.completed[mark ..]`.
+    /// 
+    /// Returns per region the sections of the stream inside it, `[from, to)` relative to `start` and clamped to `len`,
+    /// and whether the region was left at their end.
+    fn region_sections(&mut self, mark: usize, start: usize, len: usize) -> Vec<Vec<(usize, usize, bool)>> {
+        let mut events: Vec<(usize, usize)> = self.completed[mark ..].to_vec();
+        events.sort_by_key(|&(i, end)| (end, i));
+        let mut sections = vec![vec![]; self.regions.len()];
+        for (region, sections) in self.regions.iter_mut().zip(sections.iter_mut()) {
+            let mut from = 0;
+            for &(i, end) in &events {
+                let at = end.saturating_sub(start).min(len);
+                if !region.inside && i == region.enter {
+                    region.inside = true;
+                    from = at;
+                } else if region.inside && i == region.leave {
+                    region.inside = false;
+                    sections.push((from, at, true));
+                }
+            }
+            if region.inside {
+                sections.push((from, len, false));
+            }
+        }
+        sections
+    }
+
+    fn add_search(&mut self, regex: Regex<D>, memory: usize) -> usize {
         let state_id = regex.forward().start_state();
+        self.push_search(Search::new(Engine::Dfa { regex: Arc::new(regex), state_id }, memory))
+    }
+
+    /// add a Regex shared with other searchers, taking `memory` into account for the memory limit
+    pub(crate) fn add_shared_search(&mut self, regex: Arc<Regex<D>>, memory: usize) -> Result<usize, Error> {
+        if matches_empty(&regex) {
+            return Err(Error::EmptyMatch);
+        }
+        self.check_memory(memory)?;
+        let state_id = regex.forward().start_state();
+        Ok(self.push_search(Search::new(Engine::Dfa { regex, state_id }, memory)))
+    }
+
+    /// Fails with `MemoryLimit` if `memory` more bytes would exceed the memory limit.
+    pub(crate) fn check_memory(&self, memory: usize) -> Result<(), Error> {
+        match self.memory_limit {
+            Some(limit) if self.memory_usage() + memory > limit => Err(Error::MemoryLimit),
+            _ => Ok(()),
+        }
+    }
+
+    fn push_search(&mut self, mut search: Search<D>) -> usize {
+        search.since = self.position;
         let search_nr = self.searches.len();
-        self.searches.push(Search {
-            state_id,
-            regex,
-            is_match: false, // first input byte requires this to work.
-            was_match: false,
-        });
+        #[cfg(feature = "tracing")]
+        tracing::debug!(search = search_nr, memory = search.memory, "added search");
+        self.searches.push(search);
+        self.update_buffer_size();
         search_nr
     }
 
     /// feed one stream byte to the searcher
     /// `matches` or `matches_string` must be called to obtain the matches ending at the *previous* input byte.
+    /// 
+    /// Panics after `finish`, see `try_push`.
     pub fn push(&mut self, input: u8) {
-        if self.buffer.len() + 1 > self.buffer_size {
-            self.buffer.pop_front();
+        assert!(!self.finished, "push after finish");
+        self.clear_found();
+        self.push_byte(input);
+        self.resolve();
+    }
+
+    /// feed a chunk of stream bytes to the searcher
+    /// 
+    /// `matches` then yields all matches completed within the chunk, in the order of their end.
+    /// 
+    /// Instead of stepping every search for every byte, each search runs over a piece of the chunk at once,
+    /// which keeps its transition table in cache.
+    /// For this, the buffer holds up to `CHUNK_PIECE` bytes more than its size until the next push.
+    /// DFAs from `RegexBuilder` are premultiplied, so a state id is directly an offset into that table.
+    /// 
+    /// A search in its start state, which leaves it on at most three bytes, skips to the next of them with `memchr`.
+    /// 
+    /// A chunk larger than the buffer is searched in place, only its last `buffer_size` bytes are copied.
+    /// 
+    /// The data of matches early in a large chunk may already be evicted,
+    /// `match_data` then only returns the part still in the buffer.
+    /// 
+    /// Panics after `finish`, see `try_push_slice`.
+    pub fn push_slice(&mut self, chunk: &[u8]) {
+        assert!(!self.finished, "push after finish");
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("push_slice", position = self.position, len = chunk.len()).entered();
+        self.clear_found();
+        if self.crlf {
+            // `\r\n` has to be handled byte by byte
+            for &b in chunk {
+                self.push_byte(b);
+                self.resolve();
+            }
+            return;
+        }
+        // the callbacks, pinning, queued matches and anchored searches need the data to pass through the buffer
+        let in_place = self.overflow.is_none() && self.on_match.is_none() && self.pin_limit.is_none() && !self.queue_matches
+            && self.searches.iter().all(|search| search.anchor.is_none());
+        if chunk.len() > self.buffer_size && in_place {
+            self.push_large(chunk);
+            return;
         }
+        for piece in chunk.chunks(CHUNK_PIECE) {
+            self.push_piece(piece);
+        }
+    }
+
+    /// Like `push`, failing with `Finished` after `finish`.
+    pub fn try_push(&mut self, input: u8) -> Result<(), Error> {
+        if self.finished {
+            return Err(Error::Finished);
+        }
+        self.push(input);
+        Ok(())
+    }
+
+    /// Like `push_slice`, failing with `Finished` after `finish`.
+    pub fn try_push_slice(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        if self.finished {
+            return Err(Error::Finished);
+        }
+        self.push_slice(chunk);
+        Ok(())
+    }
+
+    /// End the stream: the matches in progress are completed
+    /// and yielded by `matches`, like those of a push.
+    /// 
+    /// Further pushes are an error. Calling it again does nothing.
+    pub fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.clear_found();
+        let last: Vec<(usize, Match)> = self.final_matches().collect();
+        for (i, match_) in last {
+            self.report(i, match_);
+        }
+        for search in &mut self.searches {
+            search.match_end = None;
+            search.pin_start = None;
+            if let Some(ref mut anchor) = search.anchor {
+                anchor.start = None;
+            }
+        }
+        self.pending_cr = false;
+        self.finished = true;
+    }
+
+    /// The stream was ended with `finish`.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Start a new stream, for example the next file or connection, keeping the patterns and settings.
+    /// 
+    /// The buffer, the matches in progress and the matches not yet drained are dropped.
+    /// With `continue_positions`, the stream positions go on from the current one,
+    /// so the matches of consecutive streams can be told apart.
+    /// Otherwise positions start at 0 again, as do the record and match counts.
+    pub fn reset(&mut self, continue_positions: bool) {
+        if !continue_positions {
+            self.position = 0;
+            self.records = 0;
+            self.match_count = 0;
+        }
+        self.buffer.clear();
+        self.completed.clear();
+        self.found.clear();
+        self.pending_cr = false;
+        self.finished = false;
+        for search in &mut self.searches {
+            search.engine.reset();
+            search.since = self.position;
+            search.match_end = None;
+            search.last_end = None;
+            search.overflow_start = None;
+            search.overflowing = false;
+            search.overflowed.clear();
+            search.pin_start = None;
+            if let Some(ref mut anchor) = search.anchor {
+                anchor.pending.clear();
+                anchor.start = None;
+            }
+        }
+        for region in &mut self.regions {
+            region.inside = false;
+        }
+        if let Some(ref mut lines) = self.lines {
+            *lines = Lines { count: 0, evicted_line_start: self.position };
+        }
+        if let Some((interval, _)) = self.progress {
+            self.next_progress = self.position + interval;
+        }
+        #[cfg(feature = "std")]
+        if let Some(ref mut spill) = self.spill {
+            spill.clear(self.position);
+        }
+    }
+
+    fn push_piece(&mut self, piece: &[u8]) {
+        self.evict(self.buffer.len().saturating_sub(self.buffer_size));
+        if self.buffer.len() + piece.len() > self.buffer.capacity() && self.buffer.capacity() < self.buffer_size {
+            self.grow_buffer(piece.len());
+        }
+        self.make_room(piece.len());
+        self.buffer.extend(piece);
+
+        self.step_piece(piece);
+        self.resolve();
+    }
+
+    /// A chunk larger than the buffer is searched where it is,
+    /// only its end is copied into the buffer.
+    fn push_large(&mut self, chunk: &[u8]) {
+        let mut done = 0;
+        for piece in chunk.chunks(CHUNK_PIECE) {
+            self.step_piece(piece);
+            done += piece.len();
+            self.resolve_in(&chunk[.. done]);
+        }
+
+        // the old buffer and the start of the chunk are evicted
+        let keep = self.buffer_size.min(chunk.len());
+        let offset = self.position - chunk.len() - self.buffer.len();
+        let (head, tail) = self.buffer.as_slices();
+        let rest = &chunk[.. chunk.len() - keep];
+        for (position, data) in [(offset, head), (offset + head.len(), tail), (self.position - chunk.len(), rest)] {
+            track_evicted_lines(&mut self.lines, position, data);
+            pass_evicted(&self.evicted, #[cfg(feature = "std")] &mut self.spill, position, data);
+        }
+        self.buffer.clear();
+        if self.buffer.capacity() < self.buffer_size {
+            self.grow_buffer(self.buffer_size);
+        }
+        self.buffer.extend(&chunk[chunk.len() - keep ..]);
+    }
+
+    /// Step all searches over `piece`, which follows the data pushed so far.
+    fn step_piece(&mut self, piece: &[u8]) {
+        let start = self.position;
+        self.position += piece.len();
+        let delimiter = self.delimiter;
+        self.records += piece.iter().filter(|&&b| b == delimiter).count();
+        if let Some(ref mut lines) = self.lines {
+            lines.count += memchr::memchr_iter(b'\n', piece).count();
+        }
+
+        let mapped: Vec<u8>;
+        let input = match self.byte_map {
+            Some(ref map) => {
+                mapped = piece.iter().map(|&b| map[b as usize]).collect();
+                &mapped[..]
+            }
+            None => piece,
+        };
+
+        let piece = Piece { raw: piece, input, start, delimiter, record_reset: self.record_reset };
+        let mark = self.completed.len();
+        for (i, search) in self.searches.iter_mut().enumerate() {
+            if !search.paused && search.region.is_none() && search.anchor.is_none() {
+                #[cfg(feature = "profile")]
+                let started = std::time::Instant::now();
+                search.step_range(i, &piece, 0, input.len(), &mut self.completed);
+                #[cfg(feature = "profile")]
+                {
+                    search.profile.time += started.elapsed();
+                    search.profile.bytes += input.len();
+                }
+            }
+        }
+        if !self.regions.is_empty() {
+            let sections = self.region_sections(mark, start, input.len());
+            for (i, search) in self.searches.iter_mut().enumerate() {
+                let region = match search.region {
+                    Some(region) if !search.paused && search.anchor.is_none() => region,
+                    _ => continue,
+                };
+                for &(from, to, left) in &sections[region] {
+                    #[cfg(feature = "profile")]
+                    let started = std::time::Instant::now();
+                    search.step_range(i, &piece, from, to, &mut self.completed);
+                    #[cfg(feature = "profile")]
+                    {
+                        search.profile.time += started.elapsed();
+                        search.profile.bytes += to - from;
+                    }
+                    if left {
+                        search.engine.reset();
+                        self.completed.extend(search.finish_match().map(|end| (i, end)));
+                    }
+                }
+            }
+        }
+        self.completed.sort_by_key(|&(i, end)| (end, i));
+    }
+
+    /// Remove the oldest `n` bytes from the buffer,
+    /// passing those that belong to a match in progress to the overflow callback.
+    fn evict(&mut self, n: usize) {
+        let n = self.unpinned(n.min(self.buffer.len()));
+        self.evict_exact(n);
+    }
+
+    /// Evict the oldest `n` bytes, even if they are pinned.
+    fn evict_exact(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        if let Some(callback) = self.overflow.clone() {
+            // first data byte in self.buffer is at this stream position
+            let offset = self.position - self.buffer.len();
+            for i in 0 .. self.searches.len() {
+                let end = match self.searches[i].match_end {
+                    Some(end) => end,
+                    None => continue,
+                };
+                let start = match self.searches[i].overflow_start {
+                    Some(start) => start,
+                    None => {
+                        let start = match self.find_match(i, end) {
+                            Some(match_) => match_.start().unwrap_or(offset),
+                            None => continue,
+                        };
+                        self.searches[i].overflow_start = Some(start);
+                        start
+                    }
+                };
+                if start >= offset + n {
+                    continue;
+                }
+                self.searches[i].overflowing = true;
+                let from = start.max(offset) - offset;
+                let (head, tail) = self.buffer.as_slices();
+                let head = slice_window(head, from, n);
+                let tail = slice_window(tail, from.saturating_sub(head.len()), n.saturating_sub(head.len()));
+                for (position, data) in [(offset + from, head), (offset + from + head.len(), tail)] {
+                    if !data.is_empty() {
+                        callback(i, position, data);
+                    }
+                }
+            }
+        }
+        let offset = self.position - self.buffer.len();
+        let (head, tail) = self.buffer.as_slices();
+        let head = slice_window(head, 0, n);
+        let tail = slice_window(tail, 0, n - head.len());
+        for (position, data) in [(offset, head), (offset + head.len(), tail)] {
+            track_evicted_lines(&mut self.lines, position, data);
+            pass_evicted(&self.evicted, #[cfg(feature = "std")] &mut self.spill, position, data);
+        }
+        self.buffer.drain(.. n);
+    }
+
+    /// How many of the oldest `n` bytes can be evicted without losing pinned bytes
+    /// or the data of queued matches.
+    fn unpinned(&self, n: usize) -> usize {
+        let offset = self.position - self.buffer.len();
+        let n = match self.found.iter().map(|(_, m)| m.start.position()).min() {
+            Some(start) if self.queue_matches => n.min(start.saturating_sub(offset)),
+            _ => n,
+        };
+        let limit = match self.pin_limit {
+            Some(limit) => limit,
+            None => return n,
+        };
+        // with the byte before the pin, the reverse search can tell the start is exact
+        let pin = match self.searches.iter().filter_map(|s| s.pin_start).min() {
+            Some(pin) => pin.saturating_sub(1).max(self.position.saturating_sub(limit)),
+            None => return n,
+        };
+        n.min(pin.saturating_sub(offset))
+    }
+
+    /// Find the starts of the completed matches while their data is still in the buffer.
+    fn resolve(&mut self) {
+        self.resolve_in(&[]);
+    }
+
+    /// Like `resolve`, with `tail` following the buffer in the stream.
+    fn resolve_in(&mut self, tail: &[u8]) {
+        let mut completed = core::mem::take(&mut self.completed);
+        for (i, end) in completed.drain(..) {
+            let match_ = self.find_match_in(i, end, tail);
+            self.searches[i].overflowed.retain(|&e| e > end);
+            if let Some(match_) = match_ {
+                self.report(i, match_);
+            }
+        }
+        self.completed = completed;
+        if self.searches.iter().any(|search| search.anchor.is_some()) {
+            let mark = self.found.len();
+            for i in 0 .. self.searches.len() {
+                while let ControlFlow::Continue(match_) = self.step_candidate(i) {
+                    if let Some(match_) = match_ {
+                        self.report(i, match_);
+                    }
+                }
+            }
+            self.found[mark ..].sort_by_key(|&(i, match_)| (match_.end, i));
+        }
+        #[cfg(feature = "profile")]
+        self.enforce_budget();
+        self.report_progress();
+    }
+
+    /// Add `match_` of search `search_nr` to the found matches, if `set_preceded_by` allows it.
+    fn report(&mut self, search_nr: usize, match_: Match) {
+        if match_.is_empty() == Some(true) || !self.is_preceded(search_nr, &match_) {
+            return;
+        }
+        self.searches[search_nr].last_end = Some(match_.end);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(search = search_nr, start = ?match_.start, end = match_.end, "match");
+        if let Some(ref callback) = self.on_match {
+            callback(search_nr, &match_, self.match_data(&match_));
+        }
+        self.found.push((search_nr, match_));
+        self.match_count += 1;
+        for search in &mut self.searches {
+            if let Some(ref mut anchor) = search.anchor {
+                if anchor.source == Some(search_nr) {
+                    anchor.pending.insert(match_.end);
+                }
+            }
+        }
+    }
+
+    /// Step the candidate of the anchored search `search_nr` over the buffered bytes,
+    /// starting one at the next flagged position if there is none in progress.
+    /// 
+    /// Continues with the match of a candidate that can not be extended anymore,
+    /// breaks once the candidate reached the end of the buffer or nothing is left to try.
+    fn step_candidate(&mut self, search_nr: usize) -> ControlFlow<(), Option<Match>> {
+        let offset = self.position - self.buffer.len();
+        let (position, buffer, byte_map) = (self.position, &self.buffer, &self.byte_map);
+        let search = &mut self.searches[search_nr];
+        let anchor = match search.anchor {
+            Some(ref mut anchor) if !search.paused => anchor,
+            _ => return ControlFlow::Break(()),
+        };
+        let start = match anchor.start {
+            Some(start) => start,
+            None => {
+                // matches of a search do not overlap
+                let first = offset.max(search.last_end.unwrap_or(0));
+                anchor.pending = anchor.pending.split_off(&first);
+                let start = match anchor.pending.first() {
+                    Some(&start) if start < position => start,
+                    _ => return ControlFlow::Break(()),
+                };
+                anchor.pending.remove(&start);
+                anchor.start = Some(start);
+                anchor.scanned = start;
+                search.engine.reset();
+                search.match_end = None;
+                start
+            }
+        };
+        while anchor.scanned < position {
+            let b = buffer[anchor.scanned - offset];
+            let b = match *byte_map {
+                Some(ref map) => map[b as usize],
+                None => b,
+            };
+            anchor.scanned += 1;
+            match search.engine.step_anchored(b) {
+                Some(true) => search.match_end = Some(anchor.scanned),
+                Some(false) => {}
+                None => {
+                    anchor.start = None;
+                    search.pin_start = None;
+                    return ControlFlow::Continue(search.match_end.take().map(|end| Match::new(start, end)));
+                }
+            }
+        }
+        search.pin_start = Some(start);
+        ControlFlow::Break(())
+    }
+
+    /// The requirement set with `set_preceded_by` holds for `match_` of search `search_nr`.
+    fn is_preceded(&self, search_nr: usize, match_: &Match) -> bool {
+        match self.searches[search_nr].preceded_by {
+            Some((other, distance)) => self.searches[other].last_end
+                .is_some_and(|end| end + distance >= match_.start.position()),
+            None => true,
+        }
+    }
+
+    fn report_progress(&mut self) {
+        if let Some((interval, ref callback)) = self.progress {
+            if self.position >= self.next_progress {
+                callback(&Progress { position: self.position, matches: self.match_count, records: self.records });
+                self.next_progress = self.position - (self.position - self.next_progress) % interval + interval;
+            }
+        }
+    }
+
+    fn push_byte(&mut self, input: u8) {
+        if self.buffer.len() == self.buffer.capacity() && self.buffer.len() < self.buffer_size {
+            self.grow_buffer(1);
+        }
+        // `push_slice` may have left more than `buffer_size` bytes
+        self.evict((self.buffer.len() + 1).saturating_sub(self.buffer_size));
+        self.make_room(1);
         self.buffer.push_back(input);
         self.position += 1;
+        if input == b'\n' {
+            if let Some(ref mut lines) = self.lines {
+                lines.count += 1;
+            }
+        }
+
+        // a `\r` is only fed to the DFAs once we know it is not followed by `\n`.
+        if self.pending_cr {
+            self.pending_cr = false;
+            if input != b'\n' {
+                self.step(self.map_byte(b'\r'), self.position - 1, false);
+            }
+        }
+        if input == self.delimiter {
+            self.records += 1;
+            self.step(self.map_byte(input), self.position, true);
+            return;
+        }
+        if self.crlf && input == b'\r' {
+            self.pending_cr = true;
+            return;
+        }
+        self.step(self.map_byte(input), self.position, false);
+    }
+
+    fn map_byte(&self, input: u8) -> u8 {
+        match self.byte_map {
+            Some(ref map) => map[input as usize],
+            None => input,
+        }
+    }
 
-        for search in &mut self.searches {
-            let dfa = search.regex.forward();
-            let mut state_id = dfa.next_state(search.state_id, input);
-            let is_match = dfa.is_match_state(state_id);
 
-            if dfa.is_dead_state(state_id) {
-                state_id = dfa.start_state();
+    /// grow the buffer towards `buffer_size` to fit `additional` bytes, as far as the memory limit allows
+    fn grow_buffer(&mut self, additional: usize) {
+        let len = self.buffer.len();
+        let mut new_capacity = (2 * len).max(len + additional).max(64).min(self.buffer_size);
+        if let Some(limit) = self.memory_limit {
+            let available = limit.saturating_sub(self.memory_usage() - self.buffer.capacity());
+            if new_capacity > available {
+                new_capacity = available.max(len).max(1);
+                self.buffer_size = new_capacity;
+                self.truncated = Some(new_capacity);
             }
+        }
+        self.buffer.reserve_exact(new_capacity.saturating_sub(len));
+    }
 
-            // update state
-            search.was_match = search.is_match;
-            search.is_match = is_match;
-            search.state_id = state_id;
+    /// Make room for `additional` bytes, growing the buffer past its size if pinned bytes
+    /// or the data of queued matches could not be evicted.
+    /// 
+    /// The memory limit takes precedence: what does not fit is evicted, even if pinned.
+    fn make_room(&mut self, additional: usize) {
+        let len = self.buffer.len();
+        if len + additional <= self.buffer.capacity() {
+            return;
+        }
+        let mut capacity = (2 * len).max(len + additional);
+        if let Some(limit) = self.memory_limit {
+            let available = limit.saturating_sub(self.memory_usage() - self.buffer.capacity()).max(self.buffer.capacity());
+            if len + additional > available {
+                self.evict_exact((len + additional - available).min(len));
+                self.truncated = Some(available);
+            }
+            capacity = capacity.min(available).max(self.buffer.len() + additional);
         }
+        self.buffer.reserve_exact(capacity - self.buffer.len());
     }
 
-    /// Obtain the matches ending at the previous input byte.
+    /// Step all searches over the byte ending at `position`.
+    /// 
+    /// At a `delimiter`, searches that reset at record delimiters complete their match in progress
+    /// and go back to their start state, the others step over it.
+    fn step(&mut self, input: u8, position: usize, delimiter: bool) {
+        let record_reset = self.record_reset;
+        let step = |search: &mut Search<D>| if delimiter && search.record_reset.unwrap_or(record_reset) {
+            search.engine.reset();
+            search.finish_match()
+        } else {
+            search.step(input, position)
+        };
+        let mark = self.completed.len();
+        for (i, search) in self.searches.iter_mut().enumerate() {
+            if search.paused || search.region.is_some() || search.anchor.is_some() {
+                continue;
+            }
+            if let Some(end) = step(search) {
+                self.completed.push((i, end));
+            }
+        }
+        if self.regions.is_empty() {
+            return;
+        }
+        let sections = self.region_sections(mark, position - 1, 1);
+        for (i, search) in self.searches.iter_mut().enumerate() {
+            let region = match search.region {
+                Some(region) if !search.paused && search.anchor.is_none() => region,
+                _ => continue,
+            };
+            for &(from, to, left) in &sections[region] {
+                if from < to {
+                    if let Some(end) = step(search) {
+                        self.completed.push((i, end));
+                    }
+                }
+                if left {
+                    search.engine.reset();
+                    self.completed.extend(search.finish_match().map(|end| (i, end)));
+                }
+            }
+        }
+    }
+
+    /// Obtain the matches ending at the previous input byte,
+    /// or the matches completed within the chunk passed to `push_slice`.
+    /// After `extend`, these are all matches completed since the last `push`, `push_slice` or `drain_matches`.
     /// 
     /// The iterator yields (search identifier, match).
     pub fn matches(&self) -> impl Iterator<Item=(usize, Match)> + '_ {
-        let position = self.position;
-        self.searches.iter().enumerate().filter_map(move |(i, search)| {
-            if (search.was_match, search.is_match) == (true, false) {
-                rfind_iter(search.regex.reverse(), self.buffer.iter().rev().cloned().skip(1)).map(move |len| {
-                    let start = if len == self.buffer.len() {
-                        None
-                    } else {
-                        Some(position - len - 1)
-                    };
-
-                    (i, Match {
-                        start,
-                        end: position - 1,
-                    })
-                })
-            } else {
-                None
-            }
+        self.found.iter().cloned()
+    }
+
+    /// Like `matches`, with the match data as a string. Invalid UTF-8 is replaced with U+FFFD.
+    /// 
+    /// The iterator yields (search identifier, match, data).
+    pub fn matches_string(&self) -> impl Iterator<Item=(usize, Match, String)> + '_ {
+        self.found.iter().map(move |&(id, match_)| {
+            let data = String::from_utf8_lossy(&self.match_data(&match_).to_vec()).into_owned();
+            (id, match_, data)
         })
     }
 
+    /// Remove and return the matches `matches` would yield.
+    /// With `set_match_queue`, these are all matches found since the last call.
+    pub fn drain_matches(&mut self) -> impl Iterator<Item=(usize, Match)> + '_ {
+        self.found.drain(..)
+    }
+
     /// Obtain the final matches.
     /// 
     /// This will return the matches ending at the last input byte and should only be called when no more input follows.
+    /// `finish` does the same, and also prevents further input.
     /// The iterator yields (search identifier, match).
     pub fn final_matches(&self) -> impl Iterator<Item=(usize, Match)> + '_ {
-        let position = self.position;
         self.searches.iter().enumerate().filter_map(move |(i, search)| {
-            if search.is_match {
-                rfind_iter(search.regex.reverse(), self.buffer.iter().rev().cloned()).map(move |len| {
-                    let start = if len == self.buffer.len() {
-                        None
-                    } else {
-                        Some(position - len)
-                    };
-
-                    (i, Match {
-                        start,
-                        end: position,
-                    })
-                })
-            } else {
-                None
+            let mut end = search.match_end;
+            if self.pending_cr && !search.paused && search.anchor.is_none() {
+                // the trailing `\r` is not followed by `\n`
+                if search.engine.peek(self.map_byte(b'\r')) == Step::Match {
+                    end = Some(self.position);
+                }
             }
+            let match_ = match search.anchor {
+                Some(ref anchor) => anchor.start.zip(end).map(|(start, end)| Match::new(start, end)),
+                None => end.and_then(|end| self.find_match(i, end)),
+            };
+            match_.filter(|match_| match_.is_empty() != Some(true) && self.is_preceded(i, match_))
+                .map(|match_| (i, match_))
         })
     }
 
+    /// Find the start of the match of search `search_nr` ending at stream position `end`.
+    fn find_match(&self, search_nr: usize, end: usize) -> Option<Match> {
+        self.find_match_in(search_nr, end, &[])
+    }
+
+    /// Like `find_match`, with `tail` following the buffer in the stream.
+    fn find_match_in(&self, search_nr: usize, end: usize, tail: &[u8]) -> Option<Match> {
+        let search = &self.searches[search_nr];
+        // first data byte in self.buffer is at this stream position
+        let buffer_offset = self.position - self.buffer.len() - tail.len();
+
+        // spilled data extends the buffer and the default window
+        #[cfg(feature = "std")]
+        let (data_offset, spill_limit, spilled) = match self.spill {
+            Some(ref spill) => (spill.start().min(buffer_offset), spill.limit(), Some(spill.rev_bytes(end))),
+            None => (buffer_offset, 0, None),
+        };
+        #[cfg(not(feature = "std"))]
+        let (data_offset, spill_limit, spilled) = (buffer_offset, 0, None::<core::iter::Empty<u8>>);
+
+        // pinned bytes extend the default window
+        let default_window = self.default_window().max(self.pin_limit.unwrap_or(0));
+        let window = search.window.unwrap_or(default_window.saturating_add(spill_limit));
+        let window_start = end.saturating_sub(window);
+        let offset = data_offset.max(window_start).max(search.since);
+        let available = end.checked_sub(offset)?;
+        let delimiter = self.delimiter;
+        let record_reset = search.record_reset.unwrap_or(self.record_reset);
+        let len = end.saturating_sub(buffer_offset);
+        let buffer_len = len.min(self.buffer.len());
+        let bytes = tail[.. len - buffer_len].iter().rev().chain(self.buffer.range(.. buffer_len).rev()).cloned()
+            .chain(spilled.into_iter().flatten())
+            .take(available)
+            .take_while(move |&b| !record_reset || b != delimiter)
+            .map(move |b| self.map_byte(b));
+        let len = if self.crlf {
+            search.engine.rfind(skip_crlf(bytes))
+        } else {
+            search.engine.rfind(bytes.enumerate())
+        }?;
+
+        // the match may extend into data that was already evicted
+        let overflowed = search.overflowed.contains(&end) || (search.overflowing && search.match_end == Some(end));
+        let start = if overflowed || (len == available && offset > search.since) {
+            StartBound::AtOrBefore(end - len)
+        } else {
+            StartBound::Exact(end - len)
+        };
+        let match_ = Match { start, end };
+
+        #[cfg(feature = "fancy")]
+        if let Some(ref confirm) = search.confirm {
+            return self.confirm(confirm, match_, tail);
+        }
+        Some(match_)
+    }
 
     /// Obtain the data for a specific match, as far as it is still in the buffer.
     /// Data is obtained as a pair of slices to avoid copying.
-    pub fn match_data(&self, match_: &Match) -> MatchData {
+    /// 
+    /// Never panics: the data of evicted or future matches is empty, see `checked_match_data`.
+    pub fn match_data(&self, match_: &Match) -> MatchData<'_> {
         let (head, tail) = self.buffer.as_slices();
 
         // first data byte in self.buffer is at this stream position
         let offset = self.position - self.buffer.len();
 
         // position of match start in the buffer
-        let start = match_.start.unwrap_or(offset) - offset;
+        let start = match_.start().unwrap_or(offset).saturating_sub(offset);
 
         // position of match end in the buffer
-        let end = match_.end - offset;
+        let end = match_.end.saturating_sub(offset);
         
         MatchData {
             head: slice_window(head, start, end),
@@ -160,6 +1946,59 @@ impl<D: DFA> RingSearcher<D> {
         }
     }
 
+    /// Like `match_data`, failing if it can not return all data of the match.
+    /// A match whose start is only known as `AtOrBefore` fails with `Evicted`, as its first bytes are gone.
+    /// 
+    /// Stream positions only grow, so they tell whether the buffer still holds the bytes of a match,
+    /// however many pushes happened since it was found.
+    pub fn checked_match_data(&self, match_: &Match) -> Result<MatchData<'_>, MatchDataError> {
+        let offset = self.position - self.buffer.len();
+        if match_.start.position() > match_.end {
+            return Err(MatchDataError::Inverted);
+        }
+        if match_.end > self.position {
+            return Err(MatchDataError::NotPushed);
+        }
+        if match_.start.position() < offset || match_.start.exact().is_none() {
+            return Err(MatchDataError::Evicted);
+        }
+        Ok(self.match_data(match_))
+    }
+
+    /// The bytes at the stream positions `range`, like a protocol field or the context of a match.
+    /// `None` if they are not all buffered.
+    pub fn get_range(&self, range: Range<usize>) -> Option<MatchData<'_>> {
+        if range.start > range.end {
+            return None;
+        }
+        self.checked_match_data(&Match::new(range.start, range.end)).ok()
+    }
+
+    /// All non-overlapping matches of `regex` in the buffered data, without registering a search.
+    /// 
+    /// For tools probing the retained window. The regex sees the bytes after the byte map, like the searches do.
+    /// Matches do not extend into evicted data and empty matches are left out.
+    pub fn search_buffer<R: DFA>(&self, regex: &Regex<R>) -> Vec<Match> {
+        let offset = self.position - self.buffer.len();
+        let mut found = vec![];
+        let mut at = 0;
+        while at < self.buffer.len() {
+            let end = match find_end(regex.forward(), self.buffer.range(at ..).map(|&b| self.map_byte(b))) {
+                Some(len) => at + len,
+                None => break,
+            };
+            let len = rfind_iter(regex.reverse(), self.buffer.range(at .. end).rev().map(|&b| self.map_byte(b)).enumerate())
+                .unwrap_or(0);
+            if len == 0 {
+                at = end + 1;
+                continue;
+            }
+            found.push(Match::new(offset + end - len, offset + end));
+            at = end;
+        }
+        found
+    }
+
     /// Perform matching on the entire input iterator and call `callback` for every match.
     /// 
     /// The callback recieves:
@@ -169,37 +2008,503 @@ impl<D: DFA> RingSearcher<D> {
     pub fn input_matches<I, V, F>(&mut self, input: I, mut callback: F)
         where I: IntoIterator<Item=V>, V: Borrow<u8>, F: FnMut(usize, &Match, MatchData)
     {
-        for b in input.into_iter() {
+        let result = self.try_input_matches(input, |re_nr, match_, data| {
+            callback(re_nr, match_, data);
+            Ok::<(), core::convert::Infallible>(())
+        });
+        match result {
+            Ok(()) => {}
+            Err(e) => match e {},
+        }
+    }
+
+    /// Like `input_matches`, but the callback may fail.
+    /// The first error stops the scan and is returned, the remaining input is not consumed.
+    pub fn try_input_matches<I, V, E, F>(&mut self, input: I, mut callback: F) -> Result<(), E>
+        where I: IntoIterator<Item=V>, V: Borrow<u8>, F: FnMut(usize, &Match, MatchData) -> Result<(), E>
+    {
+        for (i, b) in input.into_iter().enumerate() {
+            if i % CHUNK_PIECE == 0 && self.is_cancelled() {
+                return Ok(());
+            }
             self.push(*b.borrow());
             for (re_nr, match_) in self.matches() {
                 let data = self.match_data(&match_);
-                callback(re_nr, &match_, data);
+                callback(re_nr, &match_, data)?;
             }
         }
 
         for (re_nr, match_) in self.final_matches() {
             let data = self.match_data(&match_);
-            callback(re_nr, &match_, data);
+            callback(re_nr, &match_, data)?;
+        }
+        Ok(())
+    }
+
+    /// Like `input_matches`, but the callback can stop the scan by returning `ControlFlow::Break`.
+    /// The remaining input is not consumed.
+    pub fn input_matches_until<I, V, B, F>(&mut self, input: I, mut callback: F) -> ControlFlow<B>
+        where I: IntoIterator<Item=V>, V: Borrow<u8>, F: FnMut(usize, &Match, MatchData) -> ControlFlow<B>
+    {
+        let result = self.try_input_matches(input, |re_nr, match_, data| match callback(re_nr, match_, data) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(b) => Err(b),
+        });
+        match result {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(b) => ControlFlow::Break(b),
+        }
+    }
+
+    /// Like `input_matches`, but returns an iterator over the matches which pulls input as needed.
+    /// 
+    /// The iterator yields (search identifier, match, match data).
+    /// The match data is copied, as it may leave the buffer before the next item is requested.
+    pub fn matches_iter<I, V>(&mut self, input: I) -> MatchesIter<'_, D, I::IntoIter>
+        where I: IntoIterator<Item=V>, V: Borrow<u8>
+    {
+        MatchesIter {
+            searcher: self,
+            input: Some(input.into_iter()),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Wrap the input in an iterator which yields the input bytes and the matches in stream order:
+    /// a match comes after the last byte of it.
+    pub fn scan_iter<I, V>(&mut self, input: I) -> ScanIter<'_, D, I::IntoIter>
+        where I: IntoIterator<Item=V>, V: Borrow<u8>
+    {
+        ScanIter {
+            searcher: self,
+            input: Some(input.into_iter()),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+/// A match together with a copy of its data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchRecord {
+    /// search identifier
+    pub id: usize,
+    pub start: StartBound,
+    pub end: usize,
+    pub data: Vec<u8>,
+    /// hash of `data`, if enabled with `set_fingerprints`
+    pub fingerprint: Option<u64>,
+}
+
+impl MatchRecord {
+    pub fn match_(&self) -> Match {
+        Match { start: self.start, end: self.end }
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "futures"))]
+impl<D: DFA> RingSearcher<D> {
+    /// Push `input` and append a record of every match to `out`.
+    pub(crate) fn push_records(&mut self, input: &[u8], out: &mut VecDeque<MatchRecord>) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("push_records", position = self.position, len = input.len()).entered();
+        for &b in input {
+            self.push(b);
+            for (id, match_) in self.matches() {
+                out.push_back(self.record(id, match_));
+            }
+        }
+    }
+
+    /// Append a record of every final match to `out`.
+    pub(crate) fn final_records(&self, out: &mut VecDeque<MatchRecord>) {
+        for (id, match_) in self.final_matches() {
+            out.push_back(self.record(id, match_));
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<D: DFA> RingSearcher<D> {
+    fn record(&self, id: usize, match_: Match) -> MatchRecord {
+        let data = self.match_data(&match_).to_vec();
+        let fingerprint = self.fingerprints.then(|| fingerprint(&data));
+        MatchRecord { id, start: match_.start, end: match_.end, data, fingerprint }
+    }
+}
+
+/// Iterator over the input bytes and matches, see `RingSearcher::scan_iter`.
+pub struct ScanIter<'s, D: DFA, I> {
+    searcher: &'s mut RingSearcher<D>,
+    /// None once the input is exhausted
+    input: Option<I>,
+    pending: VecDeque<Event>,
+}
+
+/// An item of `ScanIter`
+#[derive(Copy, Clone, Debug)]
+pub enum Event {
+    Byte(u8),
+    /// (search identifier, match)
+    Match(usize, Match),
+}
+
+impl<'s, D: DFA, I, V> Iterator for ScanIter<'s, D, I>
+    where I: Iterator<Item=V>, V: Borrow<u8>
+{
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            let searcher = &mut *self.searcher;
+            match self.input.as_mut()?.next() {
+                Some(b) => {
+                    let b = *b.borrow();
+                    searcher.push(b);
+                    // the matches end before this byte
+                    self.pending.extend(searcher.matches().map(|(i, match_)| Event::Match(i, match_)));
+                    self.pending.push_back(Event::Byte(b));
+                }
+                None => {
+                    self.input = None;
+                    self.pending.extend(searcher.final_matches().map(|(i, match_)| Event::Match(i, match_)));
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over the matches in an input, see `RingSearcher::matches_iter`.
+pub struct MatchesIter<'s, D: DFA, I> {
+    searcher: &'s mut RingSearcher<D>,
+    /// None once the input is exhausted
+    input: Option<I>,
+    pending: VecDeque<(usize, Match, Vec<u8>)>,
+}
+
+impl<'s, D: DFA, I, V> Iterator for MatchesIter<'s, D, I>
+    where I: Iterator<Item=V>, V: Borrow<u8>
+{
+    type Item = (usize, Match, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            let searcher = &mut *self.searcher;
+            match self.input.as_mut()?.next() {
+                Some(b) => {
+                    searcher.push(*b.borrow());
+                    self.pending.extend(searcher.matches().map(|(i, match_)| {
+                        (i, match_, searcher.match_data(&match_).to_vec())
+                    }));
+                }
+                None => {
+                    self.input = None;
+                    self.pending.extend(searcher.final_matches().map(|(i, match_)| {
+                        (i, match_, searcher.match_data(&match_).to_vec())
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// size of the reads by `scan_reader`
+#[cfg(feature = "std")]
+const READ_SIZE: usize = 64 * 1024;
+
+#[cfg(feature = "std")]
+impl<D: DFA> RingSearcher<D> {
+    /// Scan everything `reader` yields and call `callback` for every match, including the final matches.
+    /// The callback recieves the same arguments as for `input_matches`.
+    /// 
+    /// The input is pushed in pieces no larger than the buffer, so the data of every match is still buffered.
+    pub fn scan_reader<R, F>(&mut self, reader: R, mut callback: F) -> std::io::Result<()>
+        where R: std::io::Read, F: FnMut(usize, &Match, MatchData)
+    {
+        let flow = self.scan_reader_until(reader, |re_nr, match_, data| {
+            callback(re_nr, match_, data);
+            ControlFlow::<core::convert::Infallible>::Continue(())
+        })?;
+        match flow {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(b) => match b {},
+        }
+    }
+
+    /// Like `scan_reader`, but the callback can stop the scan by returning `ControlFlow::Break`.
+    /// No more data is read then.
+    /// 
+    /// A scan stopped by the cancel flag returns an error of kind `Other`.
+    pub fn scan_reader_until<R, B, F>(&mut self, mut reader: R, mut callback: F) -> std::io::Result<ControlFlow<B>>
+        where R: std::io::Read, F: FnMut(usize, &Match, MatchData) -> ControlFlow<B>
+    {
+        let mut buf = vec![0; READ_SIZE];
+        let piece_len = self.buffer_size.clamp(1, CHUNK_PIECE);
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for piece in buf[.. len].chunks(piece_len) {
+                if self.is_cancelled() {
+                    return Err(std::io::Error::other("scan cancelled"));
+                }
+                self.push_slice(piece);
+                for (re_nr, match_) in self.matches() {
+                    if let ControlFlow::Break(b) = callback(re_nr, &match_, self.match_data(&match_)) {
+                        return Ok(ControlFlow::Break(b));
+                    }
+                }
+            }
+        }
+
+        for (re_nr, match_) in self.final_matches() {
+            if let ControlFlow::Break(b) = callback(re_nr, &match_, self.match_data(&match_)) {
+                return Ok(ControlFlow::Break(b));
+            }
         }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Open and scan the file at `path`, see `scan_reader`. The callback also recieves the path.
+    pub fn scan_file<P, F>(&mut self, path: P, mut callback: F) -> std::io::Result<()>
+        where P: AsRef<std::path::Path>, F: FnMut(&std::path::Path, usize, &Match, MatchData)
+    {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        self.scan_reader(file, |re_nr, match_, data| callback(path, re_nr, match_, data))
+    }
+
+    /// Write the buffer contents to `w`, for crash handlers and debugging.
+    /// 
+    /// A header line `offset <stream position> length <number of bytes>` is followed by the raw bytes.
+    pub fn dump_buffer(&self, mut w: impl std::io::Write) -> std::io::Result<()> {
+        let offset = self.position - self.buffer.len();
+        writeln!(w, "offset {} length {}", offset, self.buffer.len())?;
+        let (head, tail) = self.buffer.as_slices();
+        w.write_all(head)?;
+        w.write_all(tail)
     }
 }
 
+/// Feeds the bytes to the searcher. The matches are kept until drained with `drain_matches`,
+/// or the next `push` or `push_slice`.
+impl<D: DFA> Extend<u8> for RingSearcher<D> {
+    /// Panics after `finish`, like `push`.
+    fn extend<I: IntoIterator<Item=u8>>(&mut self, iter: I) {
+        assert!(!self.finished, "push after finish");
+        for b in iter {
+            self.push_byte(b);
+            self.resolve();
+        }
+    }
+}
+
+impl<'a, D: DFA> Extend<&'a u8> for RingSearcher<D> {
+    fn extend<I: IntoIterator<Item=&'a u8>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().cloned())
+    }
+}
+
+#[cfg(feature = "std")]
 impl RingSearcher<DenseDFA<Vec<usize>, usize>> {
     /// convinience function to add Regex from a `str`.
-    pub fn add_regex_str(&mut self, regex_str: &str) -> Result<(), Error> {
-        let regex = RegexBuilder::new().build(regex_str).map_err(|e| Error::InvalidRegex)?;
-        self.add_regex(regex);
-        Ok(())
+    /// 
+    /// Returns the identifier for this search.
+    pub fn add_regex_str(&mut self, regex_str: &str) -> Result<usize, Error> {
+        self.add_built_regex_str(&RegexBuilder::new(), regex_str)
+    }
+
+    /// Add a search for the regex `regex_str` that only starts at flagged positions, see `anchor_search`.
+    /// 
+    /// Returns the identifier for this search.
+    pub fn add_anchored_regex_str(&mut self, regex_str: &str, source: Option<usize>) -> Result<usize, Error> {
+        let search_nr = self.add_built_regex_str(RegexBuilder::new().anchored(true), regex_str)?;
+        self.anchor_search(search_nr, source);
+        Ok(search_nr)
+    }
+
+    /// Add searches for all `patterns`, skipping the ones that fail.
+    ///
+    /// Returns the identifier of the search or the reason it was not added for each pattern, in order.
+    pub fn try_add_many<'a>(&mut self, patterns: impl IntoIterator<Item=&'a str>) -> Vec<Result<usize, PatternError>> {
+        let builder = RegexBuilder::new();
+        patterns.into_iter().map(|pattern| self.add_compiled(builder.build(pattern), pattern)).collect()
+    }
+
+    pub(crate) fn add_compiled(&mut self, compiled: Result<Regex, regex_automata::Error>, pattern: &str) -> Result<usize, PatternError> {
+        let regex = compiled.map_err(|e| PatternError { error: Error::InvalidRegex, reason: e.to_string() })?;
+        let search_nr = self.add_dense_regex(regex).map_err(|error| {
+            let reason = match error {
+                Error::EmptyMatch => "the pattern matches the empty string",
+                _ => "the memory limit would be exceeded",
+            };
+            PatternError { error, reason: reason.into() }
+        })?;
+        self.searches[search_nr].pattern = Some(pattern.into());
+        Ok(search_nr)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: StateID> RingSearcher<DenseDFA<Vec<S>, S>> {
+    /// Like `add_regex_str`, for searchers with DFA state ids of type `S`.
+    /// 
+    /// `u16` or `u32` ids shrink the transition tables to a quarter or half of `usize` ids,
+    /// which keeps many small patterns in cache.
+    /// Fails with `InvalidRegex` if the DFA has more states than `S` can number.
+    /// 
+    /// Returns the identifier for this search.
+    pub fn add_sized_regex_str(&mut self, regex_str: &str) -> Result<usize, Error> {
+        self.add_built_regex_str(&RegexBuilder::new(), regex_str)
+    }
+
+    /// Like `add_sized_regex_str`, with minimized DFAs.
+    /// 
+    /// Minimization can shrink the DFAs of patterns with many equivalent states considerably,
+    /// which matters when they are kept resident for thousands of streams.
+    /// Beware that it is expensive: compiling can take orders of magnitude longer.
+    pub fn add_minimized_regex_str(&mut self, regex_str: &str) -> Result<usize, Error> {
+        self.add_built_regex_str(RegexBuilder::new().minimize(true), regex_str)
+    }
+
+    /// Like `swap_patterns`, accounting for the memory of the DFAs.
+    /// 
+    /// Fails with `MemoryLimit` if the new searches would exceed the memory limit.
+    pub fn swap_dense_patterns<I>(&mut self, new_set: I) -> Result<Vec<(usize, Match)>, Error> where I: IntoIterator<Item=Regex<DenseDFA<Vec<S>, S>>> {
+        self.swap_searches(new_set.into_iter().map(|regex| {
+            let memory = regex.forward().memory_usage() + regex.reverse().memory_usage();
+            (Arc::new(regex), memory)
+        }).collect())
+    }
+
+    fn add_built_regex_str(&mut self, builder: &RegexBuilder, regex_str: &str) -> Result<usize, Error> {
+        let regex = builder.build_with_size::<S>(regex_str).map_err(|_| Error::InvalidRegex)?;
+        let search_nr = self.add_dense_regex(regex)?;
+        self.searches[search_nr].pattern = Some(regex_str.into());
+        Ok(search_nr)
+    }
+
+    /// add a Regex, accounting for its memory
+    pub(crate) fn add_dense_regex(&mut self, regex: Regex<DenseDFA<Vec<S>, S>>) -> Result<usize, Error> {
+        self.add_shared_regex(Arc::new(regex))
+    }
+
+    /// add a Regex shared with other searchers, accounting for its memory
+    pub(crate) fn add_shared_regex(&mut self, regex: Arc<Regex<DenseDFA<Vec<S>, S>>>) -> Result<usize, Error> {
+        let memory = regex.forward().memory_usage() + regex.reverse().memory_usage();
+        self.add_shared_search(regex, memory)
+    }
+}
+
+/// A builder with the regex flags in `flags`:
+///  - `i`: case insensitive
+///  - `s`: `.` matches `\n`
+///  - `x`: ignore whitespace
+///  - `U`: swap greed
+/// 
+/// Returns the first unknown flag as error.
+#[cfg(feature = "std")]
+pub(crate) fn flags_builder(flags: &str) -> Result<RegexBuilder, char> {
+    let mut builder = RegexBuilder::new();
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            's' => builder.dot_matches_new_line(true),
+            'x' => builder.ignore_whitespace(true),
+            'U' => builder.swap_greed(true),
+            _ => return Err(flag),
+        };
+    }
+    Ok(builder)
+}
+
+/// The bytes leading from the start state of `dfa` towards a match, if there are at most three.
+/// 
+/// Other bytes either stay in the start state or only lead back to it (like UTF-8 sequences
+/// in the unanchored prefix), so a search in the start state can skip them.
+fn start_bytes<D: DFA>(dfa: &D) -> Option<Vec<u8>> {
+    // give up on large DFAs
+    const MAX_STATES: usize = 1024;
+
+    let start = dfa.start_state();
+    if dfa.is_match_state(start) {
+        return None;
+    }
+
+    // the states reachable from a state without passing the start state, or None if a match is
+    let mut reachable: BTreeMap<D::ID, Option<BTreeSet<D::ID>>> = BTreeMap::new();
+    let mut bytes = vec![];
+    for b in 0 ..= 255 {
+        let next = dfa.next_state(start, b);
+        if next == start || dfa.is_dead_state(next) {
+            continue;
+        }
+        let states = reachable.entry(next).or_insert_with(|| {
+            let mut states = BTreeSet::new();
+            let mut todo = vec![next];
+            while let Some(state) = todo.pop() {
+                if dfa.is_match_state(state) || states.len() == MAX_STATES {
+                    return None;
+                }
+                if !states.insert(state) {
+                    continue;
+                }
+                todo.extend((0 ..= 255).map(|b| dfa.next_state(state, b))
+                    .filter(|&s| s != start && !dfa.is_dead_state(s) && !states.contains(&s)));
+            }
+            Some(states)
+        });
+        if states.is_none() {
+            if bytes.len() == 3 {
+                return None;
+            }
+            bytes.push(b);
+        }
+    }
+
+    // skipping a byte is only equivalent if the skipped states react to the start bytes like the start state
+    for states in reachable.values().flatten() {
+        for &state in states {
+            for &b in &bytes {
+                let next = dfa.next_state(state, b);
+                if !dfa.is_dead_state(next) && next != dfa.next_state(start, b) {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(bytes)
+}
+
+/// position of the first of `needles` in `haystack`
+fn find_any(needles: &[u8], haystack: &[u8]) -> Option<usize> {
+    match *needles {
+        [] => None,
+        [a] => memchr::memchr(a, haystack),
+        [a, b] => memchr::memchr2(a, b, haystack),
+        [a, b, c] => memchr::memchr3(a, b, c, haystack),
+        _ => haystack.iter().position(|b| needles.contains(b)),
     }
 }
 
 fn slice_window(slice: &[u8], start: usize, end: usize) -> &[u8] {
-    &slice[start.min(slice.len()) .. end.min(slice.len())]
+    let end = end.min(slice.len());
+    &slice[start.min(end) .. end]
 }
 
 
 /// Works like rfind, but returns the number of bytes in the reverse direction and takes an iterator input.
-fn rfind_iter<D: DFA>(dfa: &D, bytes: impl Iterator<Item=u8>) -> Option<usize> {
+/// 
+/// The iterator yields the bytes together with their index in the reversed input.
+fn rfind_iter<D: DFA>(dfa: &D, bytes: impl Iterator<Item=(usize, u8)>) -> Option<usize> {
     let mut state = dfa.start_state();
     let mut last_match = if dfa.is_dead_state(state) {
         return None;
@@ -208,8 +2513,40 @@ fn rfind_iter<D: DFA>(dfa: &D, bytes: impl Iterator<Item=u8>) -> Option<usize> {
     } else {
         None
     };
+    for (i, b) in bytes {
+        #[cfg(not(feature = "unchecked"))]
+        {
+            state = dfa.next_state(state, b);
+        }
+        // SAFETY: `state` is the start state of `dfa` or a transition of it, never a value from the input,
+        // and the DFA has a transition for every byte. So the index into the transition table is in bounds,
+        // whatever bytes the stream contains.
+        #[cfg(feature = "unchecked")]
+        {
+            state = unsafe { dfa.next_state_unchecked(state, b) };
+        }
+        if dfa.is_match_or_dead_state(state) {
+            if dfa.is_dead_state(state) {
+                return last_match;
+            }
+            last_match = Some(i + 1);
+        }
+    }
+    last_match
+}
+
+/// Whether `regex` matches the empty string, at least at the start of the input.
+pub(crate) fn matches_empty<D: DFA>(regex: &Regex<D>) -> bool {
+    let dfa = regex.forward();
+    dfa.is_match_state(dfa.start_state())
+}
+
+/// Length of the leftmost-first match at the start of `bytes`, searched with the forward `dfa`.
+fn find_end<D: DFA>(dfa: &D, bytes: impl Iterator<Item=u8>) -> Option<usize> {
+    let mut state = dfa.start_state();
+    let mut last_match = if dfa.is_match_state(state) { Some(0) } else { None };
     for (i, b) in bytes.enumerate() {
-        state = unsafe { dfa.next_state_unchecked(state, b) };
+        state = dfa.next_state(state, b);
         if dfa.is_match_or_dead_state(state) {
             if dfa.is_dead_state(state) {
                 return last_match;
@@ -220,32 +2557,153 @@ fn rfind_iter<D: DFA>(dfa: &D, bytes: impl Iterator<Item=u8>) -> Option<usize> {
     last_match
 }
 
+/// Pass data leaving the buffer to the eviction callback and the spill file.
+fn pass_evicted(
+    callback: &Option<EvictionCallback>,
+    #[cfg(feature = "std")] spill: &mut Option<spill::Spill>,
+    position: usize,
+    data: &[u8],
+) {
+    if data.is_empty() {
+        return;
+    }
+    if let Some(ref callback) = *callback {
+        callback(position, data);
+    }
+    #[cfg(feature = "std")]
+    if let Some(ref mut spill) = *spill {
+        spill.write(position, data);
+    }
+}
+
+/// Remember where the last line starting in evicted `data` at stream `position` begins.
+fn track_evicted_lines(lines: &mut Option<Lines>, position: usize, data: &[u8]) {
+    if let Some(ref mut lines) = *lines {
+        if let Some(i) = memchr::memrchr(b'\n', data) {
+            lines.evicted_line_start = position + i + 1;
+        }
+    }
+}
+
+/// Step `dfa` from `state_id` over `input`. A dead state restarts the search at `input`.
+fn dfa_step<D: DFA>(dfa: &D, state_id: &mut D::ID, input: u8) -> Step {
+    let mut next = dfa.next_state(*state_id, input);
+    let restarted = dfa.is_dead_state(next);
+    if restarted {
+        // the byte may start the next match
+        next = dfa.next_state(dfa.start_state(), input);
+        if dfa.is_dead_state(next) {
+            next = dfa.start_state();
+        }
+    }
+    *state_id = next;
+    match (dfa.is_match_state(next), restarted) {
+        (false, _) => Step::NoMatch,
+        (true, false) => Step::Match,
+        (true, true) => Step::Rematch,
+    }
+}
+
+/// Enumerate reversed input, leaving out every `\r` that is followed by `\n`.
+fn skip_crlf(bytes: impl Iterator<Item=u8>) -> impl Iterator<Item=(usize, u8)> {
+    let mut next = None;
+    bytes.enumerate().filter(move |&(_, b)| {
+        let skip = b == b'\r' && next == Some(b'\n');
+        next = Some(b);
+        !skip
+    })
+}
+
 /// Match object.
 /// 
 /// Contains the stream positions of the match.
-/// If the start of a match could not be found, `start` will be `None`.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Match {
-    pub start: Option<usize>,
+    pub start: StartBound,
     pub end: usize,
 }
 
+/// The start of a match, as far as it could be found.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StartBound {
+    /// the match starts at this stream position
+    Exact(usize),
+    /// The match extends beyond the data searched for its start: the buffer or the window of the search.
+    /// It starts at or before this stream position, the oldest one searched.
+    AtOrBefore(usize),
+}
+
+impl StartBound {
+    /// The stream position, exact or not.
+    pub fn position(&self) -> usize {
+        match *self {
+            StartBound::Exact(position) | StartBound::AtOrBefore(position) => position,
+        }
+    }
+
+    pub fn exact(&self) -> Option<usize> {
+        match *self {
+            StartBound::Exact(position) => Some(position),
+            StartBound::AtOrBefore(_) => None,
+        }
+    }
+
+    /// Apply `f` to the position, keeping whether it is exact.
+    pub fn map(self, f: impl FnOnce(usize) -> usize) -> StartBound {
+        match self {
+            StartBound::Exact(position) => StartBound::Exact(f(position)),
+            StartBound::AtOrBefore(position) => StartBound::AtOrBefore(f(position)),
+        }
+    }
+}
+
+impl Match {
+    /// A match from `start` to `end`.
+    pub fn new(start: usize, end: usize) -> Match {
+        Match { start: StartBound::Exact(start), end }
+    }
+
+    /// The start, if it was found.
+    pub fn start(&self) -> Option<usize> {
+        self.start.exact()
+    }
+
+    /// The stream positions of the match, if its start was found.
+    pub fn range(&self) -> Option<Range<usize>> {
+        self.start().map(|start| start .. self.end)
+    }
+
+    /// Length of the match, if its start was found.
+    pub fn len(&self) -> Option<usize> {
+        self.start().map(|start| self.end - start)
+    }
+
+    /// The match is empty, if its start was found.
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+}
+
+/// Why `checked_match_data` can not return the data of a match.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MatchDataError {
+    /// part of the data is no longer in the buffer, or the start of the match is not exactly known
+    Evicted,
+    /// the match ends after the data pushed so far
+    NotPushed,
+    /// the match ends before its start
+    Inverted,
+}
+
 /// Input data for a Match.
 /// 
 /// Internally composed of two slices into the ringbuffer.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone)]
 pub struct MatchData<'a> {
     pub head: &'a [u8],
     pub tail: &'a [u8],
 }
 impl<'a> MatchData<'a> {
-    /// Obtain the String for this match data.
-    /// 
-    /// Warning: Allocates.
-    pub fn to_string(&self) -> String {
-        format!("{}{}", String::from_utf8_lossy(self.head), String::from_utf8_lossy(self.tail))
-    }
-
     /// Obtain the data of this match as a `Vec<u8>`
     pub fn to_vec(&self) -> Vec<u8> {
         [self.head, self.tail].concat()
@@ -255,6 +2713,41 @@ impl<'a> MatchData<'a> {
     pub fn len(&self) -> usize {
         self.head.len() + self.tail.len()
     }
+
+    /// Whether the match data is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bytes of the match data, in order.
+    pub fn bytes(&self) -> impl Iterator<Item=u8> + 'a {
+        self.head.iter().chain(self.tail).copied()
+    }
+}
+
+/// Displays the match data as lossy UTF-8.
+/// 
+/// `to_string()` obtains the String for this match data. Warning: Allocates.
+/// A character split between the two slices is decoded as a whole.
+impl<'a> fmt::Display for MatchData<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.tail.is_empty() {
+            write!(f, "{}", String::from_utf8_lossy(self.head))
+        } else {
+            write!(f, "{}", String::from_utf8_lossy(&self.to_vec()))
+        }
+    }
+}
+
+/// Shows the match data as a byte string, like `"GET /\xff"`.
+impl<'a> fmt::Debug for MatchData<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        for b in self.bytes() {
+            write!(f, "{}", b.escape_ascii())?;
+        }
+        write!(f, "\"")
+    }
 }
 
 impl<'a> PartialEq<[u8]> for MatchData<'a> {
@@ -0,0 +1,114 @@
+//! Matching against a sliding window of the last lines.
+//!
+//! Multi-line log signatures, like an exception followed by a certain stack frame,
+//! are searched in the last `n` complete lines, including their `\n`.
+//! A match is reported once, with the line it ends in.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use regex_automata::{Regex, DFA};
+#[cfg(feature = "std")]
+use regex_automata::DenseDFA;
+
+use crate::{Match, Error, matches_empty};
+
+/// Searches its patterns in the last `n` lines after each line.
+///
+/// A line is held until it is complete, so very long lines are held in full.
+pub struct LineWindow<D: DFA> {
+    regexes: Vec<Regex<D>>,
+    lines: usize,
+    /// the last complete lines, followed by the incomplete one
+    window: Vec<u8>,
+    /// length of each complete line in the window
+    line_lens: VecDeque<usize>,
+    /// stream position of the start of the window
+    offset: usize,
+    /// (search_nr, match, data) found by the last `push_slice` or `finish`
+    found: Vec<(usize, Match, Vec<u8>)>,
+}
+
+impl<D: DFA> LineWindow<D> {
+    /// Search windows of `lines` lines. Values below 1 are treated as 1.
+    pub fn new(lines: usize) -> Self {
+        LineWindow {
+            regexes: vec![],
+            lines: lines.max(1),
+            window: vec![],
+            line_lens: VecDeque::new(),
+            offset: 0,
+            found: vec![],
+        }
+    }
+
+    /// Add a search for `regex`. Fails with `EmptyMatch` if it can match the empty string.
+    ///
+    /// Returns the identifier for this search.
+    pub fn add_regex(&mut self, regex: Regex<D>) -> Result<usize, Error> {
+        if matches_empty(&regex) {
+            return Err(Error::EmptyMatch);
+        }
+        self.regexes.push(regex);
+        Ok(self.regexes.len() - 1)
+    }
+
+    pub fn push_slice(&mut self, chunk: &[u8]) {
+        self.found.clear();
+        let mut rest = chunk;
+        while let Some(i) = memchr::memchr(b'\n', rest) {
+            self.window.extend_from_slice(&rest[..= i]);
+            self.complete_line();
+            rest = &rest[i + 1 ..];
+        }
+        self.window.extend_from_slice(rest);
+    }
+
+    /// The stream ended, search the last line even without a line break.
+    pub fn finish(&mut self) {
+        self.found.clear();
+        if self.window.len() > self.line_lens.iter().sum::<usize>() {
+            self.complete_line();
+        }
+    }
+
+    fn complete_line(&mut self) {
+        let held: usize = self.line_lens.iter().sum();
+        self.line_lens.push_back(self.window.len() - held);
+        if self.line_lens.len() > self.lines {
+            let evicted = self.line_lens.pop_front().unwrap();
+            self.window.drain(.. evicted);
+            self.offset += evicted;
+        }
+
+        let last_line = self.window.len() - self.line_lens.back().unwrap();
+        for (search_nr, regex) in self.regexes.iter().enumerate() {
+            for (start, end) in regex.find_iter(&self.window) {
+                if end > last_line {
+                    let match_ = Match::new(self.offset + start, self.offset + end);
+                    self.found.push((search_nr, match_, self.window[start .. end].to_vec()));
+                }
+            }
+        }
+    }
+
+    /// The (search_nr, match, data) found by the last `push_slice` or `finish`.
+    pub fn matches(&self) -> impl Iterator<Item=&(usize, Match, Vec<u8>)> + '_ {
+        self.found.iter()
+    }
+
+    /// Number of bytes pushed so far.
+    pub fn position(&self) -> usize {
+        self.offset + self.window.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl LineWindow<DenseDFA<Vec<usize>, usize>> {
+    /// Add a search for the regex `regex_str`, see `add_regex`.
+    pub fn add_regex_str(&mut self, regex_str: &str) -> Result<usize, Error> {
+        let regex = regex_automata::RegexBuilder::new().build(regex_str).map_err(|_| Error::InvalidRegex)?;
+        self.add_regex(regex)
+    }
+}
@@ -0,0 +1,56 @@
+//! Compiling large pattern sets on all cores.
+//!
+//! Compiling the DFAs dominates loading rule sets with hundreds of patterns.
+//! The patterns are compiled on scoped threads and added to the searcher in order afterwards.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use regex_automata::{Regex, RegexBuilder, DenseDFA};
+
+use crate::{RingSearcher, Error, PatternError};
+
+type Dense = DenseDFA<Vec<usize>, usize>;
+
+/// Compile `patterns` with `builder` on up to `available_parallelism` threads.
+/// The results are in the order of `patterns`.
+pub(crate) fn compile_all(builder: &RegexBuilder, patterns: &[&str]) -> Vec<Result<Regex<Dense>, regex_automata::Error>> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(patterns.len());
+    let next = AtomicUsize::new(0);
+    let mut compiled: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = (0 .. threads).map(|_| scope.spawn(|| {
+            let mut done = vec![];
+            loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(pattern) = patterns.get(i) else { break done };
+                done.push((i, builder.build(pattern)));
+            }
+        })).collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    compiled.sort_unstable_by_key(|&(i, _)| i);
+    compiled.into_iter().map(|(_, result)| result).collect()
+}
+
+impl RingSearcher<Dense> {
+    /// Add searches for all `patterns`, compiling them in parallel.
+    ///
+    /// Nothing is added if a pattern is invalid.
+    /// Returns the identifiers of the searches, in the order of `patterns`.
+    pub fn add_regex_strs_parallel(&mut self, patterns: &[&str]) -> Result<Vec<usize>, Error> {
+        let compiled = compile_all(&RegexBuilder::new(), patterns).into_iter()
+            .collect::<Result<Vec<_>, _>>().map_err(|_| Error::InvalidRegex)?;
+        compiled.into_iter().zip(patterns).map(|(regex, &pattern)| {
+            let search_nr = self.add_dense_regex(regex)?;
+            self.searches[search_nr].pattern = Some(pattern.into());
+            Ok(search_nr)
+        }).collect()
+    }
+
+    /// Like `try_add_many`, compiling the patterns in parallel.
+    pub fn try_add_many_parallel(&mut self, patterns: &[&str]) -> Vec<Result<usize, PatternError>> {
+        compile_all(&RegexBuilder::new(), patterns).into_iter().zip(patterns)
+            .map(|(compiled, pattern)| self.add_compiled(compiled, pattern))
+            .collect()
+    }
+}
@@ -0,0 +1,366 @@
+//! Saving the complete state of a searcher and restoring it.
+//!
+//! Layout: `MAGIC`, the payload length and its `fingerprint`, then the payload.
+//! The payload holds the settings, the buffer, the DFAs and state of each search, the regions
+//! and the matches not yet drained.
+//! Numbers are LEB128 varints, options are a flag byte followed by the value.
+
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+use std::mem::size_of;
+use std::sync::Arc;
+
+use regex_automata::{Regex, DenseDFA};
+
+use crate::{RingSearcher, Search, Engine, Region, Lines, Anchor, Match, StartBound, fingerprint};
+
+const MAGIC: &[u8; 8] = b"rring\0s8";
+
+type Dense = DenseDFA<Vec<usize>, usize>;
+
+impl RingSearcher<Dense> {
+    /// Write the state of the searcher: settings, buffer, patterns and the state of every search,
+    /// including matches in progress and the matches not yet drained.
+    ///
+    /// `load` continues exactly where this searcher is. Callbacks, the cancel flag and spilled data are not saved.
+    /// Fuzzy searches and searches confirmed by a fancy regex can not be saved.
+    pub fn save(&self, mut w: impl Write) -> io::Result<()> {
+        let mut out = vec![];
+        for &n in &[self.position, self.buffer_size, self.default_window, self.records, self.match_count] {
+            write_varint(&mut out, n);
+        }
+        for &flag in &[self.unbounded, self.crlf, self.pending_cr, self.record_reset, self.fingerprints, self.queue_matches, self.finished, self.case_fold] {
+            out.push(flag as u8);
+        }
+        out.push(self.delimiter);
+        write_option(&mut out, self.memory_limit);
+        write_option(&mut out, self.truncated);
+        write_option(&mut out, self.pin_limit);
+        match self.user_byte_map {
+            Some(ref map) => {
+                out.push(1);
+                out.extend_from_slice(&map[..]);
+            }
+            None => out.push(0),
+        }
+        match self.lines {
+            Some(ref lines) => {
+                out.push(1);
+                write_varint(&mut out, lines.count);
+                write_varint(&mut out, lines.evicted_line_start);
+            }
+            None => out.push(0),
+        }
+        let (head, tail) = self.buffer.as_slices();
+        write_varint(&mut out, head.len() + tail.len());
+        out.extend_from_slice(head);
+        out.extend_from_slice(tail);
+
+        write_varint(&mut out, self.searches.len());
+        for (i, search) in self.searches.iter().enumerate() {
+            save_search(&mut out, search).map_err(|e| io::Error::new(e.kind(), format!("search {}: {}", i, e)))?;
+        }
+        write_varint(&mut out, self.regions.len());
+        for region in &self.regions {
+            write_varint(&mut out, region.enter);
+            write_varint(&mut out, region.leave);
+            out.push(region.inside as u8);
+        }
+        write_varint(&mut out, self.completed.len());
+        for &(id, end) in &self.completed {
+            write_varint(&mut out, id);
+            write_varint(&mut out, end);
+        }
+        write_varint(&mut out, self.found.len());
+        for &(id, match_) in &self.found {
+            write_varint(&mut out, id);
+            // `2 * start + 1` if exact, `2 * start` if the match starts at or before it
+            write_varint(&mut out, match match_.start {
+                StartBound::Exact(start) => 2 * start + 1,
+                StartBound::AtOrBefore(start) => 2 * start,
+            });
+            write_varint(&mut out, match_.end);
+        }
+
+        w.write_all(MAGIC)?;
+        w.write_all(&(out.len() as u64).to_le_bytes())?;
+        w.write_all(&fingerprint(&out).to_le_bytes())?;
+        w.write_all(&out)
+    }
+
+    /// Restore a searcher written by `save`.
+    ///
+    /// # Safety
+    /// The DFAs are not validated, malformed ones lead to out of bounds reads.
+    /// The checksum only detects accidental corruption, so the data has to be written by `save`
+    /// on a platform with the same byte order and pointer width, and must not come from an untrusted source.
+    pub unsafe fn load(mut r: impl Read) -> io::Result<Self> {
+        let mut header = [0; 24];
+        r.read_exact(&mut header)?;
+        if &header[.. 8] != MAGIC {
+            return Err(invalid("not a saved searcher"));
+        }
+        let len = u64::from_le_bytes(header[8 .. 16].try_into().unwrap());
+        let checksum = u64::from_le_bytes(header[16 ..].try_into().unwrap());
+        let mut data = vec![];
+        r.take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len || fingerprint(&data) != checksum {
+            return Err(invalid("corrupt searcher state"));
+        }
+
+        let mut input = &data[..];
+        let r = &mut input;
+        let position = read_varint(r)?;
+        let buffer_size = read_varint(r)?;
+        let mut searcher = RingSearcher::new(buffer_size);
+        searcher.position = position;
+        searcher.default_window = read_varint(r)?;
+        searcher.records = read_varint(r)?;
+        searcher.match_count = read_varint(r)?;
+        searcher.next_progress = position;
+        searcher.unbounded = read_flag(r)?;
+        searcher.crlf = read_flag(r)?;
+        searcher.pending_cr = read_flag(r)?;
+        searcher.record_reset = read_flag(r)?;
+        searcher.fingerprints = read_flag(r)?;
+        searcher.queue_matches = read_flag(r)?;
+        searcher.finished = read_flag(r)?;
+        searcher.case_fold = read_flag(r)?;
+        searcher.delimiter = read_slice(r, 1)?[0];
+        searcher.memory_limit = read_option(r)?;
+        searcher.truncated = read_option(r)?;
+        searcher.pin_limit = read_option(r)?;
+        if read_flag(r)? {
+            let mut map = [0; 256];
+            map.copy_from_slice(read_slice(r, 256)?);
+            searcher.user_byte_map = Some(Arc::new(map));
+        }
+        searcher.update_byte_map();
+        if read_flag(r)? {
+            searcher.lines = Some(Lines { count: read_varint(r)?, evicted_line_start: read_varint(r)? });
+        }
+        let buffer_len = read_varint(r)?;
+        searcher.buffer.extend(read_slice(r, buffer_len)?);
+
+        for _ in 0 .. read_varint(r)? {
+            // SAFETY: forwarded to the caller
+            let search = unsafe { load_search(r)? };
+            searcher.searches.push(search);
+        }
+        for _ in 0 .. read_varint(r)? {
+            let (enter, leave, inside) = (read_varint(r)?, read_varint(r)?, read_flag(r)?);
+            searcher.regions.push(Region { enter, leave, inside });
+        }
+        for _ in 0 .. read_varint(r)? {
+            searcher.completed.push((read_varint(r)?, read_varint(r)?));
+        }
+        for _ in 0 .. read_varint(r)? {
+            let id = read_varint(r)?;
+            let start = match read_varint(r)? {
+                n if n % 2 == 1 => StartBound::Exact(n / 2),
+                n => StartBound::AtOrBefore(n / 2),
+            };
+            let end = read_varint(r)?;
+            searcher.found.push((id, Match { start, end }));
+        }
+        if !input.is_empty() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(searcher)
+    }
+}
+
+fn save_search(out: &mut Vec<u8>, search: &Search<Dense>) -> io::Result<()> {
+    #[cfg(feature = "fancy")]
+    if search.confirm.is_some() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "fancy regexes can not be saved"));
+    }
+    let (regex, state_id) = match search.engine {
+        Engine::Dfa { ref regex, state_id } => (regex, state_id),
+        Engine::Fuzzy(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "fuzzy searches can not be saved")),
+        Engine::Removed => {
+            out.push(0);
+            return Ok(());
+        }
+    };
+    out.push(1);
+    for dfa in [regex.forward(), regex.reverse()] {
+        let bytes = dfa.to_bytes_native_endian().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        write_varint(out, bytes.len());
+        out.extend_from_slice(&bytes);
+    }
+    write_varint(out, state_id);
+    write_option(out, search.match_end);
+    write_option(out, search.window);
+    out.push(search.paused as u8);
+    write_option(out, search.region);
+    write_option(out, search.preceded_by.map(|(other, _)| other));
+    write_option(out, search.preceded_by.map(|(_, distance)| distance));
+    write_option(out, search.last_end);
+    write_option(out, search.record_reset.map(|reset| reset as usize));
+    match search.pattern {
+        Some(ref pattern) => {
+            out.push(1);
+            write_varint(out, pattern.len());
+            out.extend_from_slice(pattern.as_bytes());
+        }
+        None => out.push(0),
+    }
+    write_option(out, search.overflow_start);
+    out.push(search.overflowing as u8);
+    write_option(out, search.pin_start);
+    write_varint(out, search.since);
+    match search.anchor {
+        Some(ref anchor) => {
+            out.push(1);
+            write_option(out, anchor.source);
+            write_varint(out, anchor.pending.len());
+            for &start in &anchor.pending {
+                write_varint(out, start);
+            }
+            write_option(out, anchor.start);
+            write_varint(out, anchor.scanned);
+        }
+        None => out.push(0),
+    }
+    write_varint(out, search.overflowed.len());
+    for &end in &search.overflowed {
+        write_varint(out, end);
+    }
+    Ok(())
+}
+
+/// # Safety
+/// See `RingSearcher::load`.
+unsafe fn load_search(r: &mut &[u8]) -> io::Result<Search<Dense>> {
+    if !read_flag(r)? {
+        let mut search = Search::new(Engine::Removed, 0);
+        search.paused = true;
+        return Ok(search);
+    }
+    // SAFETY: forwarded to the caller
+    let forward = unsafe { load_dfa(r)? };
+    let reverse = unsafe { load_dfa(r)? };
+    let memory = forward.memory_usage() + reverse.memory_usage();
+    let regex = Regex::from_dfas(forward, reverse);
+    let state_id = read_varint(r)?;
+    if state_id >= regex.forward().memory_usage() / size_of::<usize>() {
+        return Err(invalid("state out of range"));
+    }
+    let mut search = Search::new(Engine::Dfa { regex: Arc::new(regex), state_id }, memory);
+    search.match_end = read_option(r)?;
+    search.window = read_option(r)?;
+    search.paused = read_flag(r)?;
+    search.region = read_option(r)?;
+    let other = read_option(r)?;
+    let distance = read_option(r)?;
+    search.preceded_by = other.zip(distance);
+    search.last_end = read_option(r)?;
+    search.record_reset = read_option(r)?.map(|reset| reset != 0);
+    if read_flag(r)? {
+        let len = read_varint(r)?;
+        let pattern = std::str::from_utf8(read_slice(r, len)?).map_err(|_| invalid("pattern is not UTF-8"))?;
+        search.pattern = Some(pattern.into());
+    }
+    search.overflow_start = read_option(r)?;
+    search.overflowing = read_flag(r)?;
+    search.pin_start = read_option(r)?;
+    search.since = read_varint(r)?;
+    if read_flag(r)? {
+        let source = read_option(r)?;
+        let mut pending = BTreeSet::new();
+        for _ in 0 .. read_varint(r)? {
+            pending.insert(read_varint(r)?);
+        }
+        let start = read_option(r)?;
+        let scanned = read_varint(r)?;
+        search.anchor = Some(Anchor { source, pending, start, scanned });
+    }
+    for _ in 0 .. read_varint(r)? {
+        search.overflowed.push(read_varint(r)?);
+    }
+    Ok(search)
+}
+
+/// # Safety
+/// See `RingSearcher::load`.
+unsafe fn load_dfa(r: &mut &[u8]) -> io::Result<Dense> {
+    let len = read_varint(r)?;
+    let bytes = read_slice(r, len)?;
+
+    // `from_bytes` needs the data aligned to the state id
+    let mut aligned = vec![0usize; len.div_ceil(size_of::<usize>())];
+    for (word, chunk) in aligned.iter_mut().zip(bytes.chunks(size_of::<usize>())) {
+        let mut buf = [0; size_of::<usize>()];
+        buf[.. chunk.len()].copy_from_slice(chunk);
+        *word = usize::from_ne_bytes(buf);
+    }
+    // SAFETY: `aligned` holds `len` initialized bytes in native order.
+    let bytes = unsafe { std::slice::from_raw_parts(aligned.as_ptr() as *const u8, len) };
+    // SAFETY: the caller of `load` guarantees the bytes were written by `to_bytes_native_endian` in `save`.
+    let dfa: DenseDFA<&[usize], usize> = unsafe { DenseDFA::from_bytes(bytes) };
+    Ok(dfa.to_owned())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_option(out: &mut Vec<u8>, n: Option<usize>) {
+    match n {
+        Some(n) => {
+            out.push(1);
+            write_varint(out, n);
+        }
+        None => out.push(0),
+    }
+}
+
+fn read_slice<'a>(r: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if r.len() < len {
+        return Err(invalid("truncated searcher state"));
+    }
+    let (data, rest) = r.split_at(len);
+    *r = rest;
+    Ok(data)
+}
+
+fn read_flag(r: &mut &[u8]) -> io::Result<bool> {
+    match read_slice(r, 1)?[0] {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(invalid("invalid flag")),
+    }
+}
+
+fn read_varint(r: &mut &[u8]) -> io::Result<usize> {
+    let mut n = 0;
+    for shift in (0 .. usize::BITS).step_by(7) {
+        let byte = read_slice(r, 1)?[0];
+        n |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+fn read_option(r: &mut &[u8]) -> io::Result<Option<usize>> {
+    match read_flag(r)? {
+        true => read_varint(r).map(Some),
+        false => Ok(None),
+    }
+}
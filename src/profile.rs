@@ -0,0 +1,101 @@
+//! Measuring the cost of each search, to find the patterns that slow scanning down.
+//!
+//! With a `Budget`, searches that cost too much are quarantined:
+//! they are paused, so one bad rule in a large set can not stall the pipeline.
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use regex_automata::DFA;
+
+use crate::RingSearcher;
+
+/// What a search cost since it was added or the profiles were cleared.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchProfile {
+    /// time spent stepping the search over the pieces of `push_slice`. Single bytes are not timed.
+    pub time: Duration,
+    /// number of bytes `time` was spent on
+    pub bytes: usize,
+    /// number of bytes after which the search was not in its start state
+    pub active_bytes: usize,
+}
+
+impl SearchProfile {
+    /// Average time per byte, if any bytes were timed.
+    pub fn time_per_byte(&self) -> Option<Duration> {
+        let bytes = u32::try_from(self.bytes).unwrap_or(u32::MAX);
+        (bytes > 0).then(|| self.time / bytes)
+    }
+}
+
+/// Limits on the cost of a search.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// largest average time per byte, applied once `warmup` bytes were timed
+    pub time_per_byte: Option<Duration>,
+    pub warmup: usize,
+    /// most bytes a search may stay out of its start state at a time
+    pub active_run: Option<usize>,
+}
+
+impl<D: DFA> RingSearcher<D> {
+    /// The cost of search `search_nr`.
+    pub fn profile(&self, search_nr: usize) -> SearchProfile {
+        self.searches[search_nr].profile
+    }
+
+    /// (search identifier, profile) of all searches, the most expensive first.
+    pub fn slowest_searches(&self) -> Vec<(usize, SearchProfile)> {
+        let mut profiles: Vec<_> = self.searches.iter().map(|search| search.profile).enumerate().collect();
+        profiles.sort_by_key(|&(i, profile)| (core::cmp::Reverse((profile.time, profile.active_bytes)), i));
+        profiles
+    }
+
+    /// Start measuring from zero.
+    pub fn clear_profiles(&mut self) {
+        for search in &mut self.searches {
+            search.profile = SearchProfile::default();
+        }
+    }
+
+    /// Quarantine searches exceeding `budget`, or none.
+    /// 
+    /// A quarantined search is paused and its match in progress dropped.
+    /// `resume` lets it continue.
+    pub fn set_budget(&mut self, budget: Option<Budget>) {
+        self.budget = budget;
+    }
+
+    /// The searches quarantined so far.
+    pub fn quarantined(&self) -> Vec<usize> {
+        self.searches.iter().enumerate().filter(|(_, search)| search.quarantined).map(|(i, _)| i).collect()
+    }
+
+    pub(crate) fn enforce_budget(&mut self) {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return,
+        };
+        let position = self.position;
+        for (i, search) in self.searches.iter_mut().enumerate() {
+            if search.paused {
+                continue;
+            }
+            let slow = search.profile.bytes >= budget.warmup
+                && search.profile.time_per_byte().zip(budget.time_per_byte).is_some_and(|(time, limit)| time > limit);
+            let stuck = budget.active_run.zip(search.pin_start).is_some_and(|(limit, start)| position - start > limit);
+            if slow || stuck {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(search = i, slow, stuck, "search quarantined");
+                #[cfg(not(feature = "tracing"))]
+                let _ = i;
+                search.paused = true;
+                search.quarantined = true;
+                search.engine.reset();
+                search.match_end = None;
+                search.pin_start = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Compiled patterns owned in one place and shared by any number of searchers.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use regex_automata::{Regex, DFA};
+#[cfg(feature = "std")]
+use regex_automata::DenseDFA;
+
+use crate::{RingSearcher, Error};
+
+/// Refers to a pattern of a `PatternRegistry`. Handles of removed patterns are not reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PatternHandle(usize);
+
+struct Entry<D: DFA> {
+    regex: Arc<Regex<D>>,
+    pattern: Option<Arc<str>>,
+    /// memory used by the DFAs, if known
+    memory: usize,
+}
+
+/// Owns compiled regexes. Searchers added to with `RingSearcher::add_pattern` share them,
+/// a removed pattern lives on in the searchers using it.
+pub struct PatternRegistry<D: DFA> {
+    entries: Vec<Option<Entry<D>>>,
+}
+
+impl<D: DFA> PatternRegistry<D> {
+    pub fn new() -> Self {
+        PatternRegistry { entries: vec![] }
+    }
+
+    /// Add `regex`, returning the handle for it.
+    pub fn add(&mut self, regex: Regex<D>) -> PatternHandle {
+        self.insert(Entry { regex: Arc::new(regex), pattern: None, memory: 0 })
+    }
+
+    fn insert(&mut self, entry: Entry<D>) -> PatternHandle {
+        self.entries.push(Some(entry));
+        PatternHandle(self.entries.len() - 1)
+    }
+
+    /// The compiled regex of `handle`.
+    pub fn get(&self, handle: PatternHandle) -> Option<&Arc<Regex<D>>> {
+        self.entry(handle).map(|entry| &entry.regex)
+    }
+
+    /// The source of the pattern of `handle`, if added as a string.
+    pub fn pattern(&self, handle: PatternHandle) -> Option<&str> {
+        self.entry(handle).and_then(|entry| entry.pattern.as_deref())
+    }
+
+    fn entry(&self, handle: PatternHandle) -> Option<&Entry<D>> {
+        self.entries.get(handle.0).and_then(Option::as_ref)
+    }
+
+    /// Remove the pattern of `handle` from the registry. Returns whether it was present.
+    pub fn remove(&mut self, handle: PatternHandle) -> bool {
+        self.entries.get_mut(handle.0).and_then(Option::take).is_some()
+    }
+
+    /// Number of patterns in the registry.
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The handles of all patterns, in the order they were added.
+    pub fn handles(&self) -> impl Iterator<Item=PatternHandle> + '_ {
+        self.entries.iter().enumerate().filter(|(_, entry)| entry.is_some()).map(|(i, _)| PatternHandle(i))
+    }
+}
+
+impl<D: DFA> Default for PatternRegistry<D> {
+    fn default() -> Self {
+        PatternRegistry::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl PatternRegistry<DenseDFA<Vec<usize>, usize>> {
+    /// Compile `regex_str` and add it, returning the handle for it.
+    pub fn add_str(&mut self, regex_str: &str) -> Result<PatternHandle, Error> {
+        let regex = regex_automata::RegexBuilder::new().build(regex_str).map_err(|_| Error::InvalidRegex)?;
+        let memory = regex.forward().memory_usage() + regex.reverse().memory_usage();
+        Ok(self.insert(Entry { regex: Arc::new(regex), pattern: Some(regex_str.into()), memory }))
+    }
+}
+
+impl<D: DFA> RingSearcher<D> {
+    /// Add a search for the pattern of `handle`, sharing its DFAs with the registry.
+    ///
+    /// Returns the identifier for this search.
+    pub fn add_pattern(&mut self, registry: &PatternRegistry<D>, handle: PatternHandle) -> Result<usize, Error> {
+        let entry = registry.entry(handle).ok_or(Error::UnknownPattern)?;
+        let search_nr = self.add_shared_search(entry.regex.clone(), entry.memory)?;
+        self.searches[search_nr].pattern = entry.pattern.clone();
+        Ok(search_nr)
+    }
+}
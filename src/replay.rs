@@ -0,0 +1,183 @@
+//! Recording a run of a searcher and replaying it.
+//!
+//! A `Recorder` wraps a searcher and writes every pushed chunk and every reported match to a log.
+//! `replay` feeds the logged input to a searcher with the same patterns and reports
+//! the first point where its matches differ from the logged ones.
+//!
+//! Log entries:
+//!  - `D` length bytes: pushed data
+//!  - `M` search start end: a match reported after the preceding data,
+//!    `start` is `2 * start + 1` if exact, `2 * start` if the match starts at or before it
+//!  - `F`: the input ended, the following matches are the final matches
+//!
+//! Numbers are LEB128 varints.
+
+use std::io::{self, Read, Write};
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Match, StartBound};
+
+/// Records the input and the matches of a searcher.
+pub struct Recorder<D: DFA, W: Write> {
+    searcher: RingSearcher<D>,
+    log: W,
+}
+
+impl<D: DFA, W: Write> Recorder<D, W> {
+    pub fn new(searcher: RingSearcher<D>, log: W) -> Self {
+        Recorder { searcher, log }
+    }
+
+    pub fn searcher(&self) -> &RingSearcher<D> {
+        &self.searcher
+    }
+
+    /// Like `RingSearcher::push`.
+    pub fn push(&mut self, input: u8) -> io::Result<()> {
+        self.push_slice(&[input])
+    }
+
+    /// Like `RingSearcher::push_slice`.
+    pub fn push_slice(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.log.write_all(b"D")?;
+        write_varint(&mut self.log, chunk.len())?;
+        self.log.write_all(chunk)?;
+        self.searcher.push_slice(chunk);
+        for (id, match_) in self.searcher.matches() {
+            write_match(&mut self.log, id, &match_)?;
+        }
+        Ok(())
+    }
+
+    /// Like `RingSearcher::matches`.
+    pub fn matches(&self) -> impl Iterator<Item=(usize, Match)> + '_ {
+        self.searcher.matches()
+    }
+
+    /// Record the end of the input and return the final matches.
+    pub fn finish(&mut self) -> io::Result<Vec<(usize, Match)>> {
+        self.log.write_all(b"F")?;
+        let matches: Vec<_> = self.searcher.final_matches().collect();
+        for (id, match_) in &matches {
+            write_match(&mut self.log, *id, match_)?;
+        }
+        self.log.flush()?;
+        Ok(matches)
+    }
+
+    pub fn into_inner(self) -> (RingSearcher<D>, W) {
+        (self.searcher, self.log)
+    }
+}
+
+/// Where a replay differs from the log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// stream position after the chunk that produced the matches
+    pub position: usize,
+    /// the input had ended, the matches are final matches
+    pub finished: bool,
+    /// the logged matches
+    pub expected: Vec<(usize, Match)>,
+    /// the matches of the replay
+    pub found: Vec<(usize, Match)>,
+}
+
+/// Feed the input recorded in `log` to `searcher`, which should have the same patterns and settings
+/// as the recorded one. Returns the first mismatch, if any.
+pub fn replay<D: DFA>(searcher: &mut RingSearcher<D>, mut log: impl Read) -> io::Result<Option<Mismatch>> {
+    let mut chunk = vec![];
+    let mut expected = vec![];
+    let mut finished = false;
+    let mut tag = read_tag(&mut log)?;
+    loop {
+        if tag == Some(b'M') {
+            expected.push(read_match(&mut log)?);
+            tag = read_tag(&mut log)?;
+            continue;
+        }
+
+        // the matches of the preceding chunk are complete
+        let found: Vec<_> = if finished {
+            searcher.final_matches().collect()
+        } else {
+            searcher.matches().collect()
+        };
+        if found != expected {
+            return Ok(Some(Mismatch { position: searcher.position(), finished, expected, found }));
+        }
+        expected.clear();
+
+        match tag {
+            None => return Ok(None),
+            Some(b'D') => {
+                let len = read_varint(&mut log)?;
+                // the length is not trusted: only what is actually there is allocated
+                chunk.clear();
+                log.by_ref().take(len as u64).read_to_end(&mut chunk)?;
+                if chunk.len() < len {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated data entry"));
+                }
+                searcher.push_slice(&chunk);
+            }
+            Some(b'F') if !finished => finished = true,
+            Some(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid log entry")),
+        }
+        tag = read_tag(&mut log)?;
+    }
+}
+
+fn write_match(w: &mut impl Write, id: usize, match_: &Match) -> io::Result<()> {
+    w.write_all(b"M")?;
+    write_varint(w, id)?;
+    let start = match match_.start {
+        StartBound::Exact(start) => 2 * start + 1,
+        StartBound::AtOrBefore(start) => 2 * start,
+    };
+    write_varint(w, start)?;
+    write_varint(w, match_.end)
+}
+
+fn read_match(r: &mut impl Read) -> io::Result<(usize, Match)> {
+    let id = read_varint(r)?;
+    let start = match read_varint(r)? {
+        n if n % 2 == 1 => StartBound::Exact(n / 2),
+        n => StartBound::AtOrBefore(n / 2),
+    };
+    let end = read_varint(r)?;
+    Ok((id, Match { start, end }))
+}
+
+fn write_varint(w: &mut impl Write, mut n: usize) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(r: &mut impl Read) -> io::Result<usize> {
+    let mut n = 0;
+    for shift in (0 .. usize::BITS).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        n |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "varint too long"))
+}
+
+/// The next entry tag, `None` at the end of the log.
+fn read_tag(r: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut tag = [0];
+    match r.read(&mut tag)? {
+        0 => Ok(None),
+        _ => Ok(Some(tag[0])),
+    }
+}
@@ -0,0 +1,237 @@
+//! Loading searches from rule files.
+//!
+//! A rule file contains a list of rules, for example in YAML:
+//!
+//! ```yaml
+//! rules:
+//!   - name: private-key
+//!     regex: "-----BEGIN [A-Z ]*PRIVATE KEY-----"
+//!     severity: high
+//!   - name: password
+//!     regex: "password=\\S+"
+//!     flags: i
+//!     max_length: 256
+//!     tags: [credentials]
+//!     actions: [alert, log]
+//! ```
+//!
+//! The search identifiers of the built searcher are the indices into `RuleSet::rules`.
+//! `RuleSet::alert` turns a match into an `Alert` carrying the rule metadata.
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+
+use regex_automata::{RegexBuilder, DenseDFA};
+use serde::Deserialize;
+
+use crate::{RingSearcher, Error, Match, MatchData, flags_builder};
+
+/// Maximum number of match bytes included in an `Alert`.
+pub const EXCERPT_LEN: usize = 256;
+
+/// How important a match of a rule is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single rule.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub regex: String,
+
+    /// Regex flags:
+    ///  - `i`: case insensitive
+    ///  - `s`: `.` matches `\n`
+    ///  - `x`: ignore whitespace
+    ///  - `U`: swap greed
+    #[serde(default)]
+    pub flags: String,
+
+    #[serde(default)]
+    pub severity: Option<Severity>,
+
+    /// Longest expected match. Used as the window of the search.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Free form actions for the consumer to take on a match.
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
+
+/// A list of rules, as read from a rule file.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Parse rules from YAML.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(s: &str) -> Result<RuleSet, Error> {
+        serde_yaml::from_str(s).map_err(|e| Error::InvalidRules(e.to_string()))
+    }
+
+    /// Parse rules from TOML. The rules are an array of tables named `rules`.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> Result<RuleSet, Error> {
+        toml::from_str(s).map_err(|e| Error::InvalidRules(e.to_string()))
+    }
+
+    /// Read a rule file. The format is determined by the extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<RuleSet, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| Error::InvalidRules(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "yaml")]
+            Some("yaml") | Some("yml") => RuleSet::from_yaml(&data),
+            #[cfg(feature = "toml")]
+            Some("toml") => RuleSet::from_toml(&data),
+            _ => Err(Error::InvalidRules(format!("{}: unknown rule file format", path.display())))
+        }
+    }
+
+    /// Create an alert for a match of search `search_nr` in the stream identified by `stream`.
+    pub fn alert(&self, stream: &str, search_nr: usize, match_: &Match, data: MatchData) -> Alert {
+        let rule = &self.rules[search_nr];
+        let mut excerpt = data.to_vec();
+        let truncated = excerpt.len() > EXCERPT_LEN;
+        excerpt.truncate(EXCERPT_LEN);
+        Alert {
+            rule: rule.name.clone(),
+            severity: rule.severity,
+            tags: rule.tags.clone(),
+            stream: stream.into(),
+            start: match_.start(),
+            end: match_.end,
+            excerpt,
+            truncated,
+        }
+    }
+
+    /// The rule for a search identifier.
+    pub fn rule(&self, search_nr: usize) -> Option<&Rule> {
+        self.rules.get(search_nr)
+    }
+
+    /// Create a searcher with the given ringbuffer size and add all rules to it.
+    pub fn build(&self, buffer_size: usize) -> Result<RingSearcher<DenseDFA<Vec<usize>, usize>>, Error> {
+        let mut searcher = RingSearcher::new(buffer_size);
+        self.add_to(&mut searcher)?;
+        Ok(searcher)
+    }
+
+    /// Add all rules to an existing searcher.
+    pub fn add_to(&self, searcher: &mut RingSearcher<DenseDFA<Vec<usize>, usize>>) -> Result<(), Error> {
+        for rule in &self.rules {
+            let regex = rule.builder()?.build(&rule.regex).map_err(|_| Error::InvalidRegex)?;
+            let search_nr = searcher.add_dense_regex(regex)?;
+            searcher.searches[search_nr].pattern = Some(rule.regex.as_str().into());
+            if let Some(max_length) = rule.max_length {
+                searcher.set_window(search_nr, max_length);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Rule {
+    fn builder(&self) -> Result<RegexBuilder, Error> {
+        flags_builder(&self.flags)
+            .map_err(|flag| Error::InvalidRules(format!("{}: unknown flag '{}'", self.name, flag)))
+    }
+}
+
+/// A match of a rule, with the metadata of the rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub rule: String,
+    pub severity: Option<Severity>,
+    pub tags: Vec<String>,
+    /// identifies the stream the match was found in
+    pub stream: String,
+    pub start: Option<usize>,
+    pub end: usize,
+    /// the first `EXCERPT_LEN` bytes of the match data
+    pub excerpt: Vec<u8>,
+    /// the match data was longer than the excerpt
+    pub truncated: bool,
+}
+
+impl Alert {
+    /// Format as a single line JSON object. The excerpt is included as lossy UTF-8.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        out.push_str("{\"rule\":");
+        json_str(&mut out, &self.rule);
+        out.push_str(",\"severity\":");
+        match self.severity {
+            Some(severity) => json_str(&mut out, severity.as_str()),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"tags\":[");
+        for (i, tag) in self.tags.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            json_str(&mut out, tag);
+        }
+        out.push_str("],\"stream\":");
+        json_str(&mut out, &self.stream);
+        match self.start {
+            Some(start) => write!(out, ",\"start\":{}", start).unwrap(),
+            None => out.push_str(",\"start\":null"),
+        }
+        write!(out, ",\"end\":{},\"excerpt\":", self.end).unwrap();
+        json_str(&mut out, &String::from_utf8_lossy(&self.excerpt));
+        write!(out, ",\"truncated\":{}}}", self.truncated).unwrap();
+        out
+    }
+}
+
+fn json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
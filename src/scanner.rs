@@ -0,0 +1,215 @@
+//! A common interface for engines scanning a stream.
+//!
+//! `StreamScanner` is implemented by `RingSearcher` (DFAs), `MemmemScanner` (a single literal)
+//! and, with the `aho-corasick` feature, `AhoCorasickScanner` (many literals).
+//! `MixedScanner` combines scanners of different kinds, so each pattern can use the engine suiting it.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Match};
+
+/// Steps over a stream and reports matches with their stream positions.
+pub trait StreamScanner {
+    /// Number of patterns. Matches are reported with identifiers below it.
+    fn patterns(&self) -> usize;
+
+    /// Scan `chunk`, which follows the data scanned so far,
+    /// calling `report` with the pattern identifier and the match for every match completed within it.
+    fn scan(&mut self, chunk: &[u8], report: &mut dyn FnMut(usize, Match));
+
+    /// The stream ended, report the matches still in progress.
+    fn finish(&mut self, report: &mut dyn FnMut(usize, Match));
+}
+
+impl<D: DFA> StreamScanner for RingSearcher<D> {
+    fn patterns(&self) -> usize {
+        self.num_searches()
+    }
+
+    fn scan(&mut self, chunk: &[u8], report: &mut dyn FnMut(usize, Match)) {
+        self.push_slice(chunk);
+        for (id, match_) in self.drain_matches() {
+            report(id, match_);
+        }
+    }
+
+    fn finish(&mut self, report: &mut dyn FnMut(usize, Match)) {
+        RingSearcher::finish(self);
+        for (id, match_) in self.drain_matches() {
+            report(id, match_);
+        }
+    }
+}
+
+/// Finds non-overlapping occurrences of one literal with `memchr::memmem`.
+#[cfg(feature = "std")]
+pub struct MemmemScanner {
+    finder: memchr::memmem::Finder<'static>,
+    /// the last `needle.len() - 1` bytes, in which a match could start
+    carry: Vec<u8>,
+    position: usize,
+    /// end of the last match, the next may not start before it
+    last_end: usize,
+}
+
+#[cfg(feature = "std")]
+impl MemmemScanner {
+    /// Search for `needle`, which must not be empty.
+    pub fn new(needle: &[u8]) -> Self {
+        assert!(!needle.is_empty(), "empty needle");
+        MemmemScanner {
+            finder: memchr::memmem::Finder::new(needle).into_owned(),
+            carry: vec![],
+            position: 0,
+            last_end: 0,
+        }
+    }
+
+    /// Report the matches in `haystack`, which starts at stream position `base`, starting before `limit` in it.
+    /// Each search resumes at the end of the last match, so the matches do not depend on the chunking.
+    fn find_in(&mut self, haystack: &[u8], base: usize, limit: usize, report: &mut dyn FnMut(usize, Match)) {
+        let len = self.finder.needle().len();
+        let mut from = self.last_end.saturating_sub(base);
+        while from < limit.min(haystack.len()) {
+            let start = match self.finder.find(&haystack[from ..]) {
+                Some(i) if from + i < limit => from + i,
+                _ => break,
+            };
+            self.last_end = base + start + len;
+            report(0, Match::new(base + start, self.last_end));
+            from = start + len;
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StreamScanner for MemmemScanner {
+    fn patterns(&self) -> usize {
+        1
+    }
+
+    fn scan(&mut self, chunk: &[u8], report: &mut dyn FnMut(usize, Match)) {
+        let keep = self.finder.needle().len() - 1;
+        let carry_start = self.position - self.carry.len();
+
+        // matches starting in the carried bytes
+        let mut seam = core::mem::take(&mut self.carry);
+        let carried = seam.len();
+        seam.extend_from_slice(&chunk[.. keep.min(chunk.len())]);
+        self.find_in(&seam, carry_start, carried, report);
+        self.find_in(chunk, self.position, chunk.len(), report);
+        self.position += chunk.len();
+
+        if chunk.len() >= keep {
+            seam.clear();
+            seam.extend_from_slice(&chunk[chunk.len() - keep ..]);
+        } else {
+            // the seam is the carry followed by the whole chunk
+            seam.drain(.. seam.len().saturating_sub(keep));
+        }
+        self.carry = seam;
+    }
+
+    fn finish(&mut self, _report: &mut dyn FnMut(usize, Match)) {}
+}
+
+/// Finds many literals at once with an Aho-Corasick automaton.
+///
+/// All occurrences are reported, including overlapping ones.
+#[cfg(feature = "aho-corasick")]
+pub struct AhoCorasickScanner {
+    dfa: aho_corasick::dfa::DFA,
+    state: aho_corasick::automaton::StateID,
+    position: usize,
+}
+
+#[cfg(feature = "aho-corasick")]
+impl AhoCorasickScanner {
+    /// Search for `patterns`, reported with their index.
+    pub fn new<I, P>(patterns: I) -> Result<Self, crate::Error> where I: IntoIterator<Item=P>, P: AsRef<[u8]> {
+        use aho_corasick::automaton::Automaton;
+
+        let dfa = aho_corasick::dfa::DFA::new(patterns).map_err(|_| crate::Error::InvalidRegex)?;
+        let state = dfa.start_state(aho_corasick::Anchored::No).map_err(|_| crate::Error::InvalidRegex)?;
+        Ok(AhoCorasickScanner { dfa, state, position: 0 })
+    }
+}
+
+#[cfg(feature = "aho-corasick")]
+impl StreamScanner for AhoCorasickScanner {
+    fn patterns(&self) -> usize {
+        aho_corasick::automaton::Automaton::patterns_len(&self.dfa)
+    }
+
+    fn scan(&mut self, chunk: &[u8], report: &mut dyn FnMut(usize, Match)) {
+        use aho_corasick::automaton::Automaton;
+
+        for &b in chunk {
+            self.state = self.dfa.next_state(aho_corasick::Anchored::No, self.state, b);
+            self.position += 1;
+            if self.dfa.is_special(self.state) && self.dfa.is_match(self.state) {
+                for i in 0 .. self.dfa.match_len(self.state) {
+                    let pattern = self.dfa.match_pattern(self.state, i);
+                    let len = self.dfa.pattern_len(pattern);
+                    report(pattern.as_usize(), Match::new(self.position - len, self.position));
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, _report: &mut dyn FnMut(usize, Match)) {}
+}
+
+/// Scanners of any kind behind one interface. The identifiers of their patterns are consecutive,
+/// in the order the scanners were added.
+///
+/// The matches of a chunk are reported in the order of their end.
+#[derive(Default)]
+pub struct MixedScanner {
+    /// (first identifier, scanner)
+    scanners: Vec<(usize, Box<dyn StreamScanner + Send>)>,
+    patterns: usize,
+}
+
+impl MixedScanner {
+    pub fn new() -> Self {
+        MixedScanner::default()
+    }
+
+    /// Add `scanner`. Returns the identifier of its first pattern.
+    pub fn add(&mut self, scanner: impl StreamScanner + Send + 'static) -> usize {
+        let first = self.patterns;
+        self.patterns += scanner.patterns();
+        self.scanners.push((first, Box::new(scanner)));
+        first
+    }
+
+    fn collect(&mut self, report: &mut dyn FnMut(usize, Match), mut step: impl FnMut(&mut dyn StreamScanner, &mut dyn FnMut(usize, Match))) {
+        let mut found = vec![];
+        for (first, scanner) in &mut self.scanners {
+            step(&mut **scanner, &mut |id, match_| found.push((*first + id, match_)));
+        }
+        found.sort_by_key(|&(id, match_)| (match_.end, id));
+        for (id, match_) in found {
+            report(id, match_);
+        }
+    }
+}
+
+impl StreamScanner for MixedScanner {
+    fn patterns(&self) -> usize {
+        self.patterns
+    }
+
+    fn scan(&mut self, chunk: &[u8], report: &mut dyn FnMut(usize, Match)) {
+        self.collect(report, |scanner, report| scanner.scan(chunk, report));
+    }
+
+    fn finish(&mut self, report: &mut dyn FnMut(usize, Match)) {
+        self.collect(report, |scanner, report| scanner.finish(report));
+    }
+}
@@ -0,0 +1,203 @@
+//! Destinations of matches.
+//!
+//! A `MatchSink` formats or delivers match records, independent of how they were found.
+//! `TextSink` and `JsonLinesSink` write to any `Write`, `ChannelSink` sends the records to another thread
+//! and `NullSink` drops them. Other destinations, like syslog or a webhook, implement `MatchSink` themselves.
+
+use std::io::{self, Write};
+use std::sync::mpsc::Sender;
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, MatchRecord, MatchData, StartBound, CHUNK_PIECE};
+use crate::input::InputSource;
+
+/// Receives the matches of a scan.
+pub trait MatchSink {
+    /// Deliver `record`, found in the input from `provenance`.
+    fn deliver(&mut self, provenance: Option<&str>, record: &MatchRecord) -> io::Result<()>;
+
+    /// Deliver anything still buffered. Called when the input ended.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: MatchSink + ?Sized> MatchSink for Box<S> {
+    fn deliver(&mut self, provenance: Option<&str>, record: &MatchRecord) -> io::Result<()> {
+        (**self).deliver(provenance, record)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// Writes each match like the `find` binary:
+///
+/// ```text
+/// log.txt #0 Match { start: Exact(3), end: 7 }
+/// > abbb
+/// ```
+///
+/// With `set_only_matching`, only the matched bytes follow the provenance: `log.txt abbb`.
+pub struct TextSink<W: Write> {
+    out: W,
+    only_matching: bool,
+}
+
+impl<W: Write> TextSink<W> {
+    pub fn new(out: W) -> Self {
+        TextSink { out, only_matching: false }
+    }
+
+    /// Write only the matched bytes of each match, on a line of their own.
+    pub fn set_only_matching(&mut self, only_matching: bool) {
+        self.only_matching = only_matching;
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> MatchSink for TextSink<W> {
+    fn deliver(&mut self, provenance: Option<&str>, record: &MatchRecord) -> io::Result<()> {
+        // a single `write_all`, so the matches of concurrent scans do not interleave
+        let mut line = vec![];
+        if let Some(provenance) = provenance {
+            write!(line, "{} ", provenance)?;
+        }
+        if self.only_matching {
+            line.extend_from_slice(&record.data);
+            line.push(b'\n');
+        } else {
+            writeln!(line, "#{} {:?}", record.id, record.match_())?;
+            writeln!(line, "> {}", MatchData { head: &record.data, tail: &[] })?;
+        }
+        self.out.write_all(&line)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Writes each match as a JSON object on its own line:
+///
+/// ```text
+/// {"source":"log.txt","id":0,"start":3,"exact":true,"end":7,"data":"abbb"}
+/// ```
+///
+/// `source` is left out without provenance, `fingerprint` is added if the record has one.
+/// Invalid UTF-8 in `data` is replaced with U+FFFD.
+pub struct JsonLinesSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(out: W) -> Self {
+        JsonLinesSink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> MatchSink for JsonLinesSink<W> {
+    fn deliver(&mut self, provenance: Option<&str>, record: &MatchRecord) -> io::Result<()> {
+        let mut line = String::from("{");
+        if let Some(provenance) = provenance {
+            line.push_str("\"source\":");
+            json_string(&mut line, provenance);
+            line.push(',');
+        }
+        let (start, exact) = match record.start {
+            StartBound::Exact(start) => (start, true),
+            StartBound::AtOrBefore(start) => (start, false),
+        };
+        line.push_str(&format!("\"id\":{},\"start\":{},\"exact\":{},\"end\":{},\"data\":", record.id, start, exact, record.end));
+        json_string(&mut line, &String::from_utf8_lossy(&record.data));
+        if let Some(fingerprint) = record.fingerprint {
+            line.push_str(&format!(",\"fingerprint\":{}", fingerprint));
+        }
+        line.push_str("}\n");
+        self.out.write_all(line.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Append `s` to `out` as a quoted JSON string.
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Sends (provenance, record) to a channel.
+///
+/// Delivery fails with `BrokenPipe` once the receiver is gone.
+pub struct ChannelSink {
+    sender: Sender<(Option<String>, MatchRecord)>,
+}
+
+impl ChannelSink {
+    pub fn new(sender: Sender<(Option<String>, MatchRecord)>) -> Self {
+        ChannelSink { sender }
+    }
+}
+
+impl MatchSink for ChannelSink {
+    fn deliver(&mut self, provenance: Option<&str>, record: &MatchRecord) -> io::Result<()> {
+        self.sender.send((provenance.map(String::from), record.clone()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "receiver dropped"))
+    }
+}
+
+/// Drops all matches, for scans run only for their side effects or to measure throughput.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NullSink;
+
+impl MatchSink for NullSink {
+    fn deliver(&mut self, _provenance: Option<&str>, _record: &MatchRecord) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Push all chunks of `source` to `searcher` and deliver the matches to `sink`,
+/// including the final matches once the input ended.
+///
+/// Chunks are pushed in pieces no larger than the buffer or `CHUNK_PIECE`,
+/// so the data of all matches is still in the buffer when they are recorded.
+pub fn scan_to_sink<D: DFA>(source: &mut dyn InputSource, searcher: &mut RingSearcher<D>, sink: &mut dyn MatchSink) -> io::Result<()> {
+    let piece_len = searcher.capacity().min(CHUNK_PIECE);
+    let mut records = vec![];
+    while let Some(chunk) = source.next_chunk()? {
+        for piece in chunk.chunks(piece_len) {
+            searcher.push_slice(piece);
+            let matches: Vec<_> = searcher.drain_matches().collect();
+            records.extend(matches.into_iter().map(|(id, match_)| searcher.record(id, match_)));
+        }
+        for record in records.drain(..) {
+            sink.deliver(source.provenance(), &record)?;
+        }
+    }
+    for (id, match_) in searcher.final_matches() {
+        sink.deliver(source.provenance(), &searcher.record(id, match_))?;
+    }
+    sink.flush()
+}
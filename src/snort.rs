@@ -0,0 +1,264 @@
+//! Loading the content matching parts of Snort and Suricata rules.
+//!
+//! ```text
+//! alert tcp any any -> any 80 (msg:"admin access"; content:"GET"; depth:3; content:"/admin|3F|"; within:64; nocase; sid:1000001;)
+//! ```
+//!
+//! Each `content` and `pcre` option becomes a search. Their modifiers map to:
+//!  - `nocase`: a case insensitive search
+//!  - `offset` and `depth`: positions in the stream, checked by `SnortRules::accept`
+//!  - `within`: `set_preceded_by` on the search of the previous content
+//!
+//! `distance` is not enforced and negated contents are not supported.
+//! Other options are ignored, except `msg` and `sid`.
+//! A rule matched when all its contents did, see `SnortRules::matched_rules`.
+
+use std::fs;
+use std::path::Path;
+
+use regex_automata::DenseDFA;
+
+use crate::{RingSearcher, Error, Match, flags_builder};
+use crate::hex::literal_regex;
+
+/// A `content` or `pcre` option with its modifiers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Content {
+    pub regex: String,
+    /// regex flags, see `rules::Rule::flags`
+    pub flags: String,
+    /// length of a `content`, `None` for `pcre`
+    pub len: Option<usize>,
+    pub offset: Option<usize>,
+    pub depth: Option<usize>,
+    pub distance: Option<usize>,
+    pub within: Option<usize>,
+}
+
+/// A rule with its contents.
+#[derive(Clone, Debug)]
+pub struct SnortRule {
+    pub msg: Option<String>,
+    pub sid: Option<u64>,
+    pub contents: Vec<Content>,
+}
+
+/// The rules of a rule file.
+#[derive(Clone, Debug, Default)]
+pub struct SnortRules {
+    pub rules: Vec<SnortRule>,
+    /// (rule, content) of each search
+    searches: Vec<(usize, usize)>,
+}
+
+impl SnortRules {
+    /// Parse rules, one per line. Lines ending in `\` continue on the next, `#` starts a comment.
+    pub fn parse(src: &str) -> Result<SnortRules, Error> {
+        let mut rules = SnortRules::default();
+        let mut line = String::new();
+        for (nr, part) in src.lines().enumerate() {
+            match part.strip_suffix('\\') {
+                Some(part) => {
+                    line.push_str(part);
+                    continue;
+                }
+                None => line.push_str(part),
+            }
+            let text = line.trim();
+            if !text.is_empty() && !text.starts_with('#') {
+                let rule = parse_rule(text).map_err(|msg| Error::InvalidRules(format!("line {}: {}", nr + 1, msg)))?;
+                for i in 0 .. rule.contents.len() {
+                    rules.searches.push((rules.rules.len(), i));
+                }
+                rules.rules.push(rule);
+            }
+            line.clear();
+        }
+        Ok(rules)
+    }
+
+    /// Read and parse a rule file.
+    pub fn load(path: impl AsRef<Path>) -> Result<SnortRules, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| Error::InvalidRules(format!("{}: {}", path.display(), e)))?;
+        SnortRules::parse(&data)
+    }
+
+    /// The rule and content searched by `search_nr`.
+    pub fn content(&self, search_nr: usize) -> Option<(&SnortRule, &Content)> {
+        let &(rule, content) = self.searches.get(search_nr)?;
+        let rule = &self.rules[rule];
+        Some((rule, &rule.contents[content]))
+    }
+
+    /// Whether `match_` of `search_nr` lies within the `offset` and `depth` of its content,
+    /// counted from the start of the stream.
+    pub fn accept(&self, search_nr: usize, match_: &Match) -> bool {
+        let Some((_, content)) = self.content(search_nr) else { return false };
+        let start = match_.start.position();
+        let offset = content.offset.unwrap_or(0);
+        start >= offset && content.depth.is_none_or(|depth| match_.end <= offset + depth)
+    }
+
+    /// The rules all contents of which are among the accepted matches of `search_nrs`.
+    pub fn matched_rules(&self, search_nrs: impl IntoIterator<Item=usize>) -> Vec<&SnortRule> {
+        let mut matched = vec![false; self.searches.len()];
+        for search_nr in search_nrs {
+            if let Some(m) = matched.get_mut(search_nr) {
+                *m = true;
+            }
+        }
+        let mut first = 0;
+        let mut rules = vec![];
+        for rule in &self.rules {
+            let searches = first .. first + rule.contents.len();
+            if !searches.is_empty() && matched[searches.clone()].iter().all(|&m| m) {
+                rules.push(rule);
+            }
+            first = searches.end;
+        }
+        rules
+    }
+
+    /// Create a searcher with the given ringbuffer size and add all contents to it.
+    pub fn build(&self, buffer_size: usize) -> Result<RingSearcher<DenseDFA<Vec<usize>, usize>>, Error> {
+        let mut searcher = RingSearcher::new(buffer_size);
+        self.add_to(&mut searcher)?;
+        Ok(searcher)
+    }
+
+    /// Add all contents to an existing searcher.
+    pub fn add_to(&self, searcher: &mut RingSearcher<DenseDFA<Vec<usize>, usize>>) -> Result<(), Error> {
+        let mut previous = None;
+        for &(rule_nr, content_nr) in &self.searches {
+            let rule = &self.rules[rule_nr];
+            let content = &rule.contents[content_nr];
+            let regex = flags_builder(&content.flags)
+                .map_err(Error::InvalidFlag)?
+                .unicode(false)
+                .allow_invalid_utf8(true)
+                .build(&content.regex)
+                .map_err(|e| Error::InvalidRules(format!("sid {:?}: {}", rule.sid, e)))?;
+            let search_nr = searcher.add_dense_regex(regex)?;
+            searcher.searches[search_nr].pattern = Some(content.regex.as_str().into());
+            if let (Some(within), Some(previous), true) = (content.within, previous, content_nr > 0) {
+                let distance = within.saturating_sub(content.len.unwrap_or(0));
+                searcher.set_preceded_by(search_nr, previous, distance);
+            }
+            previous = Some(search_nr);
+        }
+        Ok(())
+    }
+}
+
+fn parse_rule(text: &str) -> Result<SnortRule, String> {
+    let open = text.find('(').ok_or("missing options")?;
+    let options = text[open + 1 ..].trim_end().strip_suffix(')').ok_or("unterminated options")?;
+    let mut rule = SnortRule { msg: None, sid: None, contents: vec![] };
+    for option in split_options(options) {
+        let (key, value) = match option.split_once(':') {
+            Some((key, value)) => (key.trim(), Some(value.trim())),
+            None => (option.trim(), None),
+        };
+        let last = rule.contents.last_mut();
+        let number = || -> Result<usize, String> {
+            value.and_then(|v| v.parse().ok()).ok_or_else(|| format!("invalid {}", key))
+        };
+        match (key, last) {
+            ("msg", _) => rule.msg = value.map(|v| unquote(v).into()),
+            ("sid", _) => rule.sid = value.and_then(|v| v.parse().ok()),
+            ("content", _) => {
+                let value = value.ok_or("empty content")?;
+                if value.starts_with('!') {
+                    return Err("negated contents are not supported".into());
+                }
+                let bytes = content_bytes(unquote(value))?;
+                rule.contents.push(Content {
+                    regex: literal_regex(&bytes),
+                    flags: String::new(),
+                    len: Some(bytes.len()),
+                    offset: None, depth: None, distance: None, within: None,
+                });
+            }
+            ("pcre", _) => {
+                let value = unquote(value.ok_or("empty pcre")?);
+                let (regex, flags) = value.strip_prefix('/').and_then(|v| v.rsplit_once('/')).ok_or("invalid pcre")?;
+                let mut regex = regex.to_owned();
+                let mut builder_flags = String::new();
+                for flag in flags.chars() {
+                    match flag {
+                        'i' | 's' | 'x' => builder_flags.push(flag),
+                        'm' => regex.insert_str(0, "(?m)"),
+                        _ => return Err(format!("unsupported pcre flag '{}'", flag)),
+                    }
+                }
+                rule.contents.push(Content {
+                    regex, flags: builder_flags, len: None,
+                    offset: None, depth: None, distance: None, within: None,
+                });
+            }
+            ("nocase", Some(content)) => content.flags.push('i'),
+            ("offset", Some(content)) => content.offset = Some(number()?),
+            ("depth", Some(content)) => content.depth = Some(number()?),
+            ("distance", Some(content)) => content.distance = Some(number()?),
+            ("within", Some(content)) => content.within = Some(number()?),
+            ("nocase" | "offset" | "depth" | "distance" | "within", None) => return Err(format!("{} before any content", key)),
+            _ => {}
+        }
+    }
+    Ok(rule)
+}
+
+/// Split at `;` outside of quotes.
+fn split_options(options: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in options.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&options[start .. i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&options[start ..]);
+    parts.into_iter().filter(|part| !part.trim().is_empty()).collect()
+}
+
+fn unquote(value: &str) -> &str {
+    value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value)
+}
+
+/// The bytes of a content with `|hex|` sections and `\` escapes.
+fn content_bytes(value: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    for (i, part) in value.split('|').enumerate() {
+        if i % 2 == 1 {
+            let digits: Vec<u8> = part.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+            for pair in digits.chunks(2) {
+                let hex = std::str::from_utf8(pair).ok().filter(|_| pair.len() == 2);
+                bytes.push(hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()).ok_or_else(|| format!("invalid hex section '{}'", part))?);
+            }
+        } else {
+            let mut chars = part.bytes();
+            while let Some(b) = chars.next() {
+                match b {
+                    b'\\' => bytes.push(chars.next().ok_or("trailing \\")?),
+                    b => bytes.push(b),
+                }
+            }
+        }
+    }
+    if !value.matches('|').count().is_multiple_of(2) {
+        return Err("unterminated hex section".into());
+    }
+    if bytes.is_empty() {
+        return Err("empty content".into());
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,158 @@
+//! Spilling evicted data to a temporary file.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Match};
+
+/// size of the blocks read when searching backwards through the file
+const BLOCK_SIZE: usize = 4096;
+
+/// The most recent `limit` evicted bytes, stored in a file used as a ring.
+pub(crate) struct Spill {
+    file: File,
+    path: PathBuf,
+    limit: usize,
+    /// stream position after the last byte in the file
+    end: usize,
+    /// number of valid bytes in the file
+    len: usize,
+}
+
+impl Spill {
+    fn new(limit: usize) -> io::Result<Spill> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!("regex-ring-{}-{}.spill", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(Spill { file, path, limit, end: 0, len: 0 })
+    }
+
+    /// stream position of the first byte in the file
+    pub(crate) fn start(&self) -> usize {
+        self.end - self.len
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Drop the spilled data, the next write starts at stream `position`.
+    pub(crate) fn clear(&mut self, position: usize) {
+        self.end = position;
+        self.len = 0;
+    }
+
+    /// Append `data`, which starts at stream `position`.
+    /// On errors the spilled data is dropped, as the file no longer continues the buffer.
+    pub(crate) fn write(&mut self, position: usize, data: &[u8]) {
+        if position != self.end {
+            self.len = 0;
+        }
+        let skip = data.len().saturating_sub(self.limit);
+        let result = self.write_at(position + skip, &data[skip ..]);
+        self.end = position + data.len();
+        self.len = match result {
+            Ok(()) => (self.len + data.len()).min(self.limit),
+            Err(_) => 0,
+        };
+    }
+
+    fn write_at(&mut self, position: usize, mut data: &[u8]) -> io::Result<()> {
+        let mut at = position % self.limit;
+        while !data.is_empty() {
+            let n = data.len().min(self.limit - at);
+            self.file.seek(SeekFrom::Start(at as u64))?;
+            self.file.write_all(&data[.. n])?;
+            data = &data[n ..];
+            at = 0;
+        }
+        Ok(())
+    }
+
+    /// Read the bytes from stream position `from` to `to` into `out`.
+    pub(crate) fn read(&self, from: usize, to: usize, out: &mut Vec<u8>) -> io::Result<()> {
+        let mut file = &self.file;
+        let mut position = from;
+        while position < to {
+            let at = position % self.limit;
+            let n = (to - position).min(self.limit - at);
+            let old_len = out.len();
+            out.resize(old_len + n, 0);
+            file.seek(SeekFrom::Start(at as u64))?;
+            file.read_exact(&mut out[old_len ..])?;
+            position += n;
+        }
+        Ok(())
+    }
+
+    /// The bytes before stream position `end`, backwards. Stops at the first read error.
+    pub(crate) fn rev_bytes(&self, end: usize) -> RevBytes<'_> {
+        RevBytes { spill: self, position: end.min(self.end), block: vec![] }
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub(crate) struct RevBytes<'a> {
+    spill: &'a Spill,
+    /// stream position after the next byte yielded
+    position: usize,
+    /// the bytes before `position`
+    block: Vec<u8>,
+}
+
+impl<'a> Iterator for RevBytes<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.block.is_empty() {
+            let from = self.position.saturating_sub(BLOCK_SIZE).max(self.spill.start());
+            if from >= self.position || self.spill.read(from, self.position, &mut self.block).is_err() {
+                return None;
+            }
+        }
+        self.position -= 1;
+        self.block.pop()
+    }
+}
+
+impl<D: DFA> RingSearcher<D> {
+    /// Keep up to `limit` bytes evicted from the buffer in a temporary file,
+    /// so matches can be longer than the buffer. `None` stops spilling.
+    ///
+    /// Searches without their own window look back over the spilled data to find the start of a match.
+    /// `match_data` only covers the buffer, `read_match` includes the spilled data.
+    ///
+    /// If writing the file fails, the spilled data is dropped.
+    /// A clone of the searcher does not spill.
+    pub fn set_spill(&mut self, limit: Option<usize>) -> io::Result<()> {
+        self.spill = match limit {
+            Some(limit) if limit > 0 => Some(Spill::new(limit)?),
+            _ => None,
+        };
+        Ok(())
+    }
+
+    /// The data of `match_`, including the part spilled to disk.
+    pub fn read_match(&self, match_: &Match) -> io::Result<Vec<u8>> {
+        // first data byte in self.buffer is at this stream position
+        let offset = self.position - self.buffer.len();
+        let mut data = vec![];
+        if let Some(ref spill) = self.spill {
+            let start = match_.start().unwrap_or(0).max(spill.start());
+            spill.read(start, match_.end.min(offset), &mut data)?;
+        }
+        data.extend_from_slice(self.match_data(match_).head);
+        data.extend_from_slice(self.match_data(match_).tail);
+        Ok(data)
+    }
+}
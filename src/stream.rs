@@ -0,0 +1,217 @@
+//! `futures` integration, for async runtimes other than tokio (async-std, smol).
+//!
+//! `MatchStream` reads from an `AsyncRead` and yields the match records:
+//!
+//! ```ignore
+//! let mut matches = MatchStream::new(socket, searcher);
+//! while let Some(record) = matches.next().await {
+//!     println!("{:?}", record?);
+//! }
+//! ```
+//!
+//! `RingSearcher::scan_async_reader` drives a reader to completion and awaits a callback for every record:
+//!
+//! ```ignore
+//! searcher.scan_async_reader(upload, |record| async move { db.insert(record).await }).await?;
+//! ```
+//!
+//! `SearchSink` is the end of a pipeline, it passes the match records to a callback:
+//!
+//! ```ignore
+//! let mut sink = SearchSink::new(searcher, |record| println!("{:?}", record));
+//! sink.send_all(&mut chunks).await?;
+//! sink.close().await?;
+//! ```
+
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_io::AsyncRead;
+use futures_sink::Sink;
+use regex_automata::DFA;
+
+use crate::{RingSearcher, MatchRecord};
+
+/// Size of the reads from the reader.
+const READ_SIZE: usize = 8192;
+
+/// A stream of the matches in the data read from `R`.
+pub struct MatchStream<R, D: DFA> {
+    reader: R,
+    searcher: RingSearcher<D>,
+    buf: Box<[u8]>,
+    pending: VecDeque<MatchRecord>,
+    /// the reader is exhausted and the final matches were reported
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin, D: DFA> MatchStream<R, D> {
+    pub fn new(reader: R, searcher: RingSearcher<D>) -> Self {
+        MatchStream {
+            reader,
+            searcher,
+            buf: vec![0; READ_SIZE].into_boxed_slice(),
+            pending: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    pub fn searcher(&self) -> &RingSearcher<D> {
+        &self.searcher
+    }
+
+    pub fn into_inner(self) -> (R, RingSearcher<D>) {
+        (self.reader, self.searcher)
+    }
+}
+
+// the searcher is never pinned
+impl<R: Unpin, D: DFA> Unpin for MatchStream<R, D> {}
+
+impl<R: AsyncRead + Unpin, D: DFA> Stream for MatchStream<R, D> {
+    type Item = io::Result<MatchRecord>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(record) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(record)));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+            match Pin::new(&mut this.reader).poll_read(cx, &mut this.buf) {
+                Poll::Ready(Ok(0)) => {
+                    this.finished = true;
+                    this.searcher.final_records(&mut this.pending);
+                }
+                Poll::Ready(Ok(n)) => this.searcher.push_records(&this.buf[.. n], &mut this.pending),
+                Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<D: DFA> RingSearcher<D> {
+    /// Scan everything `reader` yields and await `callback` for every match, including the final matches.
+    /// Reading waits for each callback to complete.
+    pub async fn scan_async_reader<R, F, Fut>(&mut self, mut reader: R, mut callback: F) -> io::Result<()>
+        where R: AsyncRead + Unpin, F: FnMut(MatchRecord) -> Fut, Fut: Future<Output=()>
+    {
+        let mut buf = vec![0; READ_SIZE];
+        let mut pending = VecDeque::new();
+        loop {
+            match poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut buf)).await {
+                Ok(0) => break,
+                Ok(n) => self.push_records(&buf[.. n], &mut pending),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+            while let Some(record) = pending.pop_front() {
+                callback(record).await;
+            }
+        }
+        self.final_records(&mut pending);
+        for record in pending {
+            callback(record).await;
+        }
+        Ok(())
+    }
+}
+
+/// Searches the bytes or chunks sent to it and calls the callback for every match.
+/// The final matches are reported on the first close.
+pub struct SearchSink<D: DFA, F> {
+    searcher: RingSearcher<D>,
+    callback: F,
+    pending: VecDeque<MatchRecord>,
+    /// the final matches were reported
+    closed: bool,
+}
+
+impl<D: DFA, F: FnMut(MatchRecord)> SearchSink<D, F> {
+    pub fn new(searcher: RingSearcher<D>, callback: F) -> Self {
+        SearchSink {
+            searcher,
+            callback,
+            pending: VecDeque::new(),
+            closed: false,
+        }
+    }
+
+    pub fn searcher(&self) -> &RingSearcher<D> {
+        &self.searcher
+    }
+
+    pub fn into_inner(self) -> RingSearcher<D> {
+        self.searcher
+    }
+
+    fn send(&mut self, input: &[u8]) {
+        self.searcher.push_records(input, &mut self.pending);
+        for record in self.pending.drain(..) {
+            (self.callback)(record);
+        }
+    }
+}
+
+// the searcher is never pinned
+impl<D: DFA, F> Unpin for SearchSink<D, F> {}
+
+impl<D: DFA, F: FnMut(MatchRecord)> Sink<u8> for SearchSink<D, F> {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: u8) -> io::Result<()> {
+        self.get_mut().send(&[item]);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.closed {
+            return Poll::Ready(Ok(()));
+        }
+        this.closed = true;
+        this.searcher.final_records(&mut this.pending);
+        for record in this.pending.drain(..) {
+            (this.callback)(record);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<D: DFA, F: FnMut(MatchRecord)> Sink<Bytes> for SearchSink<D, F> {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Sink::<u8>::poll_ready(self, cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> io::Result<()> {
+        self.get_mut().send(&item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Sink::<u8>::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Sink::<u8>::poll_close(self, cx)
+    }
+}
@@ -0,0 +1,77 @@
+//! Checking that a pattern set finds the same matches however the stream is chunked.
+//!
+//! ```ignore
+//! let mut searcher = RingSearcher::new(1024);
+//! searcher.add_regex_str(r"ab+c")?;
+//! test_util::assert_chunking_invariant(&searcher, b"xx abbc abc", 100, 42);
+//! ```
+
+use std::vec::Vec;
+
+use regex_automata::DFA;
+
+use crate::{RingSearcher, Match, CHUNK_PIECE};
+
+/// All matches of a fresh `searcher` fed `input` in chunks of the given sizes, including the final matches.
+///
+/// The last chunk takes the rest of the input.
+pub fn chunked_matches<D: DFA>(searcher: &RingSearcher<D>, input: &[u8], sizes: &[usize]) -> Vec<(usize, Match)> {
+    let mut searcher = searcher.fork();
+    let mut found = vec![];
+    let mut rest = input;
+    for &size in sizes {
+        let (chunk, tail) = rest.split_at(size.min(rest.len()));
+        searcher.push_slice(chunk);
+        found.extend(searcher.drain_matches());
+        rest = tail;
+    }
+    searcher.push_slice(rest);
+    found.extend(searcher.drain_matches());
+    found.extend(searcher.final_matches());
+    found
+}
+
+/// Random chunk sizes covering `len` bytes: mostly small, sometimes larger than `CHUNK_PIECE`.
+pub fn random_chunk_sizes(len: usize, seed: u64) -> Vec<usize> {
+    let mut rng = XorShift(seed.max(1));
+    let mut sizes = vec![];
+    let mut covered = 0;
+    while covered < len {
+        let max = match rng.next() % 8 {
+            0 => 2 * CHUNK_PIECE,
+            1 | 2 => 256,
+            _ => 16,
+        };
+        let size = 1 + (rng.next() % max as u64) as usize;
+        sizes.push(size);
+        covered += size;
+    }
+    sizes
+}
+
+/// Feed `input` to clones of `searcher` in `rounds` random chunkings derived from `seed`
+/// and assert each finds the same matches as pushing the input at once.
+///
+/// The searcher should not have seen any input yet.
+/// On failure, the message contains the chunk sizes to reproduce it with `chunked_matches`.
+pub fn assert_chunking_invariant<D: DFA>(searcher: &RingSearcher<D>, input: &[u8], rounds: usize, seed: u64) {
+    let expected = chunked_matches(searcher, input, &[]);
+    let mut rng = XorShift(seed.max(1));
+    for _ in 0 .. rounds {
+        let sizes = random_chunk_sizes(input.len(), rng.next());
+        let found = chunked_matches(searcher, input, &sizes);
+        assert_eq!(found, expected, "matches differ for chunk sizes {:?}", sizes);
+    }
+}
+
+/// xorshift64, good enough to pick chunk sizes
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,387 @@
+//! Loading the strings of YARA rules.
+//!
+//! Only the `strings:` section is used: text strings, hex strings and regular expressions,
+//! with the modifiers `nocase`, `ascii`, `wide` and `private`.
+//! Conditions are not evaluated, a rule counts as matched when any of its strings matched.
+//!
+//! ```yara
+//! rule Example {
+//!     strings:
+//!         $text = "secret" nocase
+//!         $hex = { DE AD ?? EF }
+//!         $re = /pass(word)?=\w+/
+//!     condition:
+//!         any of them
+//! }
+//! ```
+//!
+//! The search identifiers of the built searcher number the strings of all rules in order,
+//! `YaraRules::string` maps them back.
+
+use std::fs;
+use std::path::Path;
+
+use regex_automata::DenseDFA;
+
+use crate::{RingSearcher, Error, flags_builder};
+use crate::hex::{hex_to_regex, literal_regex};
+
+/// How a string was written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StringKind {
+    Text,
+    Hex,
+    Regex,
+}
+
+/// A string of a rule, translated to a byte regex.
+#[derive(Clone, Debug)]
+pub struct YaraString {
+    /// identifier including the `$`
+    pub id: String,
+    pub kind: StringKind,
+    pub regex: String,
+    /// regex flags, see `rules::Rule::flags`
+    pub flags: String,
+    pub private: bool,
+}
+
+/// A rule with its strings.
+#[derive(Clone, Debug)]
+pub struct YaraRule {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub strings: Vec<YaraString>,
+}
+
+/// The rules of a YARA file.
+#[derive(Clone, Debug, Default)]
+pub struct YaraRules {
+    pub rules: Vec<YaraRule>,
+    /// (rule, string) of each search
+    searches: Vec<(usize, usize)>,
+}
+
+impl YaraRules {
+    /// Parse the rules in `src`.
+    pub fn parse(src: &str) -> Result<YaraRules, Error> {
+        let mut parser = Parser { src: src.as_bytes(), pos: 0 };
+        let mut rules = YaraRules::default();
+        while let Some(rule) = parser.rule()? {
+            for i in 0 .. rule.strings.len() {
+                rules.searches.push((rules.rules.len(), i));
+            }
+            rules.rules.push(rule);
+        }
+        Ok(rules)
+    }
+
+    /// Read and parse a YARA file.
+    pub fn load(path: impl AsRef<Path>) -> Result<YaraRules, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| Error::InvalidRules(format!("{}: {}", path.display(), e)))?;
+        YaraRules::parse(&data)
+    }
+
+    /// The rule and string searched by `search_nr`.
+    pub fn string(&self, search_nr: usize) -> Option<(&YaraRule, &YaraString)> {
+        let &(rule, string) = self.searches.get(search_nr)?;
+        let rule = &self.rules[rule];
+        Some((rule, &rule.strings[string]))
+    }
+
+    /// The names of the rules any of the `search_nrs` belong to, in the order of the rules.
+    pub fn matched_rules(&self, search_nrs: impl IntoIterator<Item=usize>) -> Vec<&str> {
+        let mut matched = vec![false; self.rules.len()];
+        for search_nr in search_nrs {
+            if let Some(&(rule, _)) = self.searches.get(search_nr) {
+                matched[rule] = true;
+            }
+        }
+        self.rules.iter().zip(matched).filter(|&(_, m)| m).map(|(rule, _)| rule.name.as_str()).collect()
+    }
+
+    /// Create a searcher with the given ringbuffer size and add all strings to it.
+    pub fn build(&self, buffer_size: usize) -> Result<RingSearcher<DenseDFA<Vec<usize>, usize>>, Error> {
+        let mut searcher = RingSearcher::new(buffer_size);
+        self.add_to(&mut searcher)?;
+        Ok(searcher)
+    }
+
+    /// Add all strings to an existing searcher.
+    pub fn add_to(&self, searcher: &mut RingSearcher<DenseDFA<Vec<usize>, usize>>) -> Result<(), Error> {
+        for &(rule, string) in &self.searches {
+            let rule = &self.rules[rule];
+            let string = &rule.strings[string];
+            let regex = flags_builder(&string.flags)
+                .map_err(Error::InvalidFlag)?
+                .unicode(false)
+                .allow_invalid_utf8(true)
+                .build(&string.regex)
+                .map_err(|e| Error::InvalidRules(format!("{} {}: {}", rule.name, string.id, e)))?;
+            let search_nr = searcher.add_dense_regex(regex)?;
+            searcher.searches[search_nr].pattern = Some(format!("{}:{}", rule.name, string.id).into());
+        }
+        Ok(())
+    }
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> Error {
+        let line = 1 + self.src[.. self.pos].iter().filter(|&&b| b == b'\n').count();
+        Error::InvalidRules(format!("line {}: {}", line, msg))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    /// Skip whitespace and comments.
+    fn skip(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'/') if self.src[self.pos ..].starts_with(b"//") => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                Some(b'/') if self.src[self.pos ..].starts_with(b"/*") => {
+                    match self.src[self.pos + 2 ..].windows(2).position(|w| w == b"*/") {
+                        Some(n) => self.pos += n + 4,
+                        None => self.pos = self.src.len(),
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// An identifier, including a leading `$`.
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip();
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if b.is_ascii_alphanumeric() || b == b'_' || (b == b'$' && self.pos == start)) {
+            self.pos += 1;
+        }
+        match self.pos > start {
+            true => std::str::from_utf8(&self.src[start .. self.pos]).ok(),
+            false => None,
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), Error> {
+        self.skip();
+        match self.peek() == Some(b) {
+            true => {
+                self.pos += 1;
+                Ok(())
+            }
+            false => Err(self.error(&format!("expected '{}'", b as char))),
+        }
+    }
+
+    fn rule(&mut self) -> Result<Option<YaraRule>, Error> {
+        loop {
+            let keyword = match self.ident() {
+                Some(keyword) => keyword,
+                None if self.peek().is_none() => return Ok(None),
+                None => return Err(self.error("expected a rule")),
+            };
+            match keyword {
+                "import" | "include" => {
+                    self.skip();
+                    self.quoted()?;
+                }
+                "private" | "global" => {}
+                "rule" => break,
+                _ => return Err(self.error(&format!("unexpected '{}'", keyword))),
+            }
+        }
+        let name = self.ident().ok_or_else(|| self.error("expected a rule name"))?.to_owned();
+        let mut tags = vec![];
+        self.skip();
+        if self.peek() == Some(b':') {
+            self.pos += 1;
+            while let Some(tag) = self.ident() {
+                tags.push(tag.to_owned());
+            }
+        }
+        self.expect(b'{')?;
+
+        let mut strings = vec![];
+        loop {
+            self.skip();
+            if self.peek() == Some(b'}') {
+                self.pos += 1;
+                break;
+            }
+            let section = self.ident().ok_or_else(|| self.error("expected a section"))?;
+            self.expect(b':')?;
+            match section {
+                "strings" => self.strings(&mut strings)?,
+                "meta" | "condition" => self.skip_section()?,
+                _ => return Err(self.error(&format!("unknown section '{}'", section))),
+            }
+        }
+        Ok(Some(YaraRule { name, tags, strings }))
+    }
+
+    /// Skip to the next section or the end of the rule.
+    fn skip_section(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip();
+            match self.peek() {
+                None => return Err(self.error("unterminated rule")),
+                Some(b'}') => return Ok(()),
+                Some(b'"') => {
+                    self.quoted()?;
+                }
+                Some(_) => {
+                    let start = self.pos;
+                    if let Some(word) = self.ident() {
+                        self.skip();
+                        if matches!(word, "meta" | "strings" | "condition") && self.peek() == Some(b':') {
+                            self.pos = start;
+                            return Ok(());
+                        }
+                    } else {
+                        self.pos += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn strings(&mut self, strings: &mut Vec<YaraString>) -> Result<(), Error> {
+        loop {
+            self.skip();
+            if self.peek() != Some(b'$') {
+                return Ok(());
+            }
+            let id = self.ident().unwrap().to_owned();
+            self.expect(b'=')?;
+            self.skip();
+            let (kind, mut regex, mut flags) = match self.peek() {
+                Some(b'"') => (StringKind::Text, literal_regex(&self.quoted()?), String::new()),
+                Some(b'{') => {
+                    self.pos += 1;
+                    let end = self.src[self.pos ..].iter().position(|&b| b == b'}')
+                        .ok_or_else(|| self.error("unterminated hex string"))?;
+                    let hex = std::str::from_utf8(&self.src[self.pos .. self.pos + end]).unwrap();
+                    let regex = hex_to_regex(hex).map_err(|_| self.error(&format!("invalid hex string {}", id)))?;
+                    self.pos += end + 1;
+                    (StringKind::Hex, regex, String::new())
+                }
+                Some(b'/') => {
+                    let (regex, flags) = self.regex()?;
+                    (StringKind::Regex, regex, flags)
+                }
+                _ => return Err(self.error(&format!("expected a value for {}", id))),
+            };
+
+            let (mut ascii, mut wide, mut private) = (false, false, false);
+            loop {
+                self.skip();
+                let start = self.pos;
+                match self.ident() {
+                    Some("nocase") => flags.push('i'),
+                    Some("ascii") => ascii = true,
+                    Some("wide") => wide = true,
+                    Some("private") => private = true,
+                    Some(modifier) if !modifier.starts_with('$') && !matches!(modifier, "condition" | "meta" | "strings") => {
+                        return Err(self.error(&format!("unsupported modifier '{}' of {}", modifier, id)));
+                    }
+                    _ => {
+                        self.pos = start;
+                        break;
+                    }
+                }
+            }
+            if wide {
+                if kind != StringKind::Text {
+                    return Err(self.error(&format!("'wide' is only supported for text strings, not {}", id)));
+                }
+                let wide = widen(&regex);
+                regex = match ascii {
+                    true => format!("{}|{}", regex, wide),
+                    false => wide,
+                };
+            }
+            strings.push(YaraString { id, kind, regex, flags, private });
+        }
+    }
+
+    /// A quoted text string with YARA escapes.
+    fn quoted(&mut self) -> Result<Vec<u8>, Error> {
+        self.expect(b'"')?;
+        let mut text = vec![];
+        loop {
+            let b = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match b {
+                b'"' => return Ok(text),
+                b'\\' => {
+                    let e = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    text.push(match e {
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        b'x' => {
+                            let digits = self.src.get(self.pos .. self.pos + 2)
+                                .and_then(|d| std::str::from_utf8(d).ok())
+                                .and_then(|d| u8::from_str_radix(d, 16).ok())
+                                .ok_or_else(|| self.error("invalid \\x escape"))?;
+                            self.pos += 2;
+                            digits
+                        }
+                        b => b,
+                    });
+                }
+                b => text.push(b),
+            }
+        }
+    }
+
+    /// A `/regex/` with its flags.
+    fn regex(&mut self) -> Result<(String, String), Error> {
+        self.expect(b'/')?;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err(self.error("unterminated regex")),
+                Some(b'\\') => self.pos += 2,
+                Some(b'/') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+        let regex = std::str::from_utf8(&self.src[start .. self.pos]).map_err(|_| self.error("regex is not UTF-8"))?;
+        self.pos += 1;
+        let mut flags = String::new();
+        while let Some(flag @ (b'i' | b's')) = self.peek() {
+            flags.push(flag as char);
+            self.pos += 1;
+        }
+        Ok((regex.replace(r"\/", "/"), flags))
+    }
+}
+
+/// The escaped text regex for UTF-16LE, each byte followed by a zero byte.
+fn widen(regex: &str) -> String {
+    let mut wide = String::new();
+    let mut rest = regex;
+    while !rest.is_empty() {
+        let len = if rest.starts_with(r"\x") { 4 } else { 1 };
+        wide.push_str(&rest[.. len]);
+        wide.push_str(r"\x00");
+        rest = &rest[len ..];
+    }
+    wide
+}
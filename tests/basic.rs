@@ -1,4 +1,4 @@
-use regex_ring::RingSearcher;
+use regex_ring::{RingSearcher, Match};
 
 #[test]
 fn basic() {
@@ -19,10 +19,1210 @@ fn basic() {
     searcher.input_matches(input.as_bytes(), |search_id, match_, data| {
         let (expected_id, expected_pos, expected_match_str) = expected.next().expect("too many matches");
         assert_eq!(expected_id, search_id);
-        assert_eq!(expected_pos, match_.start.expect("should have a start"));
+        assert_eq!(expected_pos, match_.start().expect("should have a start"));
         assert_eq!(data, *expected_match_str.as_bytes());
         assert_eq!(expected_pos + expected_match_str.len(), match_.end);
     });
 
     assert!(expected.next().is_none());
-}
\ No newline at end of file
+}
+
+#[test]
+fn crlf() {
+    let input = "a line\r\nfoo\r\r\nbar\r";
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.set_crlf(true);
+    searcher.add_regex_str(r"e\n").expect("failed to compile regex");
+    searcher.add_regex_str(r"o+\r?\n").expect("failed to compile regex");
+    searcher.add_regex_str(r"r\r").expect("failed to compile regex");
+
+    let mut found = vec![];
+    searcher.input_matches(input.as_bytes(), |search_id, match_, data| {
+        found.push((search_id, match_.start().expect("should have a start"), match_.end, data.to_string()));
+    });
+
+    assert_eq!(found, [
+        (0, 5, 8, "e\r\n".into()),
+        (1, 9, 14, "oo\r\r\n".into()),
+        (2, 16, 18, "r\r".into()),
+    ]);
+}
+
+#[test]
+fn match_after_dead_state() {
+    // the byte ending a match is the first byte of the next one
+    let mut searcher = RingSearcher::new(64);
+    searcher.add_regex_str("ab").unwrap();
+    searcher.add_regex_str("x").unwrap();
+    let mut found = vec![];
+    for &b in b"ababxxa" {
+        searcher.push(b);
+        found.extend(searcher.matches().map(|(id, m)| (id, m.end)));
+    }
+    assert_eq!(found, [(0, 2), (0, 4), (1, 5), (1, 6)]);
+}
+
+#[test]
+fn record_reset() {
+    let input = b"ab\0c\0\0abc";
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.set_record_delimiter(b'\0');
+    searcher.set_record_reset(true);
+    searcher.add_regex_str(r"[^x]+").expect("failed to compile regex");
+
+    let mut found = vec![];
+    searcher.input_matches(&input[..], |_, match_, data| {
+        found.push((match_.start(), match_.end, data.to_vec()));
+    });
+
+    assert_eq!(found, [
+        (Some(0), 2, b"ab".to_vec()),
+        (Some(3), 4, b"c".to_vec()),
+        (Some(6), 9, b"abc".to_vec()),
+    ]);
+    assert_eq!(searcher.records(), 3);
+}
+
+#[test]
+fn memory_limit() {
+    let mut searcher = RingSearcher::new(1 << 20);
+    searcher.set_memory_limit(Some(16 * 1024));
+    searcher.add_regex_str(r"ab+c").expect("failed to compile regex");
+    assert!(searcher.add_regex_str(r"[a-z]{300}").is_err());
+
+    searcher.input_matches(vec![b'x'; 100_000], |_, _, _| ());
+    assert!(searcher.truncated());
+    assert!(searcher.memory_usage() <= 16 * 1024);
+}
+
+#[test]
+fn windows() {
+    let input = "aaaaaaaaaa-bbbbbbbbbb";
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"a+").expect("failed to compile regex");
+    searcher.add_regex_str(r"b+").expect("failed to compile regex");
+    searcher.set_window(0, 4);
+    searcher.set_window(1, 16);
+
+    let mut found = vec![];
+    searcher.input_matches(input.as_bytes(), |search_id, match_, _| {
+        found.push((search_id, match_.start(), match_.end));
+    });
+    assert_eq!(found, [(0, None, 10), (1, Some(11), 21)]);
+}
+
+#[test]
+fn clone() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+c").expect("failed to compile regex");
+    searcher.push(b'a');
+
+    let mut other = searcher.clone();
+    let mut found = vec![];
+    other.input_matches(b"bc", |_, match_, data| found.push((match_.start(), data.to_string())));
+    searcher.input_matches(b"xabbc", |_, match_, data| found.push((match_.start(), data.to_string())));
+    assert_eq!(found, [(Some(0), "abc".into()), (Some(2), "abbc".into())]);
+}
+
+#[test]
+fn swap_patterns() {
+    use regex_automata::Regex;
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"old").expect("failed to compile regex");
+
+    let mut found = vec![];
+    for &b in b"old new " {
+        searcher.push(b);
+        found.extend(searcher.matches().map(|(id, m)| (id, searcher.match_data(&m).to_string())));
+    }
+    let old = searcher.swap_patterns(vec![
+        Regex::new(r"new").unwrap(),
+        Regex::new(r"old new new").unwrap(),
+    ]).unwrap();
+    assert_eq!(old, []);
+    searcher.input_matches(b"old new new", |id, _, data| found.push((id, data.to_string())));
+
+    assert_eq!(found, [(0, "old".into()), (0, "new".into()), (0, "new".into()), (1, "old new new".into())]);
+}
+
+#[test]
+fn swap_patterns_pending() {
+    use regex_automata::{DenseDFA, Regex};
+
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.set_match_queue(true);
+    searcher.add_regex_str(r"a").unwrap();
+    searcher.add_regex_str(r"b+").unwrap();
+    searcher.push_slice(b"xaxbb");
+
+    // rejected patterns leave the old searches in place
+    assert!(matches!(searcher.swap_patterns(vec![Regex::new(r"c*").unwrap()]), Err(regex_ring::Error::EmptyMatch)));
+    searcher.set_memory_limit(Some(1));
+    assert!(matches!(searcher.swap_dense_patterns(vec![Regex::new(r"c").unwrap()]), Err(regex_ring::Error::MemoryLimit)));
+    searcher.set_memory_limit(None);
+
+    // the queued match and the one in progress are returned, not attributed to the new searches
+    let old = searcher.swap_dense_patterns(vec![Regex::new(r"b").unwrap()]).unwrap();
+    assert_eq!(old, [(0, Match::new(1, 2)), (1, Match::new(3, 5))]);
+    searcher.push_slice(b"b");
+    searcher.finish();
+    assert_eq!(searcher.drain_matches().collect::<Vec<_>>(), [(0, Match::new(5, 6))]);
+}
+
+#[test]
+fn fuzzy() {
+    use regex_automata::DenseDFA;
+
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.add_fuzzy(b"hello", 1).unwrap();
+    searcher.add_fuzzy(b"world", 2).unwrap();
+    assert!(searcher.add_fuzzy(b"ab", 2).is_err());
+
+    let mut found = vec![];
+    searcher.input_matches(&b"say hello! hallo, wrld. hxllx"[..], |search_id, match_, data| {
+        found.push((search_id, match_.start(), data.to_string()));
+    });
+    assert_eq!(found, [
+        (0, Some(4), "hello".into()),
+        (0, Some(11), "hallo".into()),
+        (1, Some(18), "wrld".into()),
+    ]);
+}
+
+#[test]
+fn fuzzy_memory_limit() {
+    use regex_automata::DenseDFA;
+
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(8);
+    searcher.set_memory_limit(Some(searcher.memory_usage() + 1024));
+    assert!(matches!(searcher.add_fuzzy(b"hello", 1), Err(regex_ring::Error::MemoryLimit)));
+    searcher.set_memory_limit(Some(searcher.memory_usage() + 8192));
+    searcher.add_fuzzy(b"hello", 1).unwrap();
+    assert!(searcher.memory_usage() > 4096);
+}
+
+#[test]
+fn fuzzy_idle() {
+    use regex_automata::DenseDFA;
+
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(8);
+    let id = searcher.add_fuzzy(b"hello", 1).unwrap();
+    searcher.set_pinning(Some(1024));
+    searcher.push_slice(b"hel");
+    assert_eq!(searcher.in_progress().collect::<Vec<_>>(), [(id, 3)]);
+
+    // once the partial match is gone, nothing is pinned
+    searcher.push_slice(&[b'z'; 100]);
+    assert_eq!(searcher.in_progress().count(), 0);
+    // `push_slice` leaves the piece in the buffer until the next push
+    searcher.push(b'z');
+    assert!(searcher.buffer_len() <= 9);
+}
+
+#[test]
+fn ascii_case_fold() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.set_ascii_case_fold(true);
+    searcher.add_regex_str(r"error: [a-z]+").expect("failed to compile regex");
+
+    let mut found = vec![];
+    searcher.input_matches(&b"ERROR: Disk; Error: net"[..], |_, match_, data| {
+        found.push((match_.start(), data.to_string()));
+    });
+    assert_eq!(found, [(Some(0), "ERROR: Disk".into()), (Some(13), "Error: net".into())]);
+}
+
+#[test]
+fn byte_map() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.set_byte_map(|b| if b == b'\t' { b' ' } else { b & 0x7f });
+    searcher.add_regex_str(r"a b").expect("failed to compile regex");
+
+    let mut found = vec![];
+    searcher.input_matches(&b"a\tb \xe1 b"[..], |_, match_, data| {
+        found.push((match_.start(), data.to_vec()));
+    });
+    assert_eq!(found, [(Some(0), b"a\tb".to_vec()), (Some(4), b"\xe1 b".to_vec())]);
+}
+
+#[test]
+fn byte_map_case_fold() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.set_byte_map(|b| if b == b'\t' { b' ' } else { b });
+    searcher.set_ascii_case_fold(true);
+    searcher.add_regex_str(r"a b").expect("failed to compile regex");
+
+    let mut found = vec![];
+    searcher.input_matches(&b"A\tb "[..], |_, match_, _| found.push(match_.start()));
+    // turning the fold off keeps the byte map
+    searcher.set_ascii_case_fold(false);
+    searcher.input_matches(&b"A\tb a\tb"[..], |_, match_, _| found.push(match_.start()));
+    assert_eq!(found, [Some(0), Some(8)]);
+}
+
+#[test]
+fn pause() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"x+").expect("failed to compile regex");
+    searcher.add_regex_str(r"y+").expect("failed to compile regex");
+
+    let mut found = vec![];
+    for (i, &b) in b"xx yy [xx yy] xx yy".iter().enumerate() {
+        if b == b'[' {
+            searcher.pause(0);
+        }
+        if b == b']' {
+            searcher.resume(0);
+        }
+        searcher.push(b);
+        found.extend(searcher.matches().map(|(id, m)| (id, m.start(), i)));
+    }
+    found.extend(searcher.final_matches().map(|(id, m)| (id, m.start(), 0)));
+    assert!(!searcher.is_paused(0));
+
+    assert_eq!(found, [
+        (0, Some(0), 2), (1, Some(3), 5),
+        (1, Some(10), 12),
+        (0, Some(14), 16), (1, Some(17), 0),
+    ]);
+}
+
+#[test]
+fn region() {
+    let input = b"xx <a> xx </a> xx <a>x</a> xx <a> x";
+    let searcher = || {
+        let mut searcher = RingSearcher::new(1024);
+        searcher.add_regex_str(r"<a>").expect("failed to compile regex");
+        searcher.add_regex_str(r"</a>").expect("failed to compile regex");
+        searcher.add_regex_str(r"x+").expect("failed to compile regex");
+        let region = searcher.add_region(0, 1);
+        searcher.set_region(2, Some(region));
+        searcher
+    };
+    let expected = [Some(7), Some(21), Some(34)];
+
+    let mut bytewise = searcher();
+    let mut found = vec![];
+    for &b in input {
+        bytewise.push(b);
+        found.extend(bytewise.matches().filter(|&(id, _)| id == 2).map(|(_, m)| m.start()));
+    }
+    found.extend(bytewise.final_matches().filter(|&(id, _)| id == 2).map(|(_, m)| m.start()));
+    assert_eq!(found, expected);
+    assert!(bytewise.in_region(0));
+
+    let mut chunked = searcher();
+    chunked.push_slice(input);
+    let mut found: Vec<_> = chunked.matches().filter(|&(id, _)| id == 2).map(|(_, m)| m.start()).collect();
+    found.extend(chunked.final_matches().filter(|&(id, _)| id == 2).map(|(_, m)| m.start()));
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn preceded_by() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"GET").expect("failed to compile regex");
+    searcher.add_regex_str(r"passwd").expect("failed to compile regex");
+    searcher.set_preceded_by(1, 0, 8);
+
+    searcher.push_slice(b"passwd GET /etc/passwd GET /a/b/c/d/e/passwd");
+    let found: Vec<_> = searcher.matches().filter(|&(id, _)| id == 1).map(|(_, m)| m.start()).collect();
+    assert_eq!(found, [Some(16)]);
+
+    searcher.clear_preceded_by(1);
+    searcher.push_slice(b" passwd");
+    assert_eq!(searcher.matches().count(), 1);
+}
+
+#[test]
+fn fingerprint() {
+    use regex_ring::fingerprint;
+
+    // XXH64 reference values
+    assert_eq!(fingerprint(b""), 0xEF46DB3751D8E999);
+    assert_eq!(fingerprint(b"a"), 0xD24EC4F1A98C6E5B);
+    assert_eq!(fingerprint(b"abc"), 0x44BC2CF5AD770999);
+    assert_eq!(fingerprint(b"Nobody inspects the spammish repetition"), 0xFBCEA83C8A378BF1);
+}
+
+#[test]
+fn save_load() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+c").expect("failed to compile regex");
+    searcher.add_regex_str(r"x+").expect("failed to compile regex");
+    searcher.set_window(1, 16);
+    searcher.set_line_tracking(true);
+    searcher.push_slice(b"x\nxx abbb");
+
+    let mut saved = vec![];
+    searcher.save(&mut saved).unwrap();
+    let mut loaded = unsafe { RingSearcher::load(&saved[..]) }.unwrap();
+    assert_eq!(loaded.position(), searcher.position());
+    assert_eq!(loaded.pattern(0), Some("ab+c"));
+
+    // the match in progress continues
+    for s in [&mut searcher, &mut loaded] {
+        s.push_slice(b"bc x");
+    }
+    let found: Vec<_> = loaded.matches().collect();
+    assert_eq!(found, searcher.matches().collect::<Vec<_>>());
+    assert_eq!(found[0], (0, Match::new(5, 11)));
+    assert_eq!(loaded.match_data(&found[0].1), b"abbbbc"[..]);
+    assert_eq!(loaded.line_col(5), searcher.line_col(5));
+
+    saved[40] ^= 1;
+    assert!(unsafe { RingSearcher::load(&saved[..]) }.is_err());
+}
+
+#[test]
+fn save_load_queued() {
+    let mut searcher = RingSearcher::new(64);
+    searcher.add_regex_str(r"ab").expect("failed to compile regex");
+    searcher.set_match_queue(true);
+    searcher.push_slice(b"ab ab ");
+
+    let mut saved = vec![];
+    searcher.save(&mut saved).unwrap();
+    let mut loaded = unsafe { RingSearcher::load(&saved[..]) }.unwrap();
+    let queued: Vec<_> = loaded.drain_matches().collect();
+    assert_eq!(queued, [(0, Match::new(0, 2)), (0, Match::new(3, 5))]);
+    assert_eq!(queued, searcher.drain_matches().collect::<Vec<_>>());
+}
+
+#[test]
+fn matches_string() {
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str(r"a(öü|🦀)").expect("failed to compile regex");
+
+    // the characters wrap around the end of the ring
+    let mut found = vec![];
+    for &b in "xx aöü a🦀 ".as_bytes() {
+        searcher.push(b);
+        found.extend(searcher.matches_string().map(|(_, _, data)| data));
+    }
+    assert_eq!(found, ["aöü", "a🦀"]);
+}
+
+#[test]
+fn start_bound() {
+    use regex_ring::StartBound;
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"a+").expect("failed to compile regex");
+    searcher.set_window(0, 4);
+    searcher.push_slice(b"xaaaaaaaax aax");
+
+    let found: Vec<_> = searcher.matches().map(|(_, m)| m).collect();
+    assert_eq!(found[0].start, StartBound::AtOrBefore(5));
+    assert_eq!((found[0].start(), found[0].range(), found[0].len()), (None, None, None));
+    assert_eq!(found[1], Match::new(11, 13));
+    assert_eq!((found[1].range(), found[1].len()), (Some(11 .. 13), Some(2)));
+}
+
+#[test]
+fn sized_state_ids() {
+    use regex_automata::DenseDFA;
+
+    let patterns = [r"ab+c", r"[0-9]{3}-[0-9]{4}", r"passwo?rd"];
+    let mut wide = RingSearcher::new(1024);
+    let mut narrow = RingSearcher::<DenseDFA<Vec<u16>, u16>>::new(1024);
+    for pattern in patterns {
+        wide.add_regex_str(pattern).expect("failed to compile regex");
+        narrow.add_sized_regex_str(pattern).expect("failed to compile regex");
+    }
+    assert!(narrow.memory_usage() < wide.memory_usage());
+
+    let input = b"xabbc 555-1234 pasword password ";
+    wide.push_slice(input);
+    narrow.push_slice(input);
+    assert_eq!(narrow.matches().collect::<Vec<_>>(), wide.matches().collect::<Vec<_>>());
+    assert_eq!(narrow.matches().count(), 3);
+}
+
+#[test]
+fn minimized() {
+    let pattern = r"(a|b)*a(a|b){4}x";
+    let mut plain = RingSearcher::new(1024);
+    plain.add_regex_str(pattern).expect("failed to compile regex");
+    let mut minimized = RingSearcher::<regex_automata::DenseDFA<Vec<usize>, usize>>::new(1024);
+    minimized.add_minimized_regex_str(pattern).expect("failed to compile regex");
+    assert!(minimized.memory_usage() <= plain.memory_usage());
+
+    let input = b"ababbbabbabx bbaaaaax ";
+    plain.push_slice(input);
+    minimized.push_slice(input);
+    assert_eq!(minimized.matches().collect::<Vec<_>>(), plain.matches().collect::<Vec<_>>());
+    assert_eq!(minimized.matches().count(), 2);
+}
+
+#[test]
+fn search_record_reset() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"a[^x]*b").expect("failed to compile regex");
+    searcher.add_regex_str(r"c[^x]*d").expect("failed to compile regex");
+    searcher.set_search_record_reset(1, true);
+
+    let mut found = vec![];
+    searcher.input_matches(&b"a\nb c\nd cd"[..], |search_id, match_, data| {
+        found.push((search_id, match_.start(), data.to_string()));
+    });
+    assert_eq!(found, [(0, Some(0), "a\nb".into()), (1, Some(8), "cd".into())]);
+}
+
+#[test]
+fn push_slice() {
+    let patterns = [r"fo+", r"[0-9]+", r"a[^x]*b"];
+    let mut input = vec![];
+    for i in 0 .. 2000 {
+        input.extend_from_slice(format!("foo{} a\nb {}x ", i, i * 7).as_bytes());
+    }
+
+    let mut bytewise = RingSearcher::new(256);
+    let mut chunked = RingSearcher::new(256);
+    for p in patterns {
+        bytewise.add_regex_str(p).expect("failed to compile regex");
+        chunked.add_regex_str(p).expect("failed to compile regex");
+    }
+    bytewise.set_record_reset(true);
+    chunked.set_record_reset(true);
+
+    let mut expected = vec![];
+    for &b in &input {
+        bytewise.push(b);
+        expected.extend(bytewise.matches().map(|(id, m)| (id, m.start(), m.end)));
+    }
+    let mut found = vec![];
+    for chunk in input.chunks(5000) {
+        chunked.push_slice(chunk);
+        found.extend(chunked.matches().map(|(id, m)| (id, m.start(), m.end)));
+    }
+    assert!(!expected.is_empty());
+    assert_eq!(found, expected);
+    assert_eq!(chunked.records(), bytewise.records());
+
+    // chunks smaller than the buffer, and the data left in it
+    let mut small = RingSearcher::new(256);
+    for p in patterns {
+        small.add_regex_str(p).expect("failed to compile regex");
+    }
+    small.set_record_reset(true);
+    let mut found = vec![];
+    for chunk in input.chunks(100) {
+        small.push_slice(chunk);
+        found.extend(small.matches().map(|(id, m)| (id, m.start(), m.end)));
+    }
+    assert_eq!(found, expected);
+    let last = Match::new(input.len() - 200, input.len());
+    assert_eq!(chunked.match_data(&last).to_vec(), small.match_data(&last).to_vec());
+}
+
+#[test]
+fn skip_idle() {
+    let mut input = vec![b'.'; 10_000];
+    input[100 .. 103].copy_from_slice(b"foo");
+    input[5000 .. 5003].copy_from_slice(b"bar");
+    input[9000 .. 9004].copy_from_slice(b"barr");
+    input[9500 .. 9507].copy_from_slice(b"\xc3barfoo");
+
+    let mut searcher = RingSearcher::new(64);
+    searcher.add_regex_str(r"foo").expect("failed to compile regex");
+    searcher.add_regex_str(r"bar+").expect("failed to compile regex");
+
+    let mut found = vec![];
+    for chunk in input.chunks(3000) {
+        searcher.push_slice(chunk);
+        found.extend(searcher.matches().map(|(id, m)| (id, m.start(), m.end)));
+    }
+    assert_eq!(found, [
+        (0, Some(100), 103), (1, Some(5000), 5003), (1, Some(9000), 9004),
+        (1, Some(9501), 9504), (0, Some(9504), 9507),
+    ]);
+
+    // the same, byte by byte
+    let mut expected = vec![];
+    searcher.input_matches(&input, |id, m, _| expected.push((id, m.start().map(|s| s - 10_000), m.end - 10_000)));
+    assert_eq!(expected, found);
+}
+
+#[test]
+fn extend() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    searcher.extend(b"ab abb ");
+    searcher.extend(b"abbb".iter());
+    searcher.extend(Some(b' '));
+    let found: Vec<_> = searcher.drain_matches().map(|(id, m)| (id, m.start(), m.end)).collect();
+    assert_eq!(found, [(0, Some(0), 2), (0, Some(3), 6), (0, Some(7), 11)]);
+    assert_eq!(searcher.matches().count(), 0);
+}
+
+#[test]
+fn debug() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher.add_regex_str(r"c").expect("failed to compile regex");
+    searcher.extend(b"xab");
+    assert_eq!(format!("{:?}", searcher),
+        "RingSearcher { position: 3, buffered: 3, buffer_size: 1024, searches: 2, in_match: [0], paused: [], records: 0 }");
+}
+
+#[test]
+fn try_input_matches() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    let mut found = vec![];
+    let mut input = b"ab abb abbb".iter();
+    let result = searcher.try_input_matches(&mut input, |_, m, _| {
+        found.push(m.end);
+        if found.len() == 2 { Err("full") } else { Ok(()) }
+    });
+    assert_eq!(result, Err("full"));
+    assert_eq!(found, [2, 6]);
+    // the scan stopped after the byte following the second match
+    assert_eq!(input.as_slice(), b"abbb");
+}
+
+#[test]
+fn input_matches_until() {
+    use std::ops::ControlFlow;
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    let flow = searcher.input_matches_until(b"xx abb ab", |_, m, data| {
+        if data.to_vec() == b"abb" { ControlFlow::Break(m.start()) } else { ControlFlow::Continue(()) }
+    });
+    assert_eq!(flow, ControlFlow::Break(Some(3)));
+
+    // a header within the first bytes of a reader
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"MAGIC").expect("failed to compile regex");
+    let mut input = b"..MAGIC".to_vec();
+    input.extend_from_slice(&[b'.'; 1 << 20]);
+    let mut reader = &input[..];
+    let flow = searcher.scan_reader_until(&mut reader, |_, m, _| ControlFlow::Break(m.end)).unwrap();
+    assert_eq!(flow, ControlFlow::Break(7));
+    assert!(!reader.is_empty());
+}
+
+#[test]
+fn cancel() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher.set_cancel_flag(flag.clone());
+
+    let mut input = vec![b' '; 100_000];
+    input[10 .. 12].copy_from_slice(b"ab");
+    input[50_000 .. 50_002].copy_from_slice(b"ab");
+    let mut found = vec![];
+    let result = searcher.scan_reader(&input[..], |_, m, _| {
+        found.push(m.end);
+        flag.store(true, Ordering::Relaxed);
+    });
+    assert!(result.is_err());
+    assert!(searcher.is_cancelled());
+    assert_eq!(found, [12]);
+    assert!(searcher.position() < 50_000);
+
+    flag.store(false, Ordering::Relaxed);
+    let mut searcher = searcher.fork();
+    let mut found = vec![];
+    searcher.input_matches(&input, |_, m, _| {
+        found.push(m.end);
+        flag.store(true, Ordering::Relaxed);
+    });
+    assert_eq!(found.len(), 1);
+}
+
+#[test]
+fn progress() {
+    use std::sync::{Arc, Mutex};
+    use regex_ring::Progress;
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    let r = reports.clone();
+    searcher.set_progress_callback(10, move |progress| r.lock().unwrap().push(*progress));
+
+    searcher.push_slice(b"ab\nab\nab\n");
+    searcher.push_slice(b"xxxxxxxxxxxxxxxxxxxxxxxxxx");
+    for &b in b"abbbb" {
+        searcher.push(b);
+    }
+    assert_eq!(*reports.lock().unwrap(), [
+        Progress { position: 35, matches: 3, records: 3 },
+        Progress { position: 40, matches: 3, records: 3 },
+    ]);
+}
+
+#[test]
+fn matches_iter() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    let mut iter = searcher.matches_iter(b"ab abb abbb");
+    assert_eq!(iter.next().map(|(_, m, data)| (m.start(), data)), Some((Some(0), b"ab".to_vec())));
+
+    let found: Vec<_> = iter.map(|(id, m, data)| (id, m.start(), m.end, data)).collect();
+    assert_eq!(found, [(0, Some(3), 6, b"abb".to_vec()), (0, Some(7), 11, b"abbb".to_vec())]);
+}
+
+#[test]
+fn scan_iter() {
+    use regex_ring::Event;
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    // mark the matches in the output
+    let mut out = vec![];
+    for event in searcher.scan_iter(b"xab abb") {
+        match event {
+            Event::Byte(b) => out.push(b),
+            Event::Match(_, m) => out.extend_from_slice(format!("<{}>", m.start().unwrap()).as_bytes()),
+        }
+    }
+    assert_eq!(out, b"xab<1> abb<4>");
+}
+
+#[test]
+fn overflow() {
+    use std::sync::{Arc, Mutex};
+
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str(r"a[a-z]*").expect("failed to compile regex");
+    let overflow = Arc::new(Mutex::new(vec![]));
+    let sink = overflow.clone();
+    searcher.set_overflow_callback(move |id, position, data| {
+        sink.lock().unwrap().push((id, position, data.to_vec()));
+    });
+
+    let long: Vec<u8> = (0 .. 40).map(|i| b'a' + i % 26).collect();
+    let mut input = b"xx ".to_vec();
+    input.extend_from_slice(&long);
+    input.extend_from_slice(b" xx");
+
+    let mut found = vec![];
+    searcher.input_matches(&input, |_, m, data| {
+        let mut full = vec![];
+        for (_, position, data) in overflow.lock().unwrap().drain(..) {
+            assert_eq!(position, 3 + full.len());
+            full.extend(data);
+        }
+        full.extend(data.to_vec());
+        found.push((m.start(), m.end, full));
+    });
+    assert_eq!(found, [(None, 43, long)]);
+}
+
+#[test]
+fn introspection() {
+    let mut searcher = RingSearcher::new(64);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher.add_fuzzy(b"hello", 1).expect("invalid fuzzy search");
+    assert_eq!(searcher.num_searches(), 2);
+    assert_eq!(searcher.pattern(0), Some("ab+"));
+    assert_eq!(searcher.pattern(1), None);
+    assert_eq!(searcher.pattern(2), None);
+
+    searcher.push_slice(&[b'x'; 100]);
+    assert_eq!(searcher.position(), 100);
+    assert_eq!(searcher.capacity(), 64);
+    assert_eq!(searcher.buffer_len(), 64);
+}
+
+#[test]
+fn unbounded() {
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str(r"a[a-z]*").expect("failed to compile regex");
+    searcher.set_unbounded(true);
+
+    let long: Vec<u8> = (0 .. 1000).map(|i| b'a' + (i % 26) as u8).collect();
+    let mut input = b"xx ".to_vec();
+    input.extend_from_slice(&long);
+    input.extend_from_slice(b" xx");
+
+    let mut found = vec![];
+    searcher.input_matches(&input, |_, m, data| found.push((m.start(), m.end, data.to_vec())));
+    assert_eq!(found, [(Some(3), 1003, long)]);
+    assert_eq!(searcher.buffer_len(), input.len());
+}
+
+#[test]
+fn eviction() {
+    use std::sync::{Arc, Mutex};
+
+    let input: Vec<u8> = (0 .. 10_000).map(|i| (i % 251) as u8).collect();
+    for chunk_size in [1, 100, 5000] {
+        let mut searcher = RingSearcher::new(64);
+        searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+        let archive = Arc::new(Mutex::new(vec![]));
+        let sink = archive.clone();
+        searcher.set_eviction_callback(move |position, data| {
+            let mut archive = sink.lock().unwrap();
+            assert_eq!(position, archive.len());
+            archive.extend_from_slice(data);
+        });
+        for chunk in input.chunks(chunk_size) {
+            searcher.push_slice(chunk);
+        }
+        let archive = archive.lock().unwrap();
+        assert_eq!(archive.len() + searcher.buffer_len(), input.len());
+        assert_eq!(&archive[..], &input[.. archive.len()]);
+    }
+}
+
+#[test]
+fn spill() {
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str(r"a[a-z]*").expect("failed to compile regex");
+    searcher.set_spill(Some(1000)).expect("failed to create spill file");
+
+    let long: Vec<u8> = (0 .. 300).map(|i| b'a' + (i % 26) as u8).collect();
+    let mut input = b"xx ".to_vec();
+    input.extend_from_slice(&long);
+    input.extend_from_slice(b" xx");
+    input.extend_from_slice(&[b' '; 2000]);
+    input.extend_from_slice(&long);
+
+    let mut found = vec![];
+    for chunk in input.chunks(100) {
+        searcher.push_slice(chunk);
+        for (_, m) in searcher.matches() {
+            found.push((m.start(), m.end, searcher.read_match(&m).unwrap()));
+        }
+    }
+    for (_, m) in searcher.final_matches() {
+        found.push((m.start(), m.end, searcher.read_match(&m).unwrap()));
+    }
+    assert_eq!(found, [(Some(3), 303, long.clone()), (Some(2306), 2606, long)]);
+}
+
+#[test]
+fn scan_file() {
+    let mut input = vec![b' '; 10000];
+    input[10 .. 13].copy_from_slice(b"abb");
+    input[4095 .. 4098].copy_from_slice(b"abb");
+    input.extend_from_slice(b"ab");
+    let path = std::env::temp_dir().join(format!("regex-ring-scan-file-{}", std::process::id()));
+    std::fs::write(&path, &input).expect("failed to write input");
+
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    let mut found = vec![];
+    let result = searcher.scan_file(&path, |p, _, m, data| {
+        assert_eq!(p, path.as_path());
+        found.push((m.start(), m.end, data.to_vec()));
+    });
+    std::fs::remove_file(&path).expect("failed to remove input");
+    result.expect("failed to scan file");
+    assert_eq!(found, [
+        (Some(10), 13, b"abb".to_vec()),
+        (Some(4095), 4098, b"abb".to_vec()),
+        (Some(10000), 10002, b"ab".to_vec()),
+    ]);
+
+    assert!(searcher.scan_file(std::env::temp_dir().join("regex-ring-missing"), |_, _, _, _| ()).is_err());
+}
+
+#[test]
+fn line_col() {
+    use regex_ring::LineCol;
+
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher.set_line_tracking(true);
+
+    let mut input = b"x\nyy\n".to_vec();
+    input.extend_from_slice(&[b' '; 20]);
+    input.extend_from_slice(b"abb\nab");
+    let mut found = vec![];
+    for chunk in input.chunks(5) {
+        searcher.push_slice(chunk);
+        for (_, m) in searcher.matches() {
+            found.push((searcher.line_col(m.start().unwrap()), searcher.line_col(m.end)));
+        }
+    }
+    for (_, m) in searcher.final_matches() {
+        found.push((searcher.line_col(m.start().unwrap()), searcher.line_col(m.end)));
+    }
+    assert_eq!(found, [
+        // the start of the line is evicted, but known
+        (Some(LineCol { line: 3, column: 21 }), Some(LineCol { line: 3, column: 24 })),
+        (Some(LineCol { line: 4, column: 1 }), Some(LineCol { line: 4, column: 3 })),
+    ]);
+    assert_eq!(searcher.line_col(0), None);
+
+    // a chunk larger than the buffer
+    let mut searcher = RingSearcher::<regex_automata::DenseDFA<Vec<usize>, usize>>::new(8);
+    searcher.set_line_tracking(true);
+    searcher.push_slice(b"a\nb\nc\n0123456789\nxy");
+    assert_eq!(searcher.line_col(searcher.position()), Some(LineCol { line: 5, column: 3 }));
+    assert_eq!(searcher.line_col(searcher.position() - 3), Some(LineCol { line: 4, column: 11 }));
+}
+
+#[test]
+fn dump_buffer() {
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher.push_slice(b"0123456789abcdef");
+
+    let mut dump = vec![];
+    searcher.dump_buffer(&mut dump).unwrap();
+    assert_eq!(dump, b"offset 8 length 8\n89abcdef");
+}
+
+#[test]
+fn fork() {
+    use std::sync::{Arc, Mutex};
+
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str(r"GET /[a-z]+").expect("failed to compile regex");
+    searcher.add_regex_str(r"POST /[a-z]+").expect("failed to compile regex");
+    let archive = Arc::new(Mutex::new(vec![]));
+    let sink = archive.clone();
+    searcher.set_eviction_callback(move |_, data| sink.lock().unwrap().extend_from_slice(data));
+    searcher.push_slice(b"GET /ind");
+
+    let mut found = vec![];
+    let mut fork = searcher.fork();
+    fork.input_matches(b"ex          ", |id, m, data| found.push((id, m.start(), data.to_string())));
+    searcher.input_matches(b" POST /x        ", |id, m, data| found.push((id, m.start(), data.to_string())));
+    assert_eq!(found, [(0, Some(0), "GET /index".into()), (0, Some(0), "GET /ind".into()), (1, Some(9), "POST /x".into())]);
+    assert_eq!(&archive.lock().unwrap()[..], b"GET /ind");
+}
+
+#[test]
+fn try_add_many() {
+    let mut searcher = RingSearcher::new(16);
+    let results = searcher.try_add_many(["foo", "(bar", "baz"]);
+    assert_eq!(results[0].as_ref().unwrap(), &0);
+    let rejected = results[1].as_ref().unwrap_err();
+    assert!(matches!(rejected.error, regex_ring::Error::InvalidRegex));
+    assert!(!rejected.reason.is_empty());
+    assert_eq!(results[2].as_ref().unwrap(), &1);
+
+    searcher.push_slice(b"baz foo ");
+    let found: Vec<usize> = searcher.matches().map(|(i, _)| i).collect();
+    assert_eq!(found, [1, 0]);
+}
+
+#[test]
+fn add_regex_str_id() {
+    let mut searcher = RingSearcher::new(16);
+    let a = searcher.add_regex_str("a").unwrap();
+    let b = searcher.add_regex(regex_automata::Regex::new("b").unwrap());
+    let c = searcher.add_regex_str("c").unwrap();
+    assert_eq!((a, b, c), (0, 1, 2));
+}
+
+#[test]
+fn pinning() {
+    let input = b"xx <aaaaaaaaaaaaaaaaaaaaaaaa> yy";
+    for limit in [None, Some(64)] {
+        let mut searcher = RingSearcher::new(8);
+        searcher.add_regex_str("<a*>").unwrap();
+        searcher.set_pinning(limit);
+        let mut found = vec![];
+        for &b in input.iter() {
+            searcher.push(b);
+            found.extend(searcher.matches().map(|(_, m)| (m, searcher.match_data(&m).to_vec())));
+        }
+        assert!(searcher.buffer_len() <= 9);
+        if limit.is_none() {
+            // the start was evicted
+            assert!(found.is_empty());
+            continue;
+        }
+        assert_eq!(found, [(Match::new(3, 29), input[3 .. 29].to_vec())]);
+    }
+}
+
+#[test]
+fn pinning_memory_limit() {
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str("<a*>").unwrap();
+    searcher.set_pinning(Some(1 << 20));
+    let limit = searcher.memory_usage() + 256;
+    searcher.set_memory_limit(Some(limit));
+    searcher.push(b'<');
+    for _ in 0 .. 4096 {
+        searcher.push(b'a');
+    }
+    assert!(searcher.memory_usage() <= limit);
+    assert!(searcher.truncated());
+
+    // the start of the long match was evicted, like without pinning, later matches are found
+    searcher.push_slice(b"> <aa> ");
+    let found: Vec<_> = searcher.matches().map(|(_, m)| (m, searcher.match_data(&m).to_vec())).collect();
+    assert_eq!(found, [(Match::new(4099, 4103), b"<aa>".to_vec())]);
+    assert!(searcher.memory_usage() <= limit);
+}
+
+#[test]
+fn stale_match_data() {
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str("ab").unwrap();
+    searcher.push_slice(b"xabx");
+    let match_ = searcher.matches().next().unwrap().1;
+    assert_eq!(searcher.checked_match_data(&match_).unwrap().to_vec(), b"ab");
+
+    searcher.push_slice(b"12345");
+    assert!(searcher.checked_match_data(&match_).is_ok());
+    searcher.push_slice(b"6");
+    searcher.push_slice(b"7");
+    assert_eq!(searcher.checked_match_data(&match_).unwrap_err(), regex_ring::MatchDataError::Evicted);
+    assert_eq!(searcher.checked_match_data(&Match::new(20, 22)).unwrap_err(), regex_ring::MatchDataError::NotPushed);
+    let inverted = Match { start: regex_ring::StartBound::Exact(12), end: 10 };
+    assert_eq!(searcher.checked_match_data(&inverted).unwrap_err(), regex_ring::MatchDataError::Inverted);
+    assert!(searcher.match_data(&inverted).is_empty());
+    assert!(searcher.match_data(&Match::new(0, 2)).is_empty());
+
+    // a match longer than the buffer has an inexact start and incomplete data
+    searcher.add_regex_str("c+").unwrap();
+    searcher.push_slice(b"cccccccccc-");
+    let (_, match_) = searcher.matches().find(|&(id, _)| id == 1).unwrap();
+    assert_eq!(match_.start, regex_ring::StartBound::AtOrBefore(13));
+    assert_eq!(searcher.checked_match_data(&match_).unwrap_err(), regex_ring::MatchDataError::Evicted);
+}
+
+#[test]
+fn match_queue() {
+    let mut searcher = RingSearcher::new(4);
+    searcher.add_regex_str("ab").unwrap();
+    searcher.set_match_queue(true);
+    searcher.push_slice(b"xab-");
+    for &b in b"abab-------" {
+        searcher.push(b);
+    }
+    searcher.push_slice(&[b'-'; 10000]);
+    let found: Vec<_> = searcher.drain_matches().collect();
+    assert_eq!(found, [(0, Match::new(1, 3)), (0, Match::new(4, 6)), (0, Match::new(6, 8))]);
+    for (_, m) in &found {
+        assert_eq!(searcher.checked_match_data(m).unwrap().to_vec(), b"ab");
+    }
+    searcher.push(b'-');
+    assert_eq!(searcher.matches().count(), 0);
+    assert!(searcher.buffer_len() <= 5);
+}
+
+#[test]
+fn match_callback() {
+    use std::sync::{Arc, Mutex};
+
+    let found = Arc::new(Mutex::new(vec![]));
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str("a+b").unwrap();
+    let sink = found.clone();
+    searcher.set_match_callback(move |id, m, data| sink.lock().unwrap().push((id, *m, data.to_vec())));
+    searcher.push_slice(b"xaab");
+    searcher.push_slice(&[b'-'; 100]);
+    searcher.push_slice(b"ab");
+    searcher.push(b'.');
+    assert_eq!(*found.lock().unwrap(), [
+        (0, Match::new(1, 4), b"aab".to_vec()),
+        (0, Match::new(104, 106), b"ab".to_vec()),
+    ]);
+}
+
+#[test]
+fn anchored_starts() {
+    let input = b"GET abc\nxGET def\nGET gh\n";
+    for chunk in [1, 3, 100] {
+        let mut searcher = RingSearcher::new(16);
+        let newline = searcher.add_regex_str("\n").unwrap();
+        let get = searcher.add_anchored_regex_str("GET [a-z]+", Some(newline)).unwrap();
+        searcher.flag_start(get, 0);
+        let mut found = vec![];
+        for piece in input.chunks(chunk) {
+            searcher.push_slice(piece);
+            found.extend(searcher.matches().filter(|&(id, _)| id == get).map(|(_, m)| (m, searcher.match_data(&m).to_vec())));
+        }
+        assert_eq!(found, [(Match::new(0, 7), b"GET abc".to_vec()), (Match::new(17, 23), b"GET gh".to_vec())]);
+    }
+
+    // flagged by the caller, completed by final_matches
+    let mut searcher = RingSearcher::new(16);
+    let get = searcher.add_anchored_regex_str("GET [a-z]+", None).unwrap();
+    searcher.flag_start(get, 5);
+    searcher.push_slice(b"GET aGET bc");
+    assert_eq!(searcher.matches().count(), 0);
+    assert_eq!(searcher.final_matches().collect::<Vec<_>>(), [(get, Match::new(5, 11))]);
+}
+
+#[test]
+fn empty_matches() {
+    let mut searcher = RingSearcher::new(16);
+    for pattern in ["a*", "(?:)", "b|", "(?:|c)d?"] {
+        assert!(matches!(searcher.add_regex_str(pattern), Err(regex_ring::Error::EmptyMatch)), "{}", pattern);
+    }
+    let results = searcher.try_add_many(["a*", "ab"]);
+    assert!(matches!(results[0], Err(ref e) if matches!(e.error, regex_ring::Error::EmptyMatch)));
+    assert!(results[1].is_ok());
+
+    // added anyway, only the non-empty matches are reported
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex(regex_automata::Regex::new("a*").unwrap());
+    searcher.add_regex(regex_automata::Regex::new("b|").unwrap());
+    let mut found = vec![];
+    for &b in b"xaayb" {
+        searcher.push(b);
+        found.extend(searcher.matches());
+    }
+    found.extend(searcher.final_matches());
+    assert_eq!(found, [(0, Match::new(1, 3)), (1, Match::new(4, 5))]);
+}
+
+#[test]
+fn finish() {
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str("ab+").unwrap();
+    searcher.push_slice(b"xabbyab");
+    assert_eq!(searcher.matches().collect::<Vec<_>>(), [(0, Match::new(1, 4))]);
+    searcher.finish();
+    assert!(searcher.is_finished());
+    assert_eq!(searcher.matches().collect::<Vec<_>>(), [(0, Match::new(5, 7))]);
+    assert_eq!(searcher.final_matches().count(), 0);
+    assert!(matches!(searcher.try_push_slice(b"b"), Err(regex_ring::Error::Finished)));
+
+    // a second call does not report the matches again
+    searcher.finish();
+    assert_eq!(searcher.matches().count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "push after finish")]
+fn push_after_finish() {
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str("a").unwrap();
+    searcher.finish();
+    searcher.push(b'a');
+}
+
+#[test]
+#[should_panic(expected = "push after finish")]
+fn extend_after_finish() {
+    let mut searcher = RingSearcher::new(16);
+    searcher.add_regex_str("a").unwrap();
+    searcher.push_slice(b"a");
+    searcher.finish();
+    searcher.extend(b"a");
+}
+
+#[test]
+fn reset() {
+    for continue_positions in [false, true] {
+        let mut searcher = RingSearcher::new(16);
+        searcher.add_regex_str("ab+").unwrap();
+        searcher.push_slice(b"xab");
+        searcher.finish();
+        assert_eq!(searcher.matches().collect::<Vec<_>>(), [(0, Match::new(1, 3))]);
+
+        searcher.reset(continue_positions);
+        assert!(!searcher.is_finished());
+        // the `b` does not continue the match of the last stream
+        searcher.push_slice(b"bab");
+        searcher.finish();
+        let start = if continue_positions { 3 } else { 0 };
+        assert_eq!(searcher.matches().collect::<Vec<_>>(), [(0, Match::new(start + 1, start + 3))]);
+        assert_eq!(searcher.position(), start + 3);
+    }
+}
+
+#[test]
+fn in_progress() {
+    let mut searcher = RingSearcher::new(16);
+    let quoted = searcher.add_regex_str("\"[^\"]*\"").unwrap();
+    searcher.add_regex_str("xyz").unwrap();
+    searcher.push_slice(b"ab \"cdef");
+    assert_eq!(searcher.in_progress().collect::<Vec<_>>(), [(quoted, 5)]);
+    searcher.push_slice(b"\" ");
+    assert_eq!(searcher.in_progress().count(), 0);
+}
+
+#[test]
+fn search_buffer() {
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str("x").unwrap();
+    // the ring wraps, so the buffered data is split in two slices
+    searcher.push_slice(b"id=1 id=22");
+    searcher.push_slice(b" id=333");
+    let regex = regex_automata::Regex::new("id=[0-9]+").unwrap();
+    let found = searcher.search_buffer(&regex);
+    assert_eq!(found, [Match::new(5, 10), Match::new(11, 17)]);
+    assert_eq!(searcher.match_data(&found[1]).to_vec(), b"id=333");
+    assert!(searcher.search_buffer(&regex_automata::Regex::new("z*").unwrap()).is_empty());
+}
+
+#[test]
+fn get_range() {
+    let mut searcher = RingSearcher::new(8);
+    searcher.add_regex_str("x").unwrap();
+    searcher.push_slice(b"0123456789");
+    searcher.push_slice(b"abc");
+    assert_eq!(searcher.get_range(4 .. 12).unwrap().to_vec(), b"456789ab");
+    assert_eq!(searcher.get_range(13 .. 13).unwrap().len(), 0);
+    assert!(searcher.get_range(1 .. 4).is_none());
+    assert!(searcher.get_range(10 .. 14).is_none());
+}
+
+#[test]
+fn add_mid_stream() {
+    let mut searcher = RingSearcher::new(16);
+    let x = searcher.add_regex_str("x").unwrap();
+    searcher.push_slice(b"aaab aaa");
+    let ab = searcher.add_regex_str("a+b").unwrap();
+    searcher.push_slice(b"ab ");
+    // the search does not look at the data before it was added
+    assert_eq!(searcher.matches().collect::<Vec<_>>(), [(ab, Match::new(8, 10))]);
+    assert_eq!(searcher.match_data(&Match::new(8, 10)).to_vec(), b"ab");
+    assert_ne!(x, ab);
+}
+
+#[test]
+fn remove_search() {
+    let mut searcher = RingSearcher::new(16);
+    let a = searcher.add_regex_str("a").unwrap();
+    let b = searcher.add_regex_str("b").unwrap();
+    let c = searcher.add_regex_str("c").unwrap();
+    searcher.push_slice(b"ab");
+    assert!(searcher.remove_search(b));
+    assert!(!searcher.remove_search(b));
+    assert!(searcher.is_removed(b));
+    assert_eq!(searcher.pattern(b), None);
+    // unknown identifiers
+    assert!(!searcher.is_removed(7));
+    assert!(!searcher.remove_search(7));
+
+    // the other identifiers stay, new searches get new ones
+    let d = searcher.add_regex_str("d").unwrap();
+    assert_eq!(d, 3);
+    searcher.push_slice(b"abcd ");
+    let ids: Vec<usize> = searcher.matches().map(|(id, _)| id).collect();
+    assert_eq!(ids, [a, c, d]);
+
+    let mut saved = vec![];
+    searcher.save(&mut saved).unwrap();
+    let loaded = unsafe { RingSearcher::load(&saved[..]) }.unwrap();
+    assert!(loaded.is_removed(b));
+    assert_eq!(loaded.pattern(c), Some("c"));
+}
+
+#[test]
+fn match_data_format() {
+    // a character split by the end of the ring
+    let data = regex_ring::MatchData { head: b"caf\xc3", tail: b"\xa9 \xff\n" };
+    assert_eq!(data.to_string(), "caf\u{e9} \u{fffd}\n");
+    assert_eq!(format!("{:?}", data), r#""caf\xc3\xa9 \xff\n""#);
+    assert_eq!(data.bytes().count(), 8);
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use regex_ring::{RingSearcher, Error, cache};
+
+#[test]
+fn cache() {
+    let mut searchers: Vec<_> = (0 .. 3).map(|_| {
+        let mut searcher = RingSearcher::new(1024);
+        searcher.add_cached_regex_str(r"ab+c", "").expect("failed to compile regex");
+        searcher.add_cached_regex_str(r"ab+c", "i").expect("failed to compile regex");
+        searcher
+    }).collect();
+    assert_eq!(cache::len(), 2);
+    assert!(Arc::ptr_eq(&cache::compile(r"ab+c", "").unwrap(), &cache::compile(r"ab+c", "").unwrap()));
+    assert!(matches!(cache::compile(r"ab+c", "q"), Err(Error::InvalidFlag('q'))));
+
+    for searcher in &mut searchers {
+        searcher.push_slice(b"abc ABBC ");
+        assert_eq!(searcher.matches().map(|(id, _)| id).collect::<Vec<_>>(), [0, 1, 1]);
+    }
+
+    cache::clear();
+    assert_eq!(cache::len(), 0);
+    searchers[0].push_slice(b"abc ");
+    assert_eq!(searchers[0].matches().count(), 2);
+}
@@ -0,0 +1,30 @@
+use regex_automata::DenseDFA;
+use regex_ring::carve::{Carver, Carve};
+
+#[test]
+fn carve() {
+    let mut carver = Carver::<DenseDFA<Vec<usize>, usize>>::new();
+    carver.add_rule_str(r"<html>", r"</html>", 64).expect("failed to compile rule");
+    carver.add_rule_str(r"%PDF-", r"%%EOF", 24).expect("failed to compile rule");
+
+    let mut input = b"junk".to_vec();
+    let html = b"<html> page <html> nested </html>";
+    input.extend_from_slice(html);
+    // too long for the pdf rule
+    input.extend_from_slice(b" %PDF-1.4 a long document body %%EOF ");
+    input.extend_from_slice(b"%PDF-1.4 short%%EOF");
+
+    let mut found = vec![];
+    for chunk in input.chunks(7) {
+        carver.push_slice(chunk);
+        found.extend(carver.carves().cloned());
+    }
+    carver.finish();
+    found.extend(carver.carves().cloned());
+
+    let pdf_start = input.len() - 19;
+    assert_eq!(found, [
+        Carve { rule: 0, start: 4, end: 4 + html.len(), data: html.to_vec() },
+        Carve { rule: 1, start: pdf_start, end: input.len(), data: b"%PDF-1.4 short%%EOF".to_vec() },
+    ]);
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "tokio")]
+use bytes::BytesMut;
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, MatchRecord, StartBound};
+use regex_ring::codec::MatchDecoder;
+use tokio_util::codec::Decoder;
+
+#[test]
+fn decode() {
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    let mut decoder = MatchDecoder::new(searcher);
+
+    let mut src = BytesMut::from(&b"ab ab"[..]);
+    let record = decoder.decode(&mut src).unwrap();
+    assert_eq!(record, Some(MatchRecord { id: 0, start: StartBound::Exact(0), end: 2, data: b"ab".to_vec(), fingerprint: None }));
+    assert!(src.is_empty());
+    assert_eq!(decoder.decode(&mut src).unwrap(), None);
+
+    src.extend_from_slice(b"bb");
+    assert_eq!(decoder.decode(&mut src).unwrap(), None);
+    let record = decoder.decode_eof(&mut src).unwrap();
+    assert_eq!(record, Some(MatchRecord { id: 0, start: StartBound::Exact(3), end: 7, data: b"abbb".to_vec(), fingerprint: None }));
+    assert_eq!(decoder.decode_eof(&mut src).unwrap(), None);
+}
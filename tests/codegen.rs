@@ -0,0 +1,38 @@
+use regex_ring::{RingSearcher, Match};
+use regex_ring::codegen::embed;
+
+/// the file contents, aligned like the generated code does
+fn load(path: std::path::PathBuf) -> &'static [u8] {
+    let bytes = std::fs::read(path).unwrap();
+    let words: Vec<u32> = bytes.chunks(4).map(|chunk| {
+        let mut word = [0; 4];
+        word[.. chunk.len()].copy_from_slice(chunk);
+        u32::from_ne_bytes(word)
+    }).collect();
+    let words: &'static [u32] = Vec::leak(words);
+    unsafe { std::slice::from_raw_parts(words.as_ptr() as *const u8, bytes.len()) }
+}
+
+#[test]
+fn embed_patterns() {
+    let dir = std::env::temp_dir().join(format!("regex-ring-codegen-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = embed(&["ab+", "c"], &dir, "rules").unwrap();
+    assert!(source.contains("pub fn rules(buffer_size: usize)"));
+    assert!(source.contains("rules_1_rev_le.dfa"));
+    assert!(embed(&["("], &dir, "broken").is_err());
+
+    let endian = if cfg!(target_endian = "little") { "le" } else { "be" };
+    let mut searcher = RingSearcher::new(16);
+    for (i, pattern) in ["ab+", "c"].iter().enumerate() {
+        let forward = load(dir.join(format!("rules_{}_fwd_{}.dfa", i, endian)));
+        let reverse = load(dir.join(format!("rules_{}_rev_{}.dfa", i, endian)));
+        assert_eq!(unsafe { searcher.add_embedded(forward, reverse, pattern) }.unwrap(), i);
+    }
+    searcher.push_slice(b"xabbc");
+    assert_eq!(searcher.matches().collect::<Vec<_>>(), [(0, Match::new(1, 4))]);
+    searcher.finish();
+    assert_eq!(searcher.matches().collect::<Vec<_>>(), [(1, Match::new(4, 5))]);
+    assert_eq!(searcher.pattern(0), Some("ab+"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
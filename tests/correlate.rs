@@ -0,0 +1,55 @@
+use regex_ring::{RingSearcher, Match};
+use regex_ring::correlate::{Correlator, Sequence, Threshold, ThresholdEvent};
+
+#[test]
+fn sequence() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"fail").expect("failed to compile regex");
+    searcher.add_regex_str(r"shell").expect("failed to compile regex");
+    searcher.add_regex_str(r"logout").expect("failed to compile regex");
+
+    let mut correlator = Correlator::new();
+    let rule = correlator.add_sequence(Sequence::new(0, 1, 10).without(2));
+
+    let input = b"fail shell | fail logout shell | fail ............ shell | fail fail shell";
+    let mut events = vec![];
+    for chunk in input.chunks(5) {
+        searcher.push_slice(chunk);
+        correlator.push(searcher.matches());
+        events.extend(correlator.events().map(|e| (e.rule, e.span())));
+    }
+    correlator.push(searcher.final_matches());
+    events.extend(correlator.events().map(|e| (e.rule, e.span())));
+
+    let events: Vec<_> = events.iter()
+        .map(|&(rule, span)| (rule, &input[span.range().unwrap()]))
+        .collect();
+    assert_eq!(events, [(rule, &b"fail shell"[..]), (rule, &b"fail shell"[..])]);
+}
+
+#[test]
+fn threshold() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"x").expect("failed to compile regex");
+
+    let mut correlator = Correlator::new();
+    let rule = correlator.add_threshold(Threshold { search_nr: 0, count: 2, window: 10 });
+
+    // three within 10 bytes, then a sparse run, then a burst
+    let input = b"x.x.x......x..........x..........xxxxxx";
+    let mut events = vec![];
+    for chunk in input.chunks(4) {
+        searcher.push_slice(chunk);
+        correlator.push(searcher.matches());
+        events.extend(correlator.threshold_events().cloned());
+    }
+    correlator.push(searcher.final_matches());
+    events.extend(correlator.threshold_events().cloned());
+
+    let span = Match::new;
+    assert_eq!(events, [
+        ThresholdEvent { rule, count: 3, span: span(0, 5) },
+        ThresholdEvent { rule, count: 3, span: span(33, 36) },
+        ThresholdEvent { rule, count: 3, span: span(36, 39) },
+    ]);
+}
@@ -0,0 +1,109 @@
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, Match};
+use regex_ring::decode::{DecodingSearcher, DecodedMatch, Encoding, Pipeline, Stage, View, ViewSearcher};
+
+#[test]
+fn decode() {
+    let mut template = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    template.add_regex_str(r"password=[a-z0-9]+").expect("failed to compile regex");
+    let mut searcher = DecodingSearcher::new(template, &[Encoding::Base64, Encoding::Hex]);
+
+    // "user=root password=hunter2;" and "say password=swordfish"
+    let input = b"auth dXNlcj1yb290IHBhc3N3b3JkPWh1bnRlcjI7 and 7361792070617373776f72643d73776f726466697368";
+    let mut found = vec![];
+    for chunk in input.chunks(7) {
+        searcher.push_slice(chunk);
+        found.extend(searcher.matches().cloned());
+    }
+    searcher.finish();
+    found.extend(searcher.matches().cloned());
+
+    assert_eq!(found, [
+        DecodedMatch {
+            pipeline: 0,
+            encoding: Encoding::Base64,
+            id: 0,
+            region: 5,
+            decoded: Match::new(10, 26),
+            encoded: Match::new(18, 40),
+            data: b"password=hunter2".to_vec(),
+        },
+        DecodedMatch {
+            pipeline: 1,
+            encoding: Encoding::Hex,
+            id: 0,
+            region: 46,
+            decoded: Match::new(4, 22),
+            encoded: Match::new(54, 90),
+            data: b"password=swordfish".to_vec(),
+        },
+    ]);
+    assert_eq!(&input[18 .. 40], b"HBhc3N3b3JkPWh1bnRlcjI");
+}
+
+/// Flips the case of ASCII letters and drops `~`, to test mapping through a stage.
+struct Flip;
+
+impl Stage for Flip {
+    fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> bool {
+        out.extend(input.iter().filter(|&&b| b != b'~').map(|&b| b ^ if b.is_ascii_alphabetic() { 0x20 } else { 0 }));
+        true
+    }
+
+    fn fresh(&self) -> Box<dyn Stage> {
+        Box::new(Flip)
+    }
+}
+
+#[test]
+fn pipeline() {
+    let mut template = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    template.add_regex_str(r"secret").expect("failed to compile regex");
+    let pipelines = vec![
+        Pipeline::new(Encoding::Base64).then(Encoding::Hex).then(Flip),
+        Pipeline::new(Encoding::Base64).then(Encoding::Hex),
+    ];
+    let mut searcher = DecodingSearcher::with_pipelines(template, pipelines);
+
+    // base64 of the hex encoding of "~~SECRET~~ secret"
+    let input = b"> N2U3ZTUzNDU0MzUyNDU1NDdlN2UyMDczNjU2MzcyNjU3NA== <";
+    searcher.push_slice(input);
+    let mut found: Vec<_> = searcher.matches().cloned().collect();
+    searcher.finish();
+    found.extend(searcher.matches().cloned());
+
+    let found: Vec<_> = found.iter().map(|m| (m.pipeline, m.decoded, m.encoded, m.data.clone())).collect();
+    assert_eq!(found, [
+        // the dropped `~~` were consumed before the first output of `Flip`, so they are part of the match
+        (0, Match::new(0, 6), Match::new(2, 24), b"secret".to_vec()),
+        (1, Match::new(11, 17), Match::new(31, 48), b"secret".to_vec()),
+    ]);
+}
+
+#[test]
+fn views() {
+    let mut raw = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    raw.add_regex_str(r"token=[a-z]+").expect("failed to compile regex");
+    let mut decoded = RingSearcher::new(1024);
+    decoded.add_regex_str(r"[a-z]+@[a-z]+").expect("failed to compile regex");
+    decoded.add_regex_str(r"token=[a-z]+").expect("failed to compile regex");
+
+    let mut decoding = DecodingSearcher::default();
+    decoding.add_pipeline(Pipeline::new(Encoding::Hex), decoded);
+    let mut searcher = ViewSearcher::new(raw, decoding);
+
+    // the hex encodes "mail admin@example token=abc"
+    let input = b"token=plain 6d61696c2061646d696e406578616d706c6520746f6b656e3d616263 end";
+    let mut found = vec![];
+    for chunk in input.chunks(10) {
+        searcher.push_slice(chunk);
+        found.extend(searcher.matches().map(|m| (m.view(), m.id(), m.stream_match(), m.data().to_vec())));
+    }
+    searcher.finish();
+    found.extend(searcher.matches().map(|m| (m.view(), m.id(), m.stream_match(), m.data().to_vec())));
+    assert_eq!(found, [
+        (View::Raw, 0, Match::new(0, 11), b"token=plain".to_vec()),
+        (View::Decoded(0), 0, Match::new(22, 48), b"admin@example".to_vec()),
+        (View::Decoded(0), 1, Match::new(50, 68), b"token=abc".to_vec()),
+    ]);
+}
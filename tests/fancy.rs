@@ -0,0 +1,40 @@
+#![cfg(feature = "fancy")]
+use regex_ring::RingSearcher;
+use regex_ring::fancy::approximate;
+
+#[test]
+fn approximation() {
+    assert_eq!(approximate(r"(a+)b\1"), r"(a+)b(?:a+)");
+    assert_eq!(approximate(r"(?P<q>['\x22])[^'\x22]*\k<q>"), r"(?P<q>['\x22])[^'\x22]*(?:['\x22])");
+    assert_eq!(approximate(r"[\1](x)\2"), r"[\1](x)(?s:.*)");
+    assert_eq!(approximate(r"(?:(a)|b)(c\1)\2"), r"(?:(a)|b)(c(?:a))(?:c(?:a))");
+}
+
+#[test]
+fn backreference() {
+    let mut searcher = RingSearcher::new(1024);
+    let id = searcher.add_fancy_regex_str(r"<([a-z]+)>[^<]*</\1>").unwrap();
+
+    let mut found = vec![];
+    searcher.input_matches(&b"<b>x</i> <em>y</em> <i>z</i>"[..], |search_id, match_, data| {
+        assert_eq!(search_id, id);
+        found.push((match_.start(), data.to_string()));
+    });
+    assert_eq!(found, [(Some(9), "<em>y</em>".into()), (Some(20), "<i>z</i>".into())]);
+}
+
+#[test]
+fn lookaround() {
+    assert_eq!(approximate(r"(?<=\$)[0-9]+(?= USD)"), r"(?:\$)[0-9]+(?: USD)");
+    assert_eq!(approximate(r"(?<![a-z])x(?!y(z))"), r"x");
+
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_fancy_regex_str(r"(?<=\$)[0-9]+(?= USD)").unwrap();
+    searcher.add_fancy_regex_str(r"(?<![a-z])x").unwrap();
+
+    let mut found = vec![];
+    searcher.input_matches(&b"$12 EUR, $34 USD, 56 USD ax x"[..], |search_id, match_, data| {
+        found.push((search_id, match_.start(), data.to_string()));
+    });
+    assert_eq!(found, [(0, Some(10), "34".into()), (1, Some(28), "x".into())]);
+}
@@ -0,0 +1,27 @@
+use regex_automata::Regex;
+use regex_ring::FixedSearcher;
+
+#[test]
+fn fixed() {
+    let mut searcher = FixedSearcher::<_, 8, 2>::new();
+    searcher.add_regex(Regex::new(r"[a-z]+").unwrap()).unwrap();
+    searcher.add_regex(Regex::new(r"[0-9]+").unwrap()).unwrap();
+    assert!(searcher.add_regex(Regex::new(r"c").unwrap()).is_err());
+
+    let mut found = vec![];
+    for &b in b"xab 12 abbbbbbbbbb 3" {
+        searcher.push(b);
+        for (id, m) in searcher.matches() {
+            found.push((id, m.start(), m.end, searcher.match_data(&m).to_string()));
+        }
+    }
+    for (id, m) in searcher.final_matches() {
+        found.push((id, m.start(), m.end, searcher.match_data(&m).to_string()));
+    }
+    assert_eq!(found, [
+        (0, Some(0), 3, "xab".into()),
+        (1, Some(4), 6, "12".into()),
+        (0, None, 18, "bbbbbbb".into()),
+        (1, Some(19), 20, "3".into()),
+    ]);
+}
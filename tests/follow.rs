@@ -0,0 +1,58 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, MatchRecord};
+use regex_ring::follow::{Follower, Polled};
+use regex_ring::sink::MatchSink;
+
+/// Collects the data of the matches.
+struct Collect(Vec<String>);
+
+impl MatchSink for Collect {
+    fn deliver(&mut self, _provenance: Option<&str>, record: &MatchRecord) -> io::Result<()> {
+        self.0.push(String::from_utf8_lossy(&record.data).into());
+        Ok(())
+    }
+}
+
+fn append(path: &std::path::Path, data: &[u8]) {
+    OpenOptions::new().append(true).open(path).unwrap().write_all(data).unwrap();
+}
+
+#[test]
+fn follow() {
+    let dir = std::env::temp_dir().join(format!("regex-ring-follow-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("log");
+    fs::write(&path, b"old ab\n").unwrap();
+
+    let mut searcher: RingSearcher<DenseDFA<Vec<usize>, usize>> = RingSearcher::new(64);
+    searcher.add_regex_str(r"ab+").unwrap();
+    let mut follower = Follower::new(&path, searcher, false).unwrap();
+    let mut sink = Collect(vec![]);
+    let mut buf = [0; 16];
+
+    // the existing contents are skipped
+    assert_eq!(follower.poll(&mut buf, &mut sink).unwrap(), Polled::default());
+    append(&path, b"x abb\n");
+    assert_eq!(follower.poll(&mut buf, &mut sink).unwrap(), Polled { read: true, ..Polled::default() });
+    assert_eq!(sink.0, ["abb"]);
+
+    // rotated: the rest of the old file is read, then the new one from its start
+    #[cfg(unix)]
+    {
+        fs::rename(&path, dir.join("log.1")).unwrap();
+        append(&dir.join("log.1"), b"abbb\n");
+        fs::write(&path, b"abbbb\n").unwrap();
+        assert_eq!(follower.poll(&mut buf, &mut sink).unwrap(), Polled { read: true, rotated: true, truncated: false });
+        assert_eq!(sink.0, ["abb", "abbb", "abbbb"]);
+    }
+
+    // truncated in place: read again from the start
+    fs::write(&path, b"ab\n").unwrap();
+    assert_eq!(follower.poll(&mut buf, &mut sink).unwrap(), Polled { read: true, rotated: false, truncated: true });
+    assert_eq!(sink.0.last().map(String::as_str), Some("ab"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
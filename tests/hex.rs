@@ -0,0 +1,23 @@
+use regex_ring::RingSearcher;
+use regex_ring::hex::hex_to_regex;
+
+#[test]
+fn hex_translation() {
+    assert_eq!(hex_to_regex("DE AD ?? EF").unwrap(), r"\xDE\xAD(?s:.)\xEF");
+    assert_eq!(hex_to_regex("de[4-12]00").unwrap(), r"\xDE(?s:.){4,12}\x00");
+    assert_eq!(hex_to_regex("00 [3] 00 [2-] 00").unwrap(), r"\x00(?s:.){3}\x00(?s:.){2,}\x00");
+    assert_eq!(hex_to_regex("A? (01 | 02 03)").unwrap(), r"[\xA0-\xAF](?:\x01|\x02\x03)");
+    for bad in ["", "DE A", "DG", "[4-2]", "(00", "00 | 01", "[x]"] {
+        assert!(hex_to_regex(bad).is_err(), "{:?}", bad);
+    }
+}
+
+#[test]
+fn hex_signature() {
+    let mut searcher = RingSearcher::new(64);
+    let id = searcher.add_hex_str("DE AD ?? EF [1-3] ?0").unwrap();
+    searcher.push_slice(b"\x00\xde\xad\xff\xef\x01\x02\x30\x01");
+    let found: Vec<_> = searcher.matches().map(|(i, m)| (i, m.range())).collect();
+    assert_eq!(found, [(id, Some(1 .. 8))]);
+    assert_eq!(searcher.pattern(id), Some("DE AD ?? EF [1-3] ?0"));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "hs-compat")]
+use regex_ring::hyperscan::*;
+
+#[test]
+fn stream() {
+    let db = compile_multi(&["foo", "ba+r"], &[HS_FLAG_CASELESS, HS_FLAG_SINGLEMATCH], &[10, 20]).unwrap();
+    let mut scratch = alloc_scratch(&db);
+    let mut stream = open_stream(&db);
+
+    let mut found = vec![];
+    for chunk in &[&b"xFO"[..], b"o baar b", b"ar ", b"bar foo"] {
+        scan_stream(&mut stream, chunk, &mut scratch, |id, from, to, _| {
+            found.push((id, from, to));
+            Matching::Continue
+        }).unwrap();
+    }
+    close_stream(stream, &mut scratch, |id, from, to, _| {
+        found.push((id, from, to));
+        Matching::Continue
+    }).unwrap();
+
+    assert_eq!(found, [(10, 1, 4), (20, 5, 9), (10, 18, 21)]);
+}
+
+#[test]
+fn terminate() {
+    let db = compile_multi(&["a"], &[0], &[0]).unwrap();
+    let mut scratch = alloc_scratch(&db);
+    let mut stream = open_stream(&db);
+    assert_eq!(scan_stream(&mut stream, b"a a a", &mut scratch, |_, _, _, _| Matching::Terminate), Err(ScanTerminated));
+    assert_eq!(scan_stream(&mut stream, b"a", &mut scratch, |_, _, _, _| Matching::Continue), Err(ScanTerminated));
+}
+
+#[test]
+fn multiline_unsupported() {
+    let err = compile_multi(&["a", "^b$"], &[0, HS_FLAG_MULTILINE], &[0, 1]).err().unwrap();
+    assert_eq!(err.expression, 1);
+    assert!(matches!(err.error, regex_ring::Error::InvalidFlag('m')));
+}
@@ -0,0 +1,75 @@
+use std::io;
+use std::net::TcpListener;
+use std::thread;
+
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, Match};
+use regex_ring::input::{InputSource, ReaderSource, scan_source};
+
+fn searcher() -> RingSearcher<DenseDFA<Vec<usize>, usize>> {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").unwrap();
+    searcher
+}
+
+fn scan(source: &mut dyn InputSource) -> Vec<(Option<String>, usize, Match)> {
+    let mut found = vec![];
+    scan_source(source, &mut searcher(), &mut |provenance, id, m| found.push((provenance.map(String::from), id, m))).unwrap();
+    found
+}
+
+/// yields the given chunks, like messages of a queue
+struct Messages(Vec<&'static [u8]>, usize);
+
+impl InputSource for Messages {
+    fn next_chunk(&mut self) -> io::Result<Option<&[u8]>> {
+        self.1 += 1;
+        Ok(self.0.get(self.1 - 1).copied())
+    }
+
+    fn provenance(&self) -> Option<&str> {
+        Some("queue")
+    }
+}
+
+#[test]
+fn custom_source() {
+    let mut source: Box<dyn InputSource> = Box::new(Messages(vec![b"xa", b"bb", b"-ab"], 0));
+    assert_eq!(scan(&mut source), [
+        (Some("queue".into()), 0, Match::new(1, 4)),
+        (Some("queue".into()), 0, Match::new(5, 7)),
+    ]);
+}
+
+#[test]
+fn reader_source() {
+    let mut source = ReaderSource::new(&b"abb ab"[..], None);
+    assert_eq!(scan(&mut source), [(None, 0, Match::new(0, 3)), (None, 0, Match::new(4, 6))]);
+}
+
+#[test]
+fn file_source() {
+    let path = std::env::temp_dir().join(format!("regex-ring-input-{}", std::process::id()));
+    std::fs::write(&path, b"--abbb").unwrap();
+    let mut source = ReaderSource::open(&path).unwrap();
+    let found = scan(&mut source);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(found, [(Some(path.display().to_string()), 0, Match::new(2, 6))]);
+}
+
+#[test]
+fn tcp_source() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let sender = thread::spawn(move || {
+        use std::io::Write;
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"xab").unwrap();
+    });
+    let mut source = ReaderSource::connect(addr).unwrap();
+    assert_eq!(source.provenance(), Some(format!("tcp:{}", addr).as_str()));
+    let found = scan(&mut source);
+    sender.join().unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].2, Match::new(1, 3));
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "journal")]
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, Match};
+use regex_ring::journal::{ExportReader, JournalSearcher};
+
+fn export() -> Vec<u8> {
+    let mut export = b"__CURSOR=s=a;i=1\nMESSAGE=disk error on sda\n_PID=12\n\n".to_vec();
+    export.extend_from_slice(b"__CURSOR=s=a;i=2\nMESSAGE\n");
+    export.extend_from_slice(&7u64.to_le_bytes());
+    export.extend_from_slice(b"all\nok\n\n\n");
+    export.extend_from_slice(b"__CURSOR=s=a;i=3\nMESSAGE=error again\n");
+    export
+}
+
+#[test]
+fn read_export() {
+    let entries: Vec<_> = ExportReader::new(&export()[..]).collect::<Result<_, _>>().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].cursor(), Some("s=a;i=1"));
+    assert_eq!(entries[0].field("_PID"), Some(&b"12"[..]));
+    assert_eq!(entries[1].field("MESSAGE"), Some(&b"all\nok\n"[..]));
+    assert_eq!(entries[2].field("MESSAGE"), Some(&b"error again"[..]));
+
+    assert!(ExportReader::new(&b"MESSAGE\n\x05\0\0\0\0\0\0\0ab"[..]).next_entry().is_err());
+    // a huge length does not allocate it up front
+    let err = ExportReader::new(&b"MESSAGE\n\xff\xff\xff\xff\xff\xff\xff\xffab"[..]).next_entry().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn search_journal() {
+    let mut searcher: RingSearcher<DenseDFA<Vec<usize>, usize>> = RingSearcher::new(1024);
+    searcher.add_regex_str(r"error").unwrap();
+    searcher.add_regex_str(r"ok\n").unwrap();
+    let mut journal = JournalSearcher::new(searcher);
+
+    let mut found = vec![];
+    for entry in ExportReader::new(&export()[..]) {
+        found.extend(journal.push_entry(&entry.unwrap()));
+    }
+    found.extend(journal.finish());
+    let found: Vec<_> = found.iter().map(|m| (m.search_nr, m.match_, m.cursor.as_deref(), m.data.as_deref())).collect();
+    assert_eq!(found, [
+        (0, Match::new(5, 10), Some("s=a;i=1"), Some(&b"error"[..])),
+        (1, Match::new(22, 25), Some("s=a;i=2"), Some(&b"ok\n"[..])),
+        (0, Match::new(26, 31), Some("s=a;i=3"), Some(&b"error"[..])),
+    ]);
+}
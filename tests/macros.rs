@@ -0,0 +1,31 @@
+#![cfg(feature = "macros")]
+
+use regex_ring::{patterns, Match};
+
+patterns! {
+    /// requests of a made up protocol
+    pub enum Rule {
+        Get = "GET /[a-z]+",
+        Post = "POST",
+    }
+}
+
+#[test]
+fn typed_rules() {
+    assert_eq!(Rule::ALL, [Rule::Get, Rule::Post]);
+    assert_eq!(Rule::Post.id(), 1);
+    assert_eq!(Rule::from_id(1), Some(Rule::Post));
+    assert_eq!(Rule::from_id(2), None);
+
+    let mut searcher = Rule::searcher(64).unwrap();
+    searcher.push_slice(b"GET /index POST ");
+    let found: Vec<_> = searcher.matches().map(|(id, m)| (Rule::from_id(id).unwrap(), m)).collect();
+    assert_eq!(found, [(Rule::Get, Match::new(0, 10)), (Rule::Post, Match::new(11, 15))]);
+    for (rule, _) in found {
+        let name = match rule {
+            Rule::Get => "get",
+            Rule::Post => "post",
+        };
+        assert_eq!(name, format!("{:?}", rule).to_lowercase());
+    }
+}
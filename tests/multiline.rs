@@ -0,0 +1,37 @@
+use regex_ring::multiline::LineWindow;
+
+#[test]
+fn line_window() {
+    let mut window = LineWindow::new(3);
+    let id = window.add_regex_str(r"Exception[^\n]*\n(?:[^\n]*\n)?\s+at foo\.bar").unwrap();
+    let log = b"Exception: boom\n  at x.y\n  at foo.bar\nok\nException: far\na\nb\n  at foo.bar\n";
+
+    let mut found = vec![];
+    for chunk in log.chunks(5) {
+        window.push_slice(chunk);
+        found.extend(window.matches().map(|(i, m, data)| (*i, m.range(), data.clone())));
+    }
+    window.finish();
+    assert_eq!(window.matches().count(), 0);
+    assert_eq!(found, [(id, Some(0 .. 37), b"Exception: boom\n  at x.y\n  at foo.bar".to_vec())]);
+    assert_eq!(window.position(), log.len());
+}
+
+#[test]
+fn line_window_finish() {
+    let mut window = LineWindow::new(1);
+    window.add_regex_str("end").unwrap();
+    window.push_slice(b"line\nthe end");
+    assert_eq!(window.matches().count(), 0);
+    window.finish();
+    let found: Vec<_> = window.matches().map(|(_, m, _)| m.range()).collect();
+    assert_eq!(found, [Some(9 .. 12)]);
+}
+
+#[test]
+fn line_window_empty_match() {
+    let mut window = LineWindow::new(2);
+    for pattern in ["", "a*", "(?:x|)"] {
+        assert!(matches!(window.add_regex_str(pattern), Err(regex_ring::Error::EmptyMatch)), "{}", pattern);
+    }
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "parallel")]
+
+use regex_ring::{RingSearcher, Error};
+
+#[test]
+fn parallel_compile() {
+    let patterns: Vec<String> = (0 .. 50).map(|i| format!("id{}x", i)).collect();
+    let patterns: Vec<&str> = patterns.iter().map(|s| s.as_str()).collect();
+    let mut searcher = RingSearcher::new(16);
+    let ids = searcher.add_regex_strs_parallel(&patterns).unwrap();
+    assert_eq!(ids, (0 .. 50).collect::<Vec<_>>());
+
+    searcher.push_slice(b"id7x id42x ");
+    let found: Vec<usize> = searcher.matches().map(|(i, _)| i).collect();
+    assert_eq!(found, [7, 42]);
+}
+
+#[test]
+fn parallel_compile_invalid() {
+    let mut searcher = RingSearcher::new(16);
+    assert!(matches!(searcher.add_regex_strs_parallel(&["ok", "(bad"]), Err(Error::InvalidRegex)));
+    assert_eq!(searcher.add_regex_strs_parallel(&["ok"]).unwrap(), [0]);
+}
+
+#[test]
+fn parallel_partial() {
+    let mut searcher = RingSearcher::new(16);
+    let results = searcher.try_add_many_parallel(&["a+", "[z", "b+"]);
+    let ids: Vec<Option<usize>> = results.iter().map(|r| r.as_ref().ok().copied()).collect();
+    assert_eq!(ids, [Some(0), None, Some(1)]);
+}
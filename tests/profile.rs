@@ -0,0 +1,36 @@
+#![cfg(feature = "profile")]
+
+use regex_ring::{RingSearcher, Budget};
+
+#[test]
+fn profile() {
+    let mut searcher = RingSearcher::new(64);
+    let rare = searcher.add_regex_str("zzz").unwrap();
+    let busy = searcher.add_regex_str("[a-y]+z").unwrap();
+    searcher.push_slice(&b"abcdefghij".repeat(100));
+    assert_eq!(searcher.profile(rare).active_bytes, 0);
+    assert_eq!(searcher.profile(busy).active_bytes, 1000);
+    assert_eq!(searcher.slowest_searches().len(), 2);
+
+    searcher.clear_profiles();
+    assert_eq!(searcher.profile(busy), Default::default());
+}
+
+#[test]
+fn quarantine() {
+    let mut searcher = RingSearcher::new(64);
+    let word = searcher.add_regex_str("[a-z]+!").unwrap();
+    let bang = searcher.add_regex_str("!").unwrap();
+    searcher.set_budget(Some(Budget { active_run: Some(100), ..Budget::default() }));
+    searcher.push_slice(b"abc! ");
+    assert_eq!(searcher.matches().count(), 2);
+
+    searcher.push_slice(&b"x".repeat(200));
+    searcher.push_slice(b"! ");
+    assert_eq!(searcher.quarantined(), [word]);
+    assert!(searcher.is_paused(word));
+    assert_eq!(searcher.matches().map(|(id, _)| id).collect::<Vec<_>>(), [bang]);
+
+    searcher.resume(word);
+    assert!(searcher.quarantined().is_empty());
+}
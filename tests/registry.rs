@@ -0,0 +1,29 @@
+use regex_ring::{RingSearcher, PatternRegistry, Error};
+
+#[test]
+fn registry() {
+    let mut registry = PatternRegistry::new();
+    let abc = registry.add_str(r"ab+c").expect("failed to compile regex");
+    let digits = registry.add_str(r"[0-9]+").expect("failed to compile regex");
+    assert_eq!(registry.pattern(abc), Some("ab+c"));
+
+    let mut searchers: Vec<_> = (0 .. 2).map(|_| {
+        let mut searcher = RingSearcher::new(1024);
+        assert_eq!(searcher.add_pattern(&registry, digits).unwrap(), 0);
+        assert_eq!(searcher.add_pattern(&registry, abc).unwrap(), 1);
+        searcher
+    }).collect();
+
+    assert!(registry.remove(abc));
+    assert!(!registry.remove(abc));
+    assert_eq!(registry.len(), 1);
+    assert_eq!(registry.handles().collect::<Vec<_>>(), [digits]);
+    assert!(matches!(searchers[0].add_pattern(&registry, abc), Err(Error::UnknownPattern)));
+
+    // the searchers keep the removed pattern
+    for searcher in &mut searchers {
+        searcher.push_slice(b"abbc 42 ");
+        assert_eq!(searcher.matches().map(|(id, _)| id).collect::<Vec<_>>(), [1, 0]);
+        assert_eq!(searcher.pattern(1), Some("ab+c"));
+    }
+}
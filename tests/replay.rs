@@ -0,0 +1,48 @@
+#![cfg(feature = "replay")]
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, Match};
+use regex_ring::replay::{Recorder, replay};
+
+fn searcher() -> RingSearcher<DenseDFA<Vec<usize>, usize>> {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher
+}
+
+#[test]
+fn record_replay() {
+    let mut recorder = Recorder::new(searcher(), vec![]);
+    recorder.push_slice(b"ab a").unwrap();
+    recorder.push(b'b').unwrap();
+    recorder.push_slice(b"bb").unwrap();
+    assert_eq!(recorder.finish().unwrap(), [(0, Match::new(3, 7))]);
+    let (_, log) = recorder.into_inner();
+
+    assert_eq!(replay(&mut searcher(), &log[..]).unwrap(), None);
+
+    // a searcher with another pattern
+    let mut other = RingSearcher::new(1024);
+    other.add_regex_str(r"ab").expect("failed to compile regex");
+    let mismatch = replay(&mut other, &log[..]).unwrap().expect("no mismatch");
+    assert_eq!(mismatch.position, 7);
+    assert_eq!(mismatch.expected, []);
+    assert_eq!(mismatch.found, [(0, Match::new(3, 5))]);
+}
+
+#[test]
+fn corrupt_log() {
+    let mut recorder = Recorder::new(searcher(), vec![]);
+    recorder.push_slice(b"ab abbb").unwrap();
+    let (_, log) = recorder.into_inner();
+
+    // cut in the middle of the data
+    let err = replay(&mut searcher(), &log[.. 5]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // a huge length does not allocate it up front
+    let mut log = b"D".to_vec();
+    log.extend_from_slice(&[0xff; 8]);
+    log.extend_from_slice(b"\x7fab");
+    let err = replay(&mut searcher(), &log[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,65 @@
+#![cfg(all(feature = "yaml", feature = "toml"))]
+use regex_ring::rules::{RuleSet, Severity};
+
+const YAML: &str = r#"
+rules:
+  - name: greeting
+    regex: "hello"
+    flags: i
+    severity: low
+  - name: number
+    regex: "[0-9]+"
+    max_length: 8
+    actions: [log]
+"#;
+
+const TOML: &str = r#"
+[[rules]]
+name = "greeting"
+regex = "hello"
+flags = "i"
+severity = "low"
+
+[[rules]]
+name = "number"
+regex = "[0-9]+"
+max_length = 8
+actions = ["log"]
+"#;
+
+#[test]
+fn load() {
+    for rules in &[RuleSet::from_yaml(YAML).unwrap(), RuleSet::from_toml(TOML).unwrap()] {
+        assert_eq!(rules.rule(0).unwrap().severity, Some(Severity::Low));
+        assert_eq!(rules.rule(1).unwrap().actions, ["log"]);
+
+        let mut searcher = rules.build(1024).unwrap();
+        let mut found = vec![];
+        searcher.input_matches(b"HeLLo 12345678901", |id, match_, _| {
+            found.push((rules.rule(id).unwrap().name.clone(), match_.start(), match_.end));
+        });
+        assert_eq!(found, [
+            ("greeting".into(), Some(0), 5),
+            ("number".into(), None, 17),
+        ]);
+    }
+}
+
+#[test]
+fn invalid_flag() {
+    let rules = RuleSet::from_yaml("rules: [{name: x, regex: x, flags: q}]").unwrap();
+    assert!(rules.build(1024).is_err());
+}
+
+#[test]
+fn alert() {
+    let rules = RuleSet::from_yaml(r#"rules: [{name: quote, regex: '"[^"]*"', severity: high, tags: [a, b]}]"#).unwrap();
+    let mut searcher = rules.build(1024).unwrap();
+    let mut alerts = vec![];
+    searcher.input_matches(br#"say "hi""#, |id, match_, data| {
+        alerts.push(rules.alert("stdin", id, match_, data));
+    });
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].to_json(),
+        r#"{"rule":"quote","severity":"high","tags":["a","b"],"stream":"stdin","start":4,"end":8,"excerpt":"\"hi\"","truncated":false}"#);
+}
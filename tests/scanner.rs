@@ -0,0 +1,52 @@
+use regex_ring::{RingSearcher, Match};
+use regex_ring::scanner::{StreamScanner, MemmemScanner, MixedScanner};
+
+fn scan_all(scanner: &mut dyn StreamScanner, input: &[u8], chunk: usize) -> Vec<(usize, Match)> {
+    let mut found = vec![];
+    for piece in input.chunks(chunk) {
+        scanner.scan(piece, &mut |id, m| found.push((id, m)));
+    }
+    scanner.finish(&mut |id, m| found.push((id, m)));
+    found
+}
+
+#[test]
+fn memmem() {
+    let input = b"abcabcab-abcab";
+    for chunk in 1 ..= input.len() {
+        let found = scan_all(&mut MemmemScanner::new(b"cab"), input, chunk);
+        assert_eq!(found, [(0, Match::new(2, 5)), (0, Match::new(5, 8)), (0, Match::new(11, 14))], "chunk {}", chunk);
+    }
+
+    // needles overlapping themselves: a match across a seam moves the next one
+    let input = b"aaaaa-ababab";
+    for chunk in 1 ..= input.len() {
+        let found = scan_all(&mut MemmemScanner::new(b"aa"), input, chunk);
+        assert_eq!(found, [(0, Match::new(0, 2)), (0, Match::new(2, 4))], "chunk {}", chunk);
+        let found = scan_all(&mut MemmemScanner::new(b"abab"), input, chunk);
+        assert_eq!(found, [(0, Match::new(6, 10))], "chunk {}", chunk);
+    }
+}
+
+#[test]
+fn mixed() {
+    let mut searcher = RingSearcher::new(64);
+    searcher.add_regex_str("[0-9]+").unwrap();
+    let mut mixed = MixedScanner::new();
+    assert_eq!(mixed.add(searcher), 0);
+    assert_eq!(mixed.add(MemmemScanner::new(b"key")), 1);
+    assert_eq!(mixed.patterns(), 2);
+
+    let found = scan_all(&mut mixed, b"key 42 monkey", 4);
+    assert_eq!(found, [(1, Match::new(0, 3)), (0, Match::new(4, 6)), (1, Match::new(10, 13))]);
+}
+
+#[cfg(feature = "aho-corasick")]
+#[test]
+fn aho_corasick() {
+    use regex_ring::scanner::AhoCorasickScanner;
+
+    let mut scanner = AhoCorasickScanner::new(["he", "she", "hers"]).unwrap();
+    let found = scan_all(&mut scanner, b"ushers", 2);
+    assert_eq!(found, [(1, Match::new(1, 4)), (0, Match::new(2, 4)), (2, Match::new(2, 6))]);
+}
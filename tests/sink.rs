@@ -0,0 +1,60 @@
+use std::io;
+use std::sync::mpsc;
+
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, Match};
+use regex_ring::input::ReaderSource;
+use regex_ring::sink::{MatchSink, TextSink, JsonLinesSink, ChannelSink, NullSink, scan_to_sink};
+
+fn scan(input: &'static [u8], provenance: Option<&str>, sink: &mut dyn MatchSink) -> io::Result<()> {
+    let mut searcher: RingSearcher<DenseDFA<Vec<usize>, usize>> = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+").unwrap();
+    searcher.add_regex_str(r#""[a-z]+""#).unwrap();
+    let mut source = ReaderSource::new(input, provenance.map(String::from));
+    scan_to_sink(&mut source, &mut searcher, sink)
+}
+
+#[test]
+fn text() {
+    let mut sink = TextSink::new(vec![]);
+    scan(b"xabb ab", Some("log"), &mut sink).unwrap();
+    assert_eq!(String::from_utf8(sink.into_inner()).unwrap(),
+        "log #0 Match { start: Exact(1), end: 4 }\n> abb\nlog #0 Match { start: Exact(5), end: 7 }\n> ab\n");
+
+    let mut sink = TextSink::new(vec![]);
+    sink.set_only_matching(true);
+    scan(b"xabb ab", Some("log"), &mut sink).unwrap();
+    assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), "log abb\nlog ab\n");
+}
+
+#[test]
+fn json_lines() {
+    let mut sink = JsonLinesSink::new(vec![]);
+    scan(b"ab \"quoted\"", None, &mut sink).unwrap();
+    assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), concat!(
+        r#"{"id":0,"start":0,"exact":true,"end":2,"data":"ab"}"#, "\n",
+        r#"{"id":1,"start":3,"exact":true,"end":11,"data":"\"quoted\""}"#, "\n",
+    ));
+}
+
+#[test]
+fn channel() {
+    let (sender, receiver) = mpsc::channel();
+    scan(b"abab", Some("in"), &mut ChannelSink::new(sender)).unwrap();
+    let received: Vec<_> = receiver.iter().map(|(provenance, record)| (provenance, record.match_(), record.data)).collect();
+    assert_eq!(received, [
+        (Some("in".into()), Match::new(0, 2), b"ab".to_vec()),
+        (Some("in".into()), Match::new(2, 4), b"ab".to_vec()),
+    ]);
+
+    let (sender, receiver) = mpsc::channel();
+    drop(receiver);
+    let err = scan(b"ab", None, &mut ChannelSink::new(sender)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn boxed_null() {
+    let mut sink: Box<dyn MatchSink> = Box::new(NullSink);
+    scan(b"abab", None, &mut sink).unwrap();
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "snort")]
+use regex_ring::snort::SnortRules;
+
+const RULES: &str = r#"
+# web rules
+alert tcp any any -> any 80 (msg:"admin access"; content:"GET"; depth:3; \
+    content:"/admin|3F|"; within:16; nocase; sid:1;)
+alert tcp any any -> any any (msg:"semi; colon"; pcre:"/user=[a-z]+/i"; sid:2;)
+"#;
+
+#[test]
+fn parse() {
+    let rules = SnortRules::parse(RULES).unwrap();
+    assert_eq!(rules.rules.len(), 2);
+    assert_eq!(rules.rules[1].msg.as_deref(), Some("semi; colon"));
+    let (rule, content) = rules.content(1).unwrap();
+    assert_eq!(rule.sid, Some(1));
+    assert_eq!((content.regex.as_str(), content.flags.as_str()), (r"\x2Fadmin\x3F", "i"));
+    assert_eq!(content.within, Some(16));
+    assert_eq!(rules.content(2).unwrap().1.flags, "i");
+}
+
+#[test]
+fn matches() {
+    let rules = SnortRules::parse(RULES).unwrap();
+    let mut searcher = rules.build(1024).unwrap();
+    let mut found = vec![];
+    searcher.input_matches(b"GET /ADMIN? USER=bob GET /admin?", |id, match_, _| {
+        if rules.accept(id, match_) {
+            found.push((id, match_.end));
+        }
+    });
+    // the second GET is beyond the depth
+    assert_eq!(found, [(0, 3), (1, 11), (2, 20), (1, 32)]);
+    let sids: Vec<_> = rules.matched_rules(found.iter().map(|&(id, _)| id)).iter().map(|rule| rule.sid).collect();
+    assert_eq!(sids, [Some(1), Some(2)]);
+}
+
+#[test]
+fn invalid() {
+    assert!(SnortRules::parse(r#"alert tcp any any -> any any (content:!"x";)"#).is_err());
+    assert!(SnortRules::parse(r#"alert tcp any any -> any any (content:"|4|";)"#).is_err());
+    assert!(SnortRules::parse(r#"alert tcp any any -> any any (nocase;)"#).is_err());
+    assert!(SnortRules::parse(r#"alert tcp any any -> any any (pcre:"/x/R";)"#).is_err());
+}
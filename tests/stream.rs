@@ -0,0 +1,72 @@
+#![cfg(feature = "futures")]
+use futures::executor::block_on;
+use futures::io::Cursor;
+use futures::stream::TryStreamExt;
+use regex_automata::DenseDFA;
+use regex_ring::{RingSearcher, MatchRecord, StartBound};
+use regex_ring::stream::MatchStream;
+
+#[test]
+fn stream() {
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    let matches = MatchStream::new(Cursor::new(b"ab xabbb".to_vec()), searcher);
+    let records: Vec<_> = block_on(matches.try_collect()).unwrap();
+    assert_eq!(records, [
+        MatchRecord { id: 0, start: StartBound::Exact(0), end: 2, data: b"ab".to_vec(), fingerprint: None },
+        MatchRecord { id: 0, start: StartBound::Exact(4), end: 8, data: b"abbb".to_vec(), fingerprint: None },
+    ]);
+}
+
+#[test]
+fn fingerprints() {
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+    searcher.set_fingerprints(true);
+
+    let matches = MatchStream::new(Cursor::new(b"abb xabb".to_vec()), searcher);
+    let records: Vec<MatchRecord> = block_on(matches.try_collect()).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].fingerprint, Some(regex_ring::fingerprint(b"abb")));
+    assert_eq!(records[0].fingerprint, records[1].fingerprint);
+}
+
+#[test]
+fn scan_async_reader() {
+    use std::cell::RefCell;
+
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    let records = RefCell::new(vec![]);
+    block_on(searcher.scan_async_reader(Cursor::new(b"ab xabbb".to_vec()), |record| {
+        let records = &records;
+        async move { records.borrow_mut().push((record.start.exact(), record.end, record.data)) }
+    })).unwrap();
+    assert_eq!(records.into_inner(), [(Some(0), 2, b"ab".to_vec()), (Some(4), 8, b"abbb".to_vec())]);
+}
+
+#[test]
+fn sink() {
+    use bytes::Bytes;
+    use futures::sink::SinkExt;
+    use futures::stream;
+    use regex_ring::stream::SearchSink;
+
+    let mut searcher = RingSearcher::<DenseDFA<Vec<usize>, usize>>::new(1024);
+    searcher.add_regex_str(r"ab+").expect("failed to compile regex");
+
+    let mut records = vec![];
+    let mut sink = SearchSink::new(searcher, |record: MatchRecord| records.push((record.start.exact(), record.end)));
+    let mut chunks = stream::iter(vec![Ok(Bytes::from_static(b"ab a")), Ok(Bytes::from_static(b"bb"))]);
+    block_on(async {
+        sink.send_all(&mut chunks).await?;
+        SinkExt::<u8>::send(&mut sink, b'b').await?;
+        SinkExt::<u8>::close(&mut sink).await?;
+        // the final matches are only reported once
+        SinkExt::<u8>::close(&mut sink).await
+    }).unwrap();
+    drop(sink);
+    assert_eq!(records, [(Some(0), 2), (Some(3), 7)]);
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "test-util")]
+use regex_ring::RingSearcher;
+use regex_ring::test_util::{assert_chunking_invariant, random_chunk_sizes};
+
+#[test]
+fn chunking_invariant() {
+    let mut searcher = RingSearcher::new(1024);
+    searcher.add_regex_str(r"ab+c").expect("failed to compile regex");
+    searcher.add_regex_str(r"[0-9]+").expect("failed to compile regex");
+
+    let input: Vec<u8> = b"xx abbc 123 abc ab4c 99".iter().cycle().take(10_000).cloned().collect();
+    assert_chunking_invariant(&searcher, &input, 20, 7);
+}
+
+#[test]
+fn chunk_sizes() {
+    let sizes = random_chunk_sizes(1000, 3);
+    assert!(sizes.iter().sum::<usize>() >= 1000);
+    assert!(sizes.iter().all(|&size| size > 0));
+    assert_eq!(sizes, random_chunk_sizes(1000, 3));
+}
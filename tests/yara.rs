@@ -0,0 +1,53 @@
+#![cfg(feature = "yara")]
+use regex_ring::yara::{YaraRules, StringKind};
+
+const RULES: &str = r#"
+import "pe"
+
+rule Secrets : credentials {
+    meta:
+        author = "someone { with braces }"
+    strings:
+        $text = "Secret" nocase
+        $hex = { DE AD ?? EF }
+        $re = /pass(word)?=\w+/
+    condition:
+        any of them
+}
+
+// a comment
+private rule Wide {
+    strings:
+        $w = "ab" ascii wide
+    condition:
+        $w
+}
+"#;
+
+#[test]
+fn parse() {
+    let rules = YaraRules::parse(RULES).unwrap();
+    assert_eq!(rules.rules.len(), 2);
+    assert_eq!(rules.rules[0].tags, ["credentials"]);
+    let (rule, string) = rules.string(1).unwrap();
+    assert_eq!((rule.name.as_str(), string.id.as_str(), string.kind), ("Secrets", "$hex", StringKind::Hex));
+    assert_eq!(rules.string(3).unwrap().1.regex, r"ab|a\x00b\x00");
+    assert!(rules.string(4).is_none());
+}
+
+#[test]
+fn matches() {
+    let rules = YaraRules::parse(RULES).unwrap();
+    let mut searcher = rules.build(1024).unwrap();
+    let mut found = vec![];
+    searcher.input_matches(b"xx SECRET a\x00b\x00 \xde\xad\x00\xef ", |id, _, _| found.push(id));
+    assert_eq!(found, [0, 3, 1]);
+    assert_eq!(rules.matched_rules(found), ["Secrets", "Wide"]);
+}
+
+#[test]
+fn unsupported() {
+    assert!(YaraRules::parse(r#"rule X { strings: $a = "a" xor condition: $a }"#).is_err());
+    assert!(YaraRules::parse(r#"rule X { strings: $a = { DE AD"#).is_err());
+    assert!(YaraRules::parse(r#"rule X { strings: $a = { 0 } condition: $a }"#).is_err());
+}